@@ -0,0 +1,143 @@
+//! Button configuration: the GPIO pin mapping, polarity and timings.
+//!
+//! The configuration is read from a YAML file, the path of which can be set
+//! with the `PEACH_BUTTONS_CONFIG` environment variable. When the file does
+//! not exist, the default configuration (matching the PeachCloud hat) is used.
+
+use std::{env, fs, io::ErrorKind, time::Duration};
+
+use log::info;
+use serde::Deserialize;
+use snafu::ResultExt;
+
+use crate::error::{ButtonError, ParseConfig, ReadConfig};
+
+/// Path of the configuration file, unless set by `PEACH_BUTTONS_CONFIG`.
+pub const CONFIG_PATH: &str = "/etc/peach-buttons/config.yml";
+
+/// A button connected to a GPIO line.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct Button {
+    /// Offset of the GPIO line on the chip.
+    pub pin: u32,
+    /// Code sent to subscribers when the button is pressed.
+    pub code: u8,
+    /// Name of the button, used as the consumer label of the GPIO line.
+    pub name: String,
+}
+
+impl Button {
+    fn new(pin: u32, code: u8, name: &str) -> Button {
+        Button {
+            pin,
+            code,
+            name: name.to_string(),
+        }
+    }
+}
+
+/// Timings used to debounce button presses and classify them.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Timing {
+    /// How long a line must hold a new level before it is accepted.
+    pub debounce: Duration,
+    /// How long a button must be held for a long press.
+    pub long_press: Duration,
+    /// Interval between repeat events while a button is held after a long
+    /// press (`None` to disable repeats).
+    pub repeat: Option<Duration>,
+}
+
+/// The configuration of the buttons.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct Config {
+    /// Whether to read button presses from GPIO lines.
+    pub gpio: bool,
+    /// Path of the GPIO character device.
+    pub chip: String,
+    /// Whether a line reads low (`0`) while its button is pressed.
+    pub active_low: bool,
+    pub debounce_ms: u64,
+    pub long_press_ms: u64,
+    /// `0` disables hold-repeat.
+    pub repeat_ms: u64,
+    pub buttons: Vec<Button>,
+    /// Path of a TTY or evdev device to read virtual button presses from.
+    pub keyboard: Option<String>,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            gpio: true,
+            chip: "/dev/gpiochip0".to_string(),
+            active_low: false,
+            debounce_ms: 20,
+            long_press_ms: 1000,
+            repeat_ms: 200,
+            buttons: vec![
+                Button::new(4, 0, "center"),
+                Button::new(27, 1, "left"),
+                Button::new(23, 2, "right"),
+                Button::new(17, 3, "up"),
+                Button::new(22, 4, "down"),
+                Button::new(5, 5, "#5"),
+                Button::new(6, 6, "#6"),
+            ],
+            keyboard: None,
+        }
+    }
+}
+
+impl Config {
+    /// Load the configuration file, falling back to the default configuration
+    /// if it does not exist.
+    pub fn load() -> Result<Config, ButtonError> {
+        let path = env::var("PEACH_BUTTONS_CONFIG").unwrap_or_else(|_| CONFIG_PATH.to_string());
+        match fs::read_to_string(&path) {
+            Ok(contents) => Config::parse(&contents).context(ParseConfig { path }),
+            Err(ref err) if err.kind() == ErrorKind::NotFound => {
+                info!("No config file at {}, using the default config.", path);
+                Ok(Config::default())
+            }
+            Err(err) => Err(err).context(ReadConfig { path }),
+        }
+    }
+
+    /// Parse a configuration from YAML. Missing values take their defaults.
+    pub fn parse(contents: &str) -> Result<Config, serde_yaml::Error> {
+        serde_yaml::from_str(contents)
+    }
+
+    /// The timings to apply to every button.
+    pub fn timing(&self) -> Timing {
+        Timing {
+            debounce: Duration::from_millis(self.debounce_ms),
+            long_press: Duration::from_millis(self.long_press_ms),
+            repeat: match self.repeat_ms {
+                0 => None,
+                ms => Some(Duration::from_millis(ms)),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_partial_config() {
+        let config = Config::parse(
+            "active_low: true\n\
+             repeat_ms: 0\n\
+             buttons:\n  - pin: 12\n    code: 0\n    name: select\n",
+        )
+        .unwrap();
+        assert!(config.active_low);
+        assert_eq!(config.debounce_ms, 20);
+        assert_eq!(config.timing().repeat, None);
+        assert_eq!(config.buttons, vec![Button::new(12, 0, "select")]);
+    }
+}
@@ -10,6 +10,30 @@ pub type BoxError = Box<dyn error::Error>;
 pub enum ButtonError {
     #[snafu(display("Invalid parameters. Subscription rejected"))]
     RejectSubscription,
+
+    #[snafu(display("Failed to read config file {}: {}", path, source))]
+    ReadConfig {
+        source: std::io::Error,
+        path: String,
+    },
+
+    #[snafu(display("Failed to parse config file {}: {}", path, source))]
+    ParseConfig {
+        source: serde_yaml::Error,
+        path: String,
+    },
+
+    #[snafu(display("{}", msg))]
+    GpioError { msg: String },
+
+    #[snafu(display("{}", msg))]
+    KeyboardError { msg: String },
+
+    #[snafu(display("{}", msg))]
+    PollError { msg: String },
+
+    #[snafu(display("Unknown button code: {}", code))]
+    UnknownButton { code: u8 },
 }
 
 impl From<ButtonError> for Error {
@@ -20,6 +44,11 @@ impl From<ButtonError> for Error {
                 message: "Invalid parameters. Subscription request rejected".to_string(),
                 data: None,
             },
+            err => Error {
+                code: ErrorCode::ServerError(-32000),
+                message: err.to_string(),
+                data: None,
+            },
         }
     }
 }
@@ -1,63 +1,296 @@
-use std::{cell::Cell, process, thread, time::Duration};
+//! Button input from GPIO edge events.
+//!
+//! The kernel reports an event each time a button line rises or falls, with
+//! a timestamp. The events of all lines are read by a single thread, which
+//! debounces them on their timestamps and classifies the presses of each
+//! button. Between events the thread sleeps, waking only when a press is due
+//! to settle, become a long press or repeat.
 
-use crossbeam_channel::{tick, Sender};
-use gpio_cdev::{Chip, LineRequestFlags};
+use std::{
+    collections::VecDeque,
+    os::unix::io::AsRawFd,
+    process, thread,
+    time::{Duration, Instant},
+};
+
+use crossbeam_channel::Sender;
+use gpio_cdev::{Chip, EventRequestFlags, EventType, LineEventHandle, LineRequestFlags};
 use log::{debug, error, info};
+use nix::poll::{poll, EventFlags, PollFd};
 
-// initialize gpio pin and poll for state
-// send button code to "subscribe_buttons" rpc method for sink notification
-pub fn interrupt_handler(pin: u32, button_code: u8, button_name: String, s: Sender<u8>) {
-    thread::spawn(move || {
+use crate::config::{Button, Timing};
+use crate::error::ButtonError;
+use crate::press::{Press, Tracker};
+
+/// A button line rising (pressed) or falling (released).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Edge {
+    /// Index of the button in the configuration.
+    pub button: usize,
+    pub pressed: bool,
+    pub time: Instant,
+}
+
+/// The result of waiting for an edge.
+#[derive(Debug, PartialEq)]
+pub enum Next {
+    Edge(Edge),
+    /// The deadline passed without an edge, at the given time.
+    Timeout(Instant),
+    /// No more edges will be received (the device has been removed).
+    Closed,
+}
+
+/// A source of button edges.
+pub trait EdgeSource {
+    /// Wait for the next edge, or until the deadline (if any) has passed.
+    fn next(&mut self, deadline: Option<Instant>) -> Result<Next, ButtonError>;
+}
+
+/// Edge events of the GPIO lines of the buttons.
+pub struct GpioEdges {
+    handles: Vec<LineEventHandle>,
+    pending: VecDeque<Edge>,
+}
+
+impl GpioEdges {
+    /// Request both-edge events for the line of each button.
+    pub fn open(
+        chip: &str,
+        buttons: &[Button],
+        active_low: bool,
+    ) -> Result<GpioEdges, ButtonError> {
         debug!("Creating handle for GPIO chip.");
-        let mut chip = Chip::new("/dev/gpiochip0").unwrap_or_else(|err| {
-            error!("Failed to create handle for GPIO chip: {}", err);
-            process::exit(1);
-        });
+        let mut chip = Chip::new(chip).map_err(|err| ButtonError::GpioError {
+            msg: format!("Failed to create handle for GPIO chip {}: {}", chip, err),
+        })?;
 
-        debug!("Creating handle for GPIO line at given pin.");
-        let input = chip.get_line(pin).unwrap_or_else(|err| {
-            error!(
-                "Failed to create handle for GPIO line at pin {}: {}",
-                pin, err
+        // the kernel inverts the value of active-low lines, so that a
+        // pressed button always rises
+        let mut flags = LineRequestFlags::INPUT;
+        if active_low {
+            flags |= LineRequestFlags::ACTIVE_LOW;
+        }
+
+        let mut handles = Vec::new();
+        for button in buttons {
+            debug!(
+                "Requesting edge events for GPIO line at pin {}.",
+                button.pin
             );
-            process::exit(1);
-        });
-
-        let line_handle = input
-            .request(LineRequestFlags::INPUT, 0, &button_name)
-            .unwrap_or_else(|err| {
-                error!("Failed to gain kernel access for pin {}: {}", pin, err);
-                process::exit(1);
-            });
-
-        let ticker = tick(Duration::from_millis(2));
-        let mut counter = Cell::new(0);
-        let mut switch = Cell::new(0);
-
-        info!(
-            "Initating polling loop for {} button on pin {}",
-            button_name, pin
-        );
+            let handle = chip
+                .get_line(button.pin)
+                .and_then(|line| line.events(flags, EventRequestFlags::BOTH_EDGES, &button.name))
+                .map_err(|err| ButtonError::GpioError {
+                    msg: format!("Failed to request events for pin {}: {}", button.pin, err),
+                })?;
+            handles.push(handle);
+        }
+
+        Ok(GpioEdges {
+            handles,
+            pending: VecDeque::new(),
+        })
+    }
+}
+
+impl EdgeSource for GpioEdges {
+    fn next(&mut self, deadline: Option<Instant>) -> Result<Next, ButtonError> {
         loop {
-            ticker.recv().unwrap();
-            let value = line_handle
-                .get_value()
-                .expect("Failed to get current state of this line from the kernel");
-            match value {
-                0 => counter.set(0),
-                1 => *counter.get_mut() += 1,
-                _ => (),
+            if let Some(edge) = self.pending.pop_front() {
+                return Ok(Next::Edge(edge));
+            }
+
+            let mut fds: Vec<PollFd> = self
+                .handles
+                .iter()
+                .map(|handle| PollFd::new(handle.as_raw_fd(), EventFlags::POLLIN))
+                .collect();
+            let ready = poll_until(&mut fds, deadline)?;
+            let now = Instant::now();
+            if ready == 0 {
+                return Ok(Next::Timeout(now));
             }
-            if counter.get() == 10 {
-                if switch.get() == 0 {
-                    *switch.get_mut() += 1
-                } else {
-                    debug!("Sending button code: {}", button_code);
-                    s.send(button_code).unwrap_or_else(|err| {
-                        error!("Failed to send button_code to publisher: {}", err);
-                    });
+
+            let mut events = Vec::new();
+            for (button, fd) in fds.iter().enumerate() {
+                if fd.revents().is_some_and(|r| r.contains(EventFlags::POLLIN)) {
+                    let event =
+                        self.handles[button]
+                            .get_event()
+                            .map_err(|err| ButtonError::GpioError {
+                                msg: format!("Failed to read GPIO event: {}", err),
+                            })?;
+                    events.push((button, event));
                 }
             }
+
+            // kernel timestamps are converted relative to the newest event,
+            // which has only just been read
+            events.sort_by_key(|(_, event)| event.timestamp());
+            let newest = events.last().map_or(0, |(_, event)| event.timestamp());
+            for (button, event) in events {
+                let age = Duration::from_nanos(newest - event.timestamp());
+                self.pending.push_back(Edge {
+                    button,
+                    pressed: match event.event_type() {
+                        EventType::RisingEdge => true,
+                        EventType::FallingEdge => false,
+                    },
+                    time: now - age,
+                });
+            }
+        }
+    }
+}
+
+/// Wait until one of the file descriptors is ready, or the deadline (if any)
+/// has passed, returning the number which are ready.
+pub fn poll_until(fds: &mut [PollFd], deadline: Option<Instant>) -> Result<i32, ButtonError> {
+    // round up, so as not to wake before the deadline
+    let timeout = match deadline {
+        Some(deadline) => {
+            let remaining = deadline
+                .checked_duration_since(Instant::now())
+                .unwrap_or_default();
+            (remaining.as_millis() as i32).saturating_add(1)
+        }
+        None => -1,
+    };
+    poll(fds, timeout).map_err(|err| ButtonError::PollError {
+        msg: format!("Failed to poll for input: {}", err),
+    })
+}
+
+/// Debounce the edges of the buttons with the given codes (indexed as the
+/// edges), sending their presses until the source is closed.
+pub fn listen<S: EdgeSource>(
+    source: &mut S,
+    codes: &[u8],
+    timing: Timing,
+    s: &Sender<Press>,
+) -> Result<(), ButtonError> {
+    let start = Instant::now();
+    let mut trackers: Vec<Tracker> = codes.iter().map(|_| Tracker::new(timing, start)).collect();
+
+    loop {
+        let deadline = trackers.iter().filter_map(Tracker::deadline).min();
+        // the levels to update the trackers with: (button, level, time)
+        let updates: Vec<(usize, bool, Instant)> = match source.next(deadline)? {
+            // settle the previous level of the button before taking the new
+            // one, in case its deadline passed while waiting
+            Next::Edge(edge) => vec![
+                (edge.button, trackers[edge.button].level(), edge.time),
+                (edge.button, edge.pressed, edge.time),
+            ],
+            Next::Timeout(now) => trackers
+                .iter()
+                .enumerate()
+                .filter(|(_, tracker)| tracker.deadline().is_some_and(|d| d <= now))
+                .map(|(button, tracker)| (button, tracker.level(), now))
+                .collect(),
+            Next::Closed => return Ok(()),
+        };
+
+        for (button, level, time) in updates {
+            if let Some((kind, duration)) = trackers[button].update(level, time) {
+                debug!("Sending {:?} press of button code: {}", kind, codes[button]);
+                let press = Press {
+                    code: codes[button],
+                    kind,
+                    duration,
+                };
+                s.send(press).unwrap_or_else(|err| {
+                    error!("Failed to send button press to publisher: {}", err);
+                });
+            }
+        }
+    }
+}
+
+// listen for edges in a thread of their own
+// send button events to "subscribe_buttons" rpc method for sink notification
+pub fn interrupt_handler<S>(mut source: S, buttons: Vec<Button>, timing: Timing, s: Sender<Press>)
+where
+    S: EdgeSource + Send + 'static,
+{
+    thread::spawn(move || {
+        info!("Listening for edge events of {} buttons.", buttons.len());
+        let codes: Vec<u8> = buttons.iter().map(|button| button.code).collect();
+        if let Err(err) = listen(&mut source, &codes, timing, &s) {
+            error!("{}", err);
+            process::exit(1);
         }
     });
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crossbeam_channel::unbounded;
+
+    use crate::press::PressKind;
+
+    // replays edges, timing out at each deadline before the next edge
+    struct Script {
+        edges: VecDeque<Edge>,
+    }
+
+    impl EdgeSource for Script {
+        fn next(&mut self, deadline: Option<Instant>) -> Result<Next, ButtonError> {
+            Ok(match (self.edges.front(), deadline) {
+                (Some(edge), Some(deadline)) if deadline < edge.time => Next::Timeout(deadline),
+                (Some(_), _) => Next::Edge(self.edges.pop_front().unwrap()),
+                (None, Some(deadline)) => Next::Timeout(deadline),
+                (None, None) => Next::Closed,
+            })
+        }
+    }
+
+    #[test]
+    fn edges_are_debounced_per_button() {
+        let start = Instant::now() + Duration::from_millis(10);
+        // (button, pressed, ms)
+        let edges = [
+            (0, true, 0),
+            (0, false, 1),
+            (0, true, 2),
+            (1, true, 10),
+            (0, false, 102),
+            (0, true, 103),
+            (0, false, 104),
+            (1, false, 1110),
+        ];
+        let mut source = Script {
+            edges: edges
+                .iter()
+                .map(|(button, pressed, ms)| Edge {
+                    button: *button,
+                    pressed: *pressed,
+                    time: start + Duration::from_millis(*ms),
+                })
+                .collect(),
+        };
+        let timing = Timing {
+            debounce: Duration::from_millis(20),
+            long_press: Duration::from_millis(1000),
+            repeat: Some(Duration::from_millis(200)),
+        };
+        let (s, r) = unbounded();
+
+        listen(&mut source, &[0, 3], timing, &s).unwrap();
+
+        let presses: Vec<(u8, PressKind, Duration)> = r
+            .try_iter()
+            .map(|press| (press.code, press.kind, press.duration))
+            .collect();
+        assert_eq!(
+            presses,
+            vec![
+                (0, PressKind::Short, Duration::from_millis(102)),
+                (3, PressKind::Long, Duration::from_millis(1000)),
+            ]
+        );
+    }
+}
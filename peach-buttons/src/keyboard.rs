@@ -0,0 +1,374 @@
+//! Virtual button input from a keyboard.
+//!
+//! Keys are read either from a TTY or from an evdev device (any path under
+//! `/dev/input/`), for driving the menu without the GPIO hat. The arrow keys
+//! act as the directional buttons, `Enter` or `Space` as the center button,
+//! and `A` and `B` as the A and B buttons. Each key publishes the code of a
+//! configured button, by its position in the configuration:
+//!
+//! | Key | Button | Default code |
+//! | --- | --- | --- |
+//! | `Enter`, `Space` | 1st (`center`) | 0 |
+//! | `Left` | 2nd (`left`) | 1 |
+//! | `Right` | 3rd (`right`) | 2 |
+//! | `Up` | 4th (`up`) | 3 |
+//! | `Down` | 5th (`down`) | 4 |
+//! | `A` | 6th (`#5`) | 5 |
+//! | `B` | 7th (`#6`) | 6 |
+//!
+//! Keys without a configured button are ignored.
+//!
+//! Key presses and releases read from an evdev device are debounced and
+//! classified like GPIO edges, so that holding a key results in long-press
+//! and repeat events. A TTY only reports keys as they are typed, so each key
+//! read from a TTY is published as a short press.
+
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, Read},
+    mem,
+    os::unix::io::AsRawFd,
+    process, thread,
+    time::{Duration, Instant},
+};
+
+use crossbeam_channel::Sender;
+use log::{debug, error, info, warn};
+use nix::libc;
+use nix::poll::{EventFlags, PollFd};
+use nix::sys::termios::{self, LocalFlags, SetArg};
+
+use crate::config::Timing;
+use crate::error::ButtonError;
+use crate::interrupt::{listen, poll_until, Edge, EdgeSource, Next};
+use crate::press::{Press, PressKind};
+
+// linux input event type and key codes (see linux/input-event-codes.h)
+const EV_KEY: u16 = 1;
+const KEY_ENTER: u16 = 28;
+const KEY_A: u16 = 30;
+const KEY_B: u16 = 48;
+const KEY_SPACE: u16 = 57;
+const KEY_UP: u16 = 103;
+const KEY_LEFT: u16 = 105;
+const KEY_RIGHT: u16 = 106;
+const KEY_DOWN: u16 = 108;
+
+// ioctl setting the clock of input event timestamps (see linux/input.h)
+const EVIOCSCLOCKID: libc::c_ulong = 0x4004_45a0;
+
+/// Read virtual button presses from the keyboard of the TTY or evdev device
+/// at the given path, in a thread of their own. `codes` are the codes of the
+/// configured buttons, in order.
+pub fn keyboard_handler(
+    path: String,
+    codes: Vec<u8>,
+    timing: Timing,
+    s: Sender<Press>,
+) -> Result<(), ButtonError> {
+    let open_err = |err| ButtonError::KeyboardError {
+        msg: format!("Failed to open keyboard {}: {}", path, err),
+    };
+    if path.starts_with("/dev/input/") {
+        let mut keys = EvdevKeys::open(&path, codes.len()).map_err(open_err)?;
+        thread::spawn(move || {
+            info!("Listening for key events from {}.", path);
+            match listen(&mut keys, &codes, timing, &s) {
+                Ok(()) => warn!("Keyboard {} was removed, no longer listening.", path),
+                Err(err) => {
+                    error!("{}", err);
+                    process::exit(1);
+                }
+            }
+        });
+    } else {
+        let tty = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&path)
+            .map_err(open_err)?;
+        raw_mode(&tty).map_err(|err| ButtonError::KeyboardError {
+            msg: format!("Failed to configure TTY {}: {}", path, err),
+        })?;
+        thread::spawn(move || {
+            info!("Listening for keys typed on {}.", path);
+            if let Err(err) = read_tty(tty, &codes, &s) {
+                error!("Failed to read from TTY {}: {}", path, err);
+                process::exit(1);
+            }
+        });
+    }
+    Ok(())
+}
+
+// read keys as they are typed, without echoing them (signals such as ctrl-c
+// are left enabled)
+fn raw_mode(tty: &File) -> nix::Result<()> {
+    let mut attrs = termios::tcgetattr(tty.as_raw_fd())?;
+    attrs
+        .local_flags
+        .remove(LocalFlags::ICANON | LocalFlags::ECHO);
+    termios::tcsetattr(tty.as_raw_fd(), SetArg::TCSANOW, &attrs)
+}
+
+fn read_tty(mut tty: File, codes: &[u8], s: &Sender<Press>) -> std::io::Result<()> {
+    let mut buf = [0; 64];
+    loop {
+        let len = tty.read(&mut buf)?;
+        if len == 0 {
+            return Ok(());
+        }
+        // keys without a configured button are ignored
+        for button in tty_buttons(&buf[..len]) {
+            let code = match codes.get(button) {
+                Some(code) => *code,
+                None => continue,
+            };
+            debug!("Sending key press of button code: {}", code);
+            let press = Press {
+                code,
+                kind: PressKind::Short,
+                duration: Duration::from_millis(0),
+            };
+            s.send(press).unwrap_or_else(|err| {
+                error!("Failed to send button press to publisher: {}", err);
+            });
+        }
+    }
+}
+
+// the button indexes of the keys typed on a TTY (arrow keys are sent as the
+// escape sequences `ESC [ A` to `ESC [ D`)
+fn tty_buttons(bytes: &[u8]) -> Vec<usize> {
+    let mut buttons = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let (button, len) = match &bytes[i..] {
+            [0x1b, b'[', b'A', ..] => (Some(3), 3),
+            [0x1b, b'[', b'B', ..] => (Some(4), 3),
+            [0x1b, b'[', b'C', ..] => (Some(2), 3),
+            [0x1b, b'[', b'D', ..] => (Some(1), 3),
+            [b'\r', ..] | [b'\n', ..] | [b' ', ..] => (Some(0), 1),
+            [b'a', ..] | [b'A', ..] => (Some(5), 1),
+            [b'b', ..] | [b'B', ..] => (Some(6), 1),
+            _ => (None, 1),
+        };
+        buttons.extend(button);
+        i += len;
+    }
+    buttons
+}
+
+/// A key pressed or released on an evdev device.
+#[derive(Debug, PartialEq)]
+struct KeyEvent {
+    key: u16,
+    pressed: bool,
+    // the timestamp of the event, on the clock of the device
+    time: Duration,
+}
+
+// the size of an input_event: a timeval (the size of which depends on the
+// architecture), followed by the type, code and value
+fn event_len() -> usize {
+    mem::size_of::<libc::timeval>() + 8
+}
+
+// parse an input_event, returning `None` for events other than key presses
+// and releases (values are 0 for a release, 1 for a press and 2 for the
+// keyboard's own autorepeat, which is ignored)
+fn parse_event(buf: &[u8]) -> Option<KeyEvent> {
+    let time_len = mem::size_of::<libc::timeval>();
+    if buf.len() < event_len() {
+        return None;
+    }
+    // the seconds and microseconds are each a native long
+    let long = |offset: usize| -> u64 {
+        let bytes = &buf[offset..offset + time_len / 2];
+        if bytes.len() == 8 {
+            let mut long = [0; 8];
+            long.copy_from_slice(bytes);
+            u64::from_ne_bytes(long)
+        } else {
+            let mut long = [0; 4];
+            long.copy_from_slice(bytes);
+            u64::from(u32::from_ne_bytes(long))
+        }
+    };
+    let time = Duration::from_secs(long(0)) + Duration::from_micros(long(time_len / 2));
+
+    let kind = u16::from_ne_bytes([buf[time_len], buf[time_len + 1]]);
+    let key = u16::from_ne_bytes([buf[time_len + 2], buf[time_len + 3]]);
+    let mut value = [0; 4];
+    value.copy_from_slice(&buf[time_len + 4..time_len + 8]);
+    let value = i32::from_ne_bytes(value);
+
+    if kind != EV_KEY || value > 1 {
+        return None;
+    }
+    Some(KeyEvent {
+        key,
+        pressed: value == 1,
+        time,
+    })
+}
+
+// the current time on the monotonic clock
+fn monotonic_now() -> Duration {
+    let mut now = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    unsafe {
+        libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut now);
+    }
+    Duration::new(now.tv_sec as u64, now.tv_nsec as u32)
+}
+
+/// Key presses and releases read from an evdev device.
+struct EvdevKeys {
+    device: File,
+    // the number of configured buttons; keys of other buttons are ignored
+    buttons: usize,
+    // whether event timestamps are on the monotonic clock
+    monotonic: bool,
+}
+
+impl EvdevKeys {
+    fn open(path: &str, buttons: usize) -> std::io::Result<EvdevKeys> {
+        let device = File::open(path)?;
+        // event timestamps are on the realtime clock unless set otherwise,
+        // which may be stepped at any time
+        let clock = libc::CLOCK_MONOTONIC;
+        let monotonic = unsafe { libc::ioctl(device.as_raw_fd(), EVIOCSCLOCKID, &clock) } == 0;
+        if !monotonic {
+            warn!(
+                "Failed to set the clock of {}, ignoring event timestamps.",
+                path
+            );
+        }
+        Ok(EvdevKeys {
+            device,
+            buttons,
+            monotonic,
+        })
+    }
+
+    // the time at which an event occurred, from its timestamp and the time
+    // at which it was read
+    fn event_time(&self, event: &KeyEvent, read: Instant) -> Instant {
+        if !self.monotonic {
+            return read;
+        }
+        let age = monotonic_now()
+            .checked_sub(event.time)
+            .unwrap_or(Duration::from_secs(0));
+        read.checked_sub(age).unwrap_or(read)
+    }
+}
+
+// the index of the button of an evdev key code, if it has one
+fn evdev_button(key: u16) -> Option<usize> {
+    match key {
+        KEY_ENTER | KEY_SPACE => Some(0),
+        KEY_LEFT => Some(1),
+        KEY_RIGHT => Some(2),
+        KEY_UP => Some(3),
+        KEY_DOWN => Some(4),
+        KEY_A => Some(5),
+        KEY_B => Some(6),
+        _ => None,
+    }
+}
+
+impl EdgeSource for EvdevKeys {
+    fn next(&mut self, deadline: Option<Instant>) -> Result<Next, ButtonError> {
+        let mut buf = vec![0; event_len()];
+        loop {
+            let mut fds = [PollFd::new(self.device.as_raw_fd(), EventFlags::POLLIN)];
+            if poll_until(&mut fds, deadline)? == 0 {
+                return Ok(Next::Timeout(Instant::now()));
+            }
+            match self.device.read_exact(&mut buf) {
+                Ok(()) => (),
+                // the device has been unplugged
+                Err(ref err)
+                    if err.kind() == io::ErrorKind::UnexpectedEof
+                        || err.raw_os_error() == Some(libc::ENODEV) =>
+                {
+                    return Ok(Next::Closed)
+                }
+                Err(err) => {
+                    return Err(ButtonError::KeyboardError {
+                        msg: format!("Failed to read key event: {}", err),
+                    })
+                }
+            }
+            let read = Instant::now();
+
+            let event = match parse_event(&buf) {
+                Some(event) => event,
+                None => continue,
+            };
+            match evdev_button(event.key) {
+                Some(button) if button < self.buttons => {
+                    return Ok(Next::Edge(Edge {
+                        button,
+                        pressed: event.pressed,
+                        time: self.event_time(&event, read),
+                    }))
+                }
+                _ => (),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tty_keys() {
+        assert_eq!(tty_buttons(b"\x1b[A\x1b[Bx\r"), vec![3, 4, 0]);
+        assert_eq!(tty_buttons(b"ab\x1b[D\x1b[C"), vec![5, 6, 1, 2]);
+        // an incomplete escape sequence is ignored
+        assert_eq!(tty_buttons(b"\x1b["), Vec::<usize>::new());
+    }
+
+    // an input_event with the given timestamp, type, code and value
+    fn input_event(secs: u64, micros: u64, kind: u16, key: u16, value: i32) -> Vec<u8> {
+        let half = mem::size_of::<libc::timeval>() / 2;
+        let mut buf = Vec::new();
+        buf.extend_from_slice(&secs.to_ne_bytes()[..half]);
+        buf.extend_from_slice(&micros.to_ne_bytes()[..half]);
+        buf.extend_from_slice(&kind.to_ne_bytes());
+        buf.extend_from_slice(&key.to_ne_bytes());
+        buf.extend_from_slice(&value.to_ne_bytes());
+        buf
+    }
+
+    #[test]
+    fn evdev_events() {
+        assert_eq!(input_event(0, 0, 0, 0, 0).len(), event_len());
+        assert_eq!(
+            parse_event(&input_event(12, 345_678, EV_KEY, KEY_UP, 1)),
+            Some(KeyEvent {
+                key: KEY_UP,
+                pressed: true,
+                time: Duration::new(12, 345_678_000),
+            })
+        );
+        assert_eq!(
+            parse_event(&input_event(13, 0, EV_KEY, KEY_UP, 0)).map(|event| event.pressed),
+            Some(false)
+        );
+        // autorepeat, other event types and partial events are ignored
+        assert_eq!(parse_event(&input_event(13, 0, EV_KEY, KEY_UP, 2)), None);
+        assert_eq!(parse_event(&input_event(13, 0, 0, 0, 0)), None);
+        assert_eq!(
+            parse_event(&input_event(13, 0, EV_KEY, KEY_UP, 1)[..8]),
+            None
+        );
+    }
+}
@@ -1,38 +1,82 @@
+mod config;
 mod error;
 mod interrupt;
+mod keyboard;
+mod press;
+mod subscribers;
 
-use std::{env, result::Result, sync::Arc, thread};
+use std::{env, result::Result, sync::Arc, time::Duration};
 
-use crossbeam_channel::bounded;
-use jsonrpc_core::futures::Future;
+use crossbeam_channel::{unbounded, Sender};
 use jsonrpc_core::*;
 use jsonrpc_pubsub::{PubSubHandler, Session, Subscriber, SubscriptionId};
 #[allow(unused_imports)]
 use jsonrpc_test as test;
 use jsonrpc_ws_server::{RequestContext, ServerBuilder};
-use log::{debug, error, info, warn};
+use log::{debug, error, info};
+use serde::Deserialize;
 
-use crate::error::{BoxError, ButtonError::RejectSubscription};
+use crate::config::Config;
+use crate::error::{
+    BoxError,
+    ButtonError::{RejectSubscription, UnknownButton},
+};
 use crate::interrupt::*;
+use crate::keyboard::keyboard_handler;
+use crate::press::{Press, PressKind};
+use crate::subscribers::{publisher, Subscribers};
 
-pub fn run() -> Result<(), BoxError> {
-    info!("Starting up.");
-
-    debug!("Creating channel for message passing.");
-    let (s, r) = bounded(0);
-
-    let pin = vec![4, 27, 23, 17, 22, 5, 6];
-    let code = vec![0, 1, 2, 3, 4, 5, 6];
-    let name = vec!["center", "left", "right", "up", "down", "#5", "#6"];
+//define the Inject struct for receiving inject_button commands
+#[derive(Debug, Deserialize)]
+struct Inject {
+    code: u8,
+    #[serde(default)]
+    kind: PressKind,
+    #[serde(default)]
+    duration: u64,
+}
 
-    debug!("Setting up interrupt handlers.");
-    for i in 0..7 {
-        interrupt_handler(pin[i], code[i], name[i].to_string(), s.clone());
+// publish a virtual button press, as if the button had been pressed
+fn inject_button(params: Params, codes: &[u8], s: &Sender<Press>) -> Result<Value, Error> {
+    let i: Inject = params.parse()?;
+    if !codes.contains(&i.code) {
+        return Err(Error::from(UnknownButton { code: i.code }));
     }
+    info!(
+        "Injecting {} press of button code: {}.",
+        i.kind.as_str(),
+        i.code
+    );
+    let press = Press {
+        code: i.code,
+        kind: i.kind,
+        duration: Duration::from_millis(i.duration),
+    };
+    s.send(press).map_err(|err| Error {
+        code: ErrorCode::InternalError,
+        message: format!("Failed to send button press to publisher: {}", err),
+        data: None,
+    })?;
+    Ok(Value::String("success".into()))
+}
 
+/// Create the JSON-RPC pub-sub handler, with a `button_press` subscription
+/// for the given subscribers and a method to inject presses of the buttons
+/// with the given codes.
+pub fn handler(
+    codes: Vec<u8>,
+    s: Sender<Press>,
+    subscribers: Arc<Subscribers>,
+) -> PubSubHandler<Arc<Session>> {
     debug!("Creating pub-sub handler.");
     let mut io = PubSubHandler::new(MetaIoHandler::default());
 
+    io.add_method("inject_button", move |params: Params| {
+        inject_button(params, &codes, &s)
+    });
+
+    let subscribers_clone = Arc::clone(&subscribers);
+
     io.add_subscription(
         "button_press",
         (
@@ -47,39 +91,51 @@ pub fn run() -> Result<(), BoxError> {
                         });
                     return;
                 }
-
-                let r1 = r.clone();
-
-                thread::spawn(move || {
-                    let sink = subscriber
-                        .assign_id_async(SubscriptionId::Number(1))
-                        .wait()
-                        .unwrap();
-
-                    info!("Listening for button code from gpio events.");
-                    loop {
-                        let button_code: u8 = r1.recv().unwrap();
-                        info!("Received button code: {}.", button_code);
-                        match sink
-                            .notify(Params::Array(vec![Value::Number(button_code.into())]))
-                            .wait()
-                        {
-                            Ok(_) => info!("Publishing button code to subscriber over ws."),
-                            Err(_) => {
-                                warn!("Failed to publish button code.");
-                                break;
-                            }
-                        }
-                    }
-                });
+                subscribers_clone.add(subscriber);
             },
         ),
-        ("remove_buttons", |_id: SubscriptionId, _| {
-            // unsubscribe
-            futures::future::ok(Value::Bool(true))
+        // also called for each subscription of a session when it closes
+        ("remove_buttons", move |id: SubscriptionId, _| {
+            futures::future::ok(Value::Bool(subscribers.remove(&id)))
         }),
     );
 
+    io
+}
+
+pub fn run() -> Result<(), BoxError> {
+    info!("Starting up.");
+
+    debug!("Loading button config.");
+    let config = Config::load()?;
+
+    debug!("Creating channel for message passing.");
+    let (s, r) = unbounded::<Press>();
+
+    if config.gpio {
+        debug!("Setting up interrupt handler.");
+        let edges = GpioEdges::open(&config.chip, &config.buttons, config.active_low)?;
+        interrupt_handler(edges, config.buttons.clone(), config.timing(), s.clone());
+    }
+
+    let codes: Vec<u8> = config.buttons.iter().map(|button| button.code).collect();
+
+    if let Some(keyboard) = &config.keyboard {
+        debug!("Setting up keyboard handler.");
+        keyboard_handler(
+            keyboard.to_string(),
+            codes.clone(),
+            config.timing(),
+            s.clone(),
+        )?;
+    }
+
+    debug!("Setting up publisher.");
+    let subscribers = Arc::new(Subscribers::default());
+    publisher(r, Arc::clone(&subscribers));
+
+    let io = handler(codes, s, subscribers);
+
     let ws_server =
         env::var("PEACH_BUTTONS_SERVER").unwrap_or_else(|_| "127.0.0.1:5111".to_string());
 
@@ -104,6 +160,10 @@ pub fn run() -> Result<(), BoxError> {
 mod tests {
     use super::*;
 
+    use std::thread;
+
+    use jsonrpc_core::futures::Stream;
+
     #[test]
     fn rpc_success() {
         let rpc = {
@@ -116,4 +176,84 @@ mod tests {
 
         assert_eq!(rpc.request("rpc_success_response", &()), r#""success""#);
     }
+
+    // test that injected presses are published like real ones
+    #[test]
+    fn rpc_inject_button() {
+        let (s, r) = unbounded();
+        let rpc = {
+            let mut io = IoHandler::new();
+            io.add_method("inject_button", move |params: Params| {
+                inject_button(params, &[0, 1, 2], &s)
+            });
+            test::Rpc::from(io)
+        };
+
+        assert_eq!(rpc.request("inject_button", &(2, "long")), r#""success""#);
+        let press = r.try_recv().unwrap();
+        assert_eq!(press.code, 2);
+        assert_eq!(press.kind, PressKind::Long);
+
+        assert!(rpc
+            .request("inject_button", &[7])
+            .contains("Unknown button code: 7"));
+    }
+
+    // test that every subscriber receives every button press, until it
+    // unsubscribes or disconnects
+    #[test]
+    fn subscribers_receive_every_press() {
+        let (s, r) = unbounded();
+        let subscribers = Arc::new(Subscribers::default());
+        publisher(r, Arc::clone(&subscribers));
+        let io = handler(vec![0, 1, 2], s, Arc::clone(&subscribers));
+
+        // subscribe three clients, each with a session of its own
+        let mut clients = Vec::new();
+        for id in 1..=3 {
+            let (sender, receiver) = futures::sync::mpsc::channel(8);
+            let session = Arc::new(Session::new(sender));
+            let response = io.handle_request_sync(
+                r#"{"jsonrpc":"2.0","method":"subscribe_buttons","id":1}"#,
+                Arc::clone(&session),
+            );
+            assert_eq!(
+                response,
+                Some(format!(r#"{{"jsonrpc":"2.0","result":{},"id":1}}"#, id))
+            );
+            clients.push((session, receiver.wait()));
+        }
+        while subscribers.len() < 3 {
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        for code in &[1, 2] {
+            let request = format!(
+                r#"{{"jsonrpc":"2.0","method":"inject_button","params":[{}],"id":2}}"#,
+                code
+            );
+            io.handle_request_sync(&request, Arc::clone(&clients[0].0));
+        }
+        for (_, notifications) in clients.iter_mut() {
+            for code in &[1, 2] {
+                let notification = notifications.next().unwrap().unwrap();
+                assert!(notification.contains(r#""method":"button_press""#));
+                assert!(notification.contains(&format!(r#""params":[{},"short",0]"#, code)));
+            }
+        }
+
+        let response = io.handle_request_sync(
+            r#"{"jsonrpc":"2.0","method":"remove_buttons","params":[1],"id":3}"#,
+            Arc::clone(&clients[0].0),
+        );
+        assert_eq!(
+            response,
+            Some(r#"{"jsonrpc":"2.0","result":true,"id":3}"#.to_string())
+        );
+        assert_eq!(subscribers.len(), 2);
+
+        // closing a session unsubscribes its client
+        clients.pop();
+        assert_eq!(subscribers.len(), 1);
+    }
 }
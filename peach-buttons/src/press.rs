@@ -0,0 +1,206 @@
+//! Classifying button presses.
+//!
+//! A `Tracker` follows the level of a single button over time. A new level
+//! is only accepted once it has been held for the debounce period. A press
+//! released before the long-press period is a short press (reported on
+//! release); a press held for the long-press period is a long press
+//! (reported as soon as the period elapses), followed by repeat events at a
+//! fixed interval for as long as the button is held.
+
+use std::time::{Duration, Instant};
+
+use jsonrpc_core::{Params, Value};
+use serde::Deserialize;
+
+use crate::config::Timing;
+
+/// The kinds of button events.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum PressKind {
+    #[default]
+    Short,
+    Long,
+    Repeat,
+}
+
+impl PressKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PressKind::Short => "short",
+            PressKind::Long => "long",
+            PressKind::Repeat => "repeat",
+        }
+    }
+}
+
+/// A button event, as published to subscribers.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Press {
+    pub code: u8,
+    pub kind: PressKind,
+    /// How long the button had been held when the event occurred.
+    pub duration: Duration,
+}
+
+impl Press {
+    /// The parameters of the `button_press` notification: the button code,
+    /// the event kind and the duration in milliseconds.
+    pub fn to_params(&self) -> Params {
+        Params::Array(vec![
+            Value::Number(self.code.into()),
+            Value::String(self.kind.as_str().to_string()),
+            Value::Number((self.duration.as_millis() as u64).into()),
+        ])
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Hold {
+    Released,
+    // pressed since the given time
+    Pressed(Instant),
+    // pressed since the given time and already reported as a long press;
+    // the next repeat is due at the second time
+    Long(Instant, Instant),
+}
+
+/// Debounces the level of a button and classifies its presses.
+#[derive(Debug)]
+pub struct Tracker {
+    timing: Timing,
+    // the last level read and when it was first read
+    level: bool,
+    level_since: Instant,
+    hold: Hold,
+}
+
+impl Tracker {
+    pub fn new(timing: Timing, now: Instant) -> Tracker {
+        Tracker {
+            timing,
+            level: false,
+            level_since: now,
+            hold: Hold::Released,
+        }
+    }
+
+    /// The last level read (`true` if pressed).
+    pub fn level(&self) -> bool {
+        self.level
+    }
+
+    /// The time at which the tracker should next be updated, even if the
+    /// level of the button has not changed: when a new level settles, a
+    /// press becomes a long press or a repeat is due.
+    pub fn deadline(&self) -> Option<Instant> {
+        let settle = self.level_since + self.timing.debounce;
+        match self.hold {
+            Hold::Released if self.level => Some(settle),
+            Hold::Released => None,
+            Hold::Pressed(since) => {
+                let long = since + self.timing.long_press;
+                Some(if self.level { long } else { long.min(settle) })
+            }
+            Hold::Long(_, _) if !self.level => Some(settle),
+            Hold::Long(_, next) => self.timing.repeat.map(|_| next),
+        }
+    }
+
+    /// Update the tracker with the level of the button (`true` if pressed)
+    /// read at the given time, returning the event which occurred, if any.
+    pub fn update(&mut self, pressed: bool, now: Instant) -> Option<(PressKind, Duration)> {
+        // levels cannot be read before the last one
+        let now = now.max(self.level_since);
+        if pressed != self.level {
+            self.level = pressed;
+            self.level_since = now;
+        }
+        let settled = now.duration_since(self.level_since) >= self.timing.debounce;
+
+        match self.hold {
+            Hold::Released if self.level && settled => {
+                self.hold = Hold::Pressed(self.level_since);
+                None
+            }
+            Hold::Pressed(since) if !self.level && settled => {
+                self.hold = Hold::Released;
+                Some((PressKind::Short, self.level_since.duration_since(since)))
+            }
+            Hold::Pressed(since) if now.duration_since(since) >= self.timing.long_press => {
+                let next = since + self.timing.long_press + self.timing.repeat.unwrap_or_default();
+                self.hold = Hold::Long(since, next);
+                Some((PressKind::Long, now.duration_since(since)))
+            }
+            Hold::Long(_, _) if !self.level && settled => {
+                self.hold = Hold::Released;
+                None
+            }
+            Hold::Long(since, next) if self.level && now >= next => {
+                let interval = self.timing.repeat?;
+                self.hold = Hold::Long(since, next + interval);
+                Some((PressKind::Repeat, now.duration_since(since)))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timing() -> Timing {
+        Timing {
+            debounce: Duration::from_millis(20),
+            long_press: Duration::from_millis(1000),
+            repeat: Some(Duration::from_millis(200)),
+        }
+    }
+
+    // feed levels read every 10ms to a tracker, collecting the events
+    fn events(levels: &[bool]) -> Vec<(PressKind, Duration)> {
+        let start = Instant::now();
+        let mut tracker = Tracker::new(timing(), start);
+        levels
+            .iter()
+            .enumerate()
+            .filter_map(|(i, level)| {
+                tracker.update(*level, start + Duration::from_millis(10 * i as u64))
+            })
+            .collect()
+    }
+
+    // a button pressed for the given number of 10ms reads, then released
+    fn press(reads: usize) -> Vec<bool> {
+        let mut levels = vec![false; 5];
+        levels.resize(5 + reads, true);
+        levels.extend([false; 5]);
+        levels
+    }
+
+    #[test]
+    fn short_press() {
+        assert_eq!(
+            events(&press(10)),
+            vec![(PressKind::Short, Duration::from_millis(100))]
+        );
+    }
+
+    #[test]
+    fn bounces_are_ignored() {
+        let mut levels = vec![false, true, false, true, false];
+        levels.extend(press(1));
+        assert_eq!(events(&levels), vec![]);
+    }
+
+    #[test]
+    fn long_press_repeats_while_held() {
+        // held for 1.5s: a long press after 1s, then repeats every 200ms
+        let kinds: Vec<PressKind> = events(&press(150)).into_iter().map(|e| e.0).collect();
+        assert_eq!(
+            kinds,
+            vec![PressKind::Long, PressKind::Repeat, PressKind::Repeat]
+        );
+    }
+}
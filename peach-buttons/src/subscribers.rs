@@ -0,0 +1,156 @@
+//! Subscribers to button presses.
+//!
+//! Each subscriber is assigned a unique id and every button press is
+//! published to every subscriber. A subscriber is removed when it
+//! unsubscribes, when its connection closes (the session unsubscribes it)
+//! or when a notification can no longer be sent to it.
+//!
+//! A subscriber is pending from the time it is assigned an id until the id
+//! has been sent to it. Removing a pending subscriber cancels it, so that it
+//! is not added once its id has been sent.
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+};
+
+use crossbeam_channel::Receiver;
+use jsonrpc_core::futures::Future;
+use jsonrpc_pubsub::{Sink, Subscriber, SubscriptionId};
+use log::{debug, info, warn};
+
+use crate::press::Press;
+
+/// The subscribers to the `button_press` notification, by id.
+#[derive(Default)]
+pub struct Subscribers {
+    next_id: AtomicU64,
+    // locked before `sinks` when both are required
+    pending: Mutex<HashSet<u64>>,
+    sinks: Mutex<HashMap<u64, Sink>>,
+}
+
+impl Subscribers {
+    /// Assign the subscriber a unique id and add it once the id has been sent.
+    pub fn add(self: &Arc<Self>, subscriber: Subscriber) {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst) + 1;
+        self.pending.lock().unwrap().insert(id);
+        let subscribers = Arc::clone(self);
+        // wait for the id to be sent before publishing to the subscriber
+        thread::spawn(move || {
+            let assigned = subscriber
+                .assign_id_async(SubscriptionId::Number(id))
+                .wait();
+            let mut pending = subscribers.pending.lock().unwrap();
+            if !pending.remove(&id) {
+                info!("Subscriber {} was removed before it was added.", id);
+                return;
+            }
+            match assigned {
+                Ok(sink) => {
+                    info!("Added subscriber {}.", id);
+                    subscribers.sinks.lock().unwrap().insert(id, sink);
+                }
+                Err(_) => warn!("Subscriber {} disconnected before it was added.", id),
+            }
+        });
+    }
+
+    /// Remove a subscriber (or cancel a pending one), returning `false` if
+    /// there is no subscriber with the given id.
+    pub fn remove(&self, id: &SubscriptionId) -> bool {
+        let removed = match id {
+            SubscriptionId::Number(id) => {
+                let mut pending = self.pending.lock().unwrap();
+                pending.remove(id) || self.sinks.lock().unwrap().remove(id).is_some()
+            }
+            SubscriptionId::String(_) => false,
+        };
+        if removed {
+            info!("Removed subscriber {:?}.", id);
+        }
+        removed
+    }
+
+    /// The number of subscribers.
+    pub fn len(&self) -> usize {
+        self.sinks.lock().unwrap().len()
+    }
+
+    /// Publish a button press to every subscriber, removing those which can
+    /// no longer be reached.
+    pub fn publish(&self, press: &Press) {
+        // notify without holding the lock, so that a slow subscriber does not
+        // block subscribers being added or removed
+        let sinks: Vec<(u64, Sink)> = self
+            .sinks
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(id, sink)| (*id, sink.clone()))
+            .collect();
+        let mut closed = Vec::new();
+        for (id, sink) in sinks {
+            match sink.notify(press.to_params()).wait() {
+                Ok(_) => debug!("Published button press to subscriber {}.", id),
+                Err(_) => closed.push(id),
+            }
+        }
+        let mut sinks = self.sinks.lock().unwrap();
+        for id in closed {
+            warn!("Failed to publish to subscriber {}, removing it.", id);
+            sinks.remove(&id);
+        }
+    }
+}
+
+/// Publish the button presses received on the channel to the subscribers,
+/// in a thread of their own.
+pub fn publisher(r: Receiver<Press>, subscribers: Arc<Subscribers>) {
+    thread::spawn(move || {
+        info!("Listening for button presses.");
+        for press in r.iter() {
+            info!(
+                "Publishing {} press of button code {} to {} subscribers.",
+                press.kind.as_str(),
+                press.code,
+                subscribers.len()
+            );
+            subscribers.publish(&press);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    // test that a subscriber removed while pending is never added
+    #[test]
+    fn removing_a_pending_subscriber_cancels_it() {
+        let subscribers = Arc::new(Subscribers::default());
+
+        // the id is only sent once the client receives it
+        let (subscriber, id, _notifications) = Subscriber::new_test("button_press");
+        subscribers.add(subscriber);
+        assert!(subscribers.remove(&SubscriptionId::Number(1)));
+        assert_eq!(id.wait().unwrap(), Ok(SubscriptionId::Number(1)));
+        thread::sleep(Duration::from_millis(50));
+        assert_eq!(subscribers.len(), 0);
+        assert!(!subscribers.remove(&SubscriptionId::Number(1)));
+
+        let (subscriber, id, _notifications) = Subscriber::new_test("button_press");
+        subscribers.add(subscriber);
+        assert_eq!(id.wait().unwrap(), Ok(SubscriptionId::Number(2)));
+        while subscribers.len() < 1 {
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert!(subscribers.remove(&SubscriptionId::Number(2)));
+        assert_eq!(subscribers.len(), 0);
+    }
+}
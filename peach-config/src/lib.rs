@@ -0,0 +1,4 @@
+//! Constants shared with other PeachCloud crates, such as the list of
+//! PeachCloud microservices (which `peach-monitor` checks the health of).
+
+pub mod constants;
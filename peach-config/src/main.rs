@@ -1,4 +1,3 @@
-mod constants;
 mod error;
 mod generate_manifest;
 mod setup_networking;
@@ -12,6 +11,9 @@ use log::error;
 use serde::{Deserialize, Serialize};
 use structopt::StructOpt;
 
+// constants are defined in the library target so that other crates can use them
+use peach_config::constants;
+
 use crate::generate_manifest::generate_manifest;
 use crate::setup_peach::setup_peach;
 use crate::update::update;
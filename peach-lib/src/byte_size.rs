@@ -0,0 +1,166 @@
+//! A quantity of data (network traffic, disk space) in bytes.
+//!
+//! `ByteSize` is serialized as a plain number of bytes, so JSON responses
+//! remain machine-readable, and is displayed with binary units for people
+//! (`512 B`, `1.5 KiB`, `2.5 GiB`). Values can be parsed from strings such as
+//! `2.5GB`, `300 MiB` or `1024`; unit prefixes are case-insensitive and are
+//! always interpreted as powers of 1024 (`MB` is treated as `MiB`), since
+//! PeachCloud has always measured traffic in binary units.
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::error::PeachError;
+
+pub const KIB: u64 = 1024;
+pub const MIB: u64 = KIB * 1024;
+pub const GIB: u64 = MIB * 1024;
+pub const TIB: u64 = GIB * 1024;
+
+// unit suffixes in increasing order of size
+const UNITS: [(&str, u64); 5] = [
+    ("B", 1),
+    ("KiB", KIB),
+    ("MiB", MIB),
+    ("GiB", GIB),
+    ("TiB", TIB),
+];
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ByteSize(pub u64);
+
+impl ByteSize {
+    pub fn from_kib(kib: u64) -> ByteSize {
+        ByteSize(kib * KIB)
+    }
+
+    pub fn from_mib(mib: u64) -> ByteSize {
+        ByteSize(mib * MIB)
+    }
+
+    pub fn as_u64(self) -> u64 {
+        self.0
+    }
+
+    /// Size in whole mebibytes (rounded to the nearest).
+    pub fn as_mib(self) -> u64 {
+        (self.0 + MIB / 2) / MIB
+    }
+
+    /// Parse a size, treating a bare number as a multiple of `unit` bytes
+    /// (for example, a threshold entered as `500` in a field labelled `MB`).
+    pub fn parse_with_unit(value: &str, unit: u64) -> Result<ByteSize, PeachError> {
+        let value = value.trim();
+        let invalid = || PeachError::ByteSizeParseError {
+            value: value.to_string(),
+        };
+        let split = value
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .unwrap_or(value.len());
+        let (number, suffix) = value.split_at(split);
+        let number: f64 = number.parse().map_err(|_| invalid())?;
+        let multiplier = match suffix.trim().to_lowercase().as_str() {
+            "" => unit,
+            "b" => 1,
+            "k" | "kb" | "kib" => KIB,
+            "m" | "mb" | "mib" => MIB,
+            "g" | "gb" | "gib" => GIB,
+            "t" | "tb" | "tib" => TIB,
+            _ => return Err(invalid()),
+        };
+
+        Ok(ByteSize((number * multiplier as f64).round() as u64))
+    }
+}
+
+impl fmt::Display for ByteSize {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (unit, size) = UNITS
+            .iter()
+            .rev()
+            .find(|(_, size)| self.0 >= *size)
+            .unwrap_or(&UNITS[0]);
+        if *size == 1 {
+            write!(f, "{} {}", self.0, unit)
+        } else {
+            write!(f, "{:.1} {}", self.0 as f64 / *size as f64, unit)
+        }
+    }
+}
+
+impl FromStr for ByteSize {
+    type Err = PeachError;
+
+    /// Parse a size, treating a bare number as bytes.
+    fn from_str(value: &str) -> Result<ByteSize, PeachError> {
+        ByteSize::parse_with_unit(value, 1)
+    }
+}
+
+impl From<u64> for ByteSize {
+    fn from(bytes: u64) -> ByteSize {
+        ByteSize(bytes)
+    }
+}
+
+impl Serialize for ByteSize {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(self.0)
+    }
+}
+
+// accepts a number of bytes or a string with a unit (eg. `"2.5GB"`)
+impl<'de> Deserialize<'de> for ByteSize {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<ByteSize, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Bytes(u64),
+            Text(String),
+        }
+
+        match Repr::deserialize(deserializer)? {
+            Repr::Bytes(bytes) => Ok(ByteSize(bytes)),
+            Repr::Text(text) => text.parse().map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+/// Deserialize a number of kilobytes (as reported by `peach-stats` for disk
+/// usage) into a `ByteSize`.
+pub fn deserialize_kib<'de, D: Deserializer<'de>>(deserializer: D) -> Result<ByteSize, D::Error> {
+    u64::deserialize(deserializer).map(ByteSize::from_kib)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_sizes() {
+        assert_eq!("1024".parse::<ByteSize>().unwrap(), ByteSize(1024));
+        assert_eq!("2.5GB".parse::<ByteSize>().unwrap(), ByteSize(5 * GIB / 2));
+        assert_eq!("300 MiB".parse::<ByteSize>().unwrap(), ByteSize(300 * MIB));
+        assert_eq!("1k".parse::<ByteSize>().unwrap(), ByteSize(KIB));
+        assert_eq!(
+            ByteSize::parse_with_unit("500", MIB).unwrap(),
+            ByteSize::from_mib(500)
+        );
+        assert!("".parse::<ByteSize>().is_err());
+        assert!("-1MB".parse::<ByteSize>().is_err());
+        assert!("12 apples".parse::<ByteSize>().is_err());
+    }
+
+    #[test]
+    fn display_sizes() {
+        assert_eq!(ByteSize(0).to_string(), "0 B");
+        assert_eq!(ByteSize(512).to_string(), "512 B");
+        assert_eq!(ByteSize(1536).to_string(), "1.5 KiB");
+        assert_eq!(ByteSize(5 * GIB / 2).to_string(), "2.5 GiB");
+        // displayed sizes can be parsed again
+        let size = ByteSize::from_mib(300);
+        assert_eq!(size.to_string().parse::<ByteSize>().unwrap(), size);
+    }
+}
@@ -34,6 +34,27 @@ pub struct PeachConfig {
     pub dyn_enabled: bool,
     #[serde(default)] // default is empty vector
     pub ssb_admin_ids: Vec<String>,
+    #[serde(default)] // default is empty string (no webhook)
+    pub alert_webhook: String,
+    #[serde(default)] // default is empty string (no token access to /metrics)
+    pub metrics_token: String,
+    #[serde(default)] // default is empty vector (no host access to /metrics)
+    pub metrics_allowed_hosts: Vec<String>,
+    #[serde(default = "default_screen_dim_timeout")]
+    pub screen_dim_timeout: u64,
+    #[serde(default = "default_screen_off_timeout")]
+    pub screen_off_timeout: u64,
+}
+
+// idle time (in seconds) before the oled display is dimmed (0 to never dim)
+fn default_screen_dim_timeout() -> u64 {
+    30
+}
+
+// idle time (in seconds) before the oled display is turned off (0 to never
+// turn off)
+fn default_screen_off_timeout() -> u64 {
+    300
 }
 
 // helper functions for serializing and deserializing PeachConfig from disc
@@ -69,6 +90,11 @@ pub fn load_peach_config() -> Result<PeachConfig, PeachError> {
             dyn_tsig_key_path: "".to_string(),
             dyn_enabled: false,
             ssb_admin_ids: Vec::new(),
+            alert_webhook: "".to_string(),
+            metrics_token: "".to_string(),
+            metrics_allowed_hosts: Vec::new(),
+            screen_dim_timeout: default_screen_dim_timeout(),
+            screen_off_timeout: default_screen_off_timeout(),
         };
     }
     // otherwise we load peach config from disk
@@ -71,6 +71,10 @@ pub enum PeachError {
     SbotCliError { msg: String },
     #[snafu(display("Error deleting ssb admin id, id not found"))]
     SsbAdminIdNotFound { id: String },
+    #[snafu(display("Monitor store error: {:?}", err))]
+    MonitorStoreError { err: nest::Error },
+    #[snafu(display("Invalid size: {}", value))]
+    ByteSizeParseError { value: String },
 }
 
 impl From<jsonrpc_client_http::Error> for PeachError {
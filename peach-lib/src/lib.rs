@@ -2,9 +2,11 @@
 // to replace code with the same code that is already there (possibly a bug)
 #![allow(clippy::nonstandard_macro_braces)]
 
+pub mod byte_size;
 pub mod config_manager;
 pub mod dyndns_client;
 pub mod error;
+pub mod monitor;
 pub mod network_client;
 pub mod oled_client;
 pub mod password_utils;
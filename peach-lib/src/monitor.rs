@@ -0,0 +1,650 @@
+//! Data store for network traffic totals, usage history, system health and
+//! the associated alert thresholds and alert flags.
+//!
+//! The store is written by `peach-monitor` and read (and partly written) by
+//! `peach-web`, so it is located at a fixed path rather than in the data
+//! directory of whichever user runs each process. The data is kept as a set
+//! of JSON files in a `nest` store:
+//!
+//! ```text
+//! /var/lib/peachcloud/monitor
+//! ├── health
+//! │   ├── alert.json      // programatically-defined health alert flags
+//! │   └── notify.json     // user-defined health thresholds and flags
+//! └── net
+//!     ├── alert.json      // programatically-defined alert flags
+//!     ├── history.json    // daily, monthly and billing-cycle usage buckets
+//!     ├── notify.json     // user-defined alert thresholds and reset day
+//!     └── traffic.json    // usage total and per-interface counters
+//! ```
+//!
+//! All reads and writes happen inside a `Transaction`, which holds an
+//! exclusive lock on `/var/lib/peachcloud/monitor/store.lock` for its duration.
+//! The lock file is kept in the store directory, the only directory which
+//! the sandboxed `peach-monitor` service is allowed to write to.
+//! Read-modify-write sequences performed in a single transaction are
+//! therefore safe against concurrent writes from other processes.
+
+use std::collections::BTreeMap;
+use std::convert::TryInto;
+use std::fs;
+use std::path::PathBuf;
+
+use fslock::LockFile;
+use nest::{Schema, Store, Value};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::byte_size::ByteSize;
+use crate::error::PeachError;
+
+// monitor data store directory
+pub const STORE_PATH: &str = "/var/lib/peachcloud/monitor";
+
+// lock file (used to avoid race conditions between peach-monitor & peach-web)
+pub const LOCK_FILE_PATH: &str = "/var/lib/peachcloud/monitor/store.lock";
+
+/// Network traffic notification thresholds and flags (user-defined).
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct Threshold {
+    pub warn: ByteSize,  // traffic warning threshold (stored in MB)
+    pub cut: ByteSize,   // traffic cutoff threshold (stored in MB)
+    pub warn_flag: bool, // traffic warning notification flag
+    pub cut_flag: bool,  // traffic cutoff notification & enforcement flag
+    pub reset_day: u64,  // billing-cycle reset day (1-28, 0 for calendar months)
+}
+
+/// Warning and cutoff network traffic alert flags (programatically-defined).
+#[derive(Debug, Default, Serialize)]
+pub struct Alert {
+    pub warn: bool,
+    pub cut: bool,
+    pub cut_iface: Option<String>, // uplink blocked by cutoff enforcement
+    pub cut_override: bool,        // cutoff enforcement overridden by the user
+}
+
+/// System health alert thresholds and notification flags (user-defined).
+#[derive(Debug, Deserialize, Serialize)]
+pub struct HealthThreshold {
+    pub disk: u64,          // root filesystem usage threshold (percent)
+    pub temp: u64,          // CPU temperature threshold (degrees Celsius)
+    pub mem: u64,           // memory pressure threshold (percent of memory in use)
+    pub disk_flag: bool,    // disk usage notification flag
+    pub temp_flag: bool,    // CPU temperature notification flag
+    pub mem_flag: bool,     // memory pressure notification flag
+    pub service_flag: bool, // failed service notification flag
+}
+
+// used until the user has defined their own thresholds
+impl Default for HealthThreshold {
+    fn default() -> Self {
+        HealthThreshold {
+            disk: 90,
+            temp: 80,
+            mem: 90,
+            disk_flag: true,
+            temp_flag: true,
+            mem_flag: true,
+            service_flag: true,
+        }
+    }
+}
+
+/// System health alert flags (programatically-defined).
+#[derive(Debug, Default, Serialize)]
+pub struct HealthAlert {
+    pub disk: bool,
+    pub temp: bool,
+    pub mem: bool,
+    pub services: Vec<String>, // failed systemd services
+}
+
+/// Last sample and running totals of the traffic counters of an interface.
+#[derive(Debug, Default)]
+pub struct Counters {
+    pub boot_id: String, // kernel boot ID at the time of the last sample
+    pub rx: u64,         // received bytes since boot at the last sample
+    pub tx: u64,         // transmitted bytes since boot at the last sample
+    pub total_rx: u64,   // received bytes across all boots
+    pub total_tx: u64,   // transmitted bytes across all boots
+}
+
+/// Network traffic for a single day, month or billing cycle.
+#[derive(Debug, Serialize)]
+pub struct Bucket {
+    pub period: String, // `YYYY-MM-DD` (day or cycle start date) or `YYYY-MM`
+    pub rx: ByteSize,   // received bytes
+    pub tx: ByteSize,   // transmitted bytes
+}
+
+/// Network traffic history (oldest period first).
+#[derive(Debug, Default, Serialize)]
+pub struct History {
+    pub daily: Vec<Bucket>,
+    pub monthly: Vec<Bucket>,
+    pub cycle: Vec<Bucket>,
+}
+
+/// Kinds of history bucket.
+#[derive(Clone, Copy, Debug)]
+pub enum Period {
+    Daily,
+    Monthly,
+    Cycle,
+}
+
+impl Period {
+    fn key(self) -> &'static str {
+        match self {
+            Period::Daily => "daily",
+            Period::Monthly => "monthly",
+            Period::Cycle => "cycle",
+        }
+    }
+}
+
+/// Handle to the monitor data store.
+pub struct MonitorStore {
+    store: Store,
+}
+
+impl MonitorStore {
+    /// Open the store, creating the store directory if it does not exist.
+    pub fn open() -> Result<MonitorStore, PeachError> {
+        fs::create_dir_all(STORE_PATH)?;
+
+        let schema: Schema = json!({
+            "health": {
+                "notify": "json",
+                "alert": "json"
+            },
+            "net": {
+                "traffic": "json",
+                "notify": "json",
+                "alert": "json",
+                "history": "json"
+            }
+        })
+        .try_into()
+        .map_err(|err| PeachError::MonitorStoreError { err })?;
+
+        Ok(MonitorStore {
+            store: Store::new(PathBuf::from(STORE_PATH), schema),
+        })
+    }
+
+    /// Run `f` while holding the store lock.
+    pub fn transaction<T, F>(&self, f: F) -> Result<T, PeachError>
+    where
+        F: FnOnce(&Transaction) -> Result<T, PeachError>,
+    {
+        let mut lock = LockFile::open(LOCK_FILE_PATH)?;
+        lock.lock()?;
+        let result = f(&Transaction { store: &self.store });
+        lock.unlock()?;
+        result
+    }
+
+    /// Retrieve the alert thresholds and flags.
+    pub fn threshold(&self) -> Result<Threshold, PeachError> {
+        self.transaction(|tx| Ok(tx.threshold()))
+    }
+
+    /// Store the alert thresholds and flags.
+    pub fn set_threshold(&self, threshold: &Threshold) -> Result<(), PeachError> {
+        self.transaction(|tx| tx.set_threshold(threshold))
+    }
+
+    /// Retrieve the alert flags.
+    pub fn alert(&self) -> Result<Alert, PeachError> {
+        self.transaction(|tx| Ok(tx.alert()))
+    }
+
+    /// Retrieve the usage total (bytes).
+    pub fn total(&self) -> Result<u64, PeachError> {
+        self.transaction(|tx| Ok(tx.total()))
+    }
+
+    /// Retrieve the usage history.
+    pub fn history(&self) -> Result<History, PeachError> {
+        self.transaction(|tx| Ok(tx.history()))
+    }
+
+    /// Retrieve the health alert thresholds and flags.
+    pub fn health_threshold(&self) -> Result<HealthThreshold, PeachError> {
+        self.transaction(|tx| Ok(tx.health_threshold()))
+    }
+
+    /// Store the health alert thresholds and flags.
+    pub fn set_health_threshold(&self, threshold: &HealthThreshold) -> Result<(), PeachError> {
+        self.transaction(|tx| tx.set_health_threshold(threshold))
+    }
+
+    /// Retrieve the health alert flags.
+    pub fn health_alert(&self) -> Result<HealthAlert, PeachError> {
+        self.transaction(|tx| Ok(tx.health_alert()))
+    }
+}
+
+/// Access to the store while the store lock is held.
+pub struct Transaction<'a> {
+    store: &'a Store,
+}
+
+impl<'a> Transaction<'a> {
+    fn get(&self, path: &[&str]) -> Option<Value> {
+        self.store.get(&path.to_vec()).ok()
+    }
+
+    fn set(&self, path: &[&str], value: Value) -> Result<(), PeachError> {
+        self.store
+            .set(&path.to_vec(), &value)
+            .map_err(|err| PeachError::MonitorStoreError { err })
+    }
+
+    fn get_bool(&self, path: &[&str]) -> bool {
+        matches!(self.get(path), Some(Value::Bool(true)))
+    }
+
+    fn get_uint(&self, path: &[&str]) -> u64 {
+        self.get_uint_or(path, 0)
+    }
+
+    fn get_bool_or(&self, path: &[&str], default: bool) -> bool {
+        match self.get(path) {
+            Some(Value::Bool(val)) => val,
+            _ => default,
+        }
+    }
+
+    fn get_uint_or(&self, path: &[&str], default: u64) -> u64 {
+        match self.get(path) {
+            Some(Value::Uint(val)) => val,
+            _ => default,
+        }
+    }
+
+    // empty strings are stored to clear optional values
+    fn get_string(&self, path: &[&str]) -> Option<String> {
+        match self.get(path) {
+            Some(Value::String(val)) if !val.is_empty() => Some(val),
+            _ => None,
+        }
+    }
+
+    // objects are returned sorted by key
+    fn get_object(&self, path: &[&str]) -> BTreeMap<String, Value> {
+        match self.get(path) {
+            Some(Value::Object(object)) => object.into_iter().collect(),
+            _ => BTreeMap::new(),
+        }
+    }
+
+    fn set_object(&self, path: &[&str], object: BTreeMap<String, Value>) -> Result<(), PeachError> {
+        self.set(path, Value::Object(object.into_iter().collect()))
+    }
+
+    fn get_strings(&self, path: &[&str]) -> Vec<String> {
+        match self.get(path) {
+            Some(Value::Array(values)) => values
+                .into_iter()
+                .filter_map(|value| match value {
+                    Value::String(val) => Some(val),
+                    _ => None,
+                })
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    fn set_strings(&self, path: &[&str], strings: &[String]) -> Result<(), PeachError> {
+        let values = strings.iter().cloned().map(Value::String).collect();
+        self.set(path, Value::Array(values))
+    }
+
+    /* THRESHOLDS */
+
+    /// Retrieve the alert thresholds and flags.
+    pub fn threshold(&self) -> Threshold {
+        Threshold {
+            warn: ByteSize::from_mib(self.get_uint(&["net", "notify", "warn"])),
+            cut: ByteSize::from_mib(self.get_uint(&["net", "notify", "cut"])),
+            warn_flag: self.get_bool(&["net", "notify", "warn_flag"]),
+            cut_flag: self.get_bool(&["net", "notify", "cut_flag"]),
+            reset_day: self.get_uint(&["net", "notify", "reset_day"]),
+        }
+    }
+
+    /// Store the alert thresholds and flags. Thresholds are rounded to whole
+    /// megabytes. Reset days after the 28th are stored as the 28th, since days
+    /// 29-31 do not occur in every month.
+    pub fn set_threshold(&self, threshold: &Threshold) -> Result<(), PeachError> {
+        self.set(
+            &["net", "notify", "warn"],
+            Value::Uint(threshold.warn.as_mib()),
+        )?;
+        self.set(
+            &["net", "notify", "cut"],
+            Value::Uint(threshold.cut.as_mib()),
+        )?;
+        self.set(
+            &["net", "notify", "warn_flag"],
+            Value::Bool(threshold.warn_flag),
+        )?;
+        self.set(
+            &["net", "notify", "cut_flag"],
+            Value::Bool(threshold.cut_flag),
+        )?;
+        self.set(
+            &["net", "notify", "reset_day"],
+            Value::Uint(threshold.reset_day.min(28)),
+        )
+    }
+
+    /// Retrieve the billing-cycle reset day (1-28). `None` is returned if the
+    /// user has not set a reset day.
+    pub fn reset_day(&self) -> Option<u32> {
+        match self.get_uint(&["net", "notify", "reset_day"]) {
+            day @ 1..=28 => Some(day as u32),
+            _ => None,
+        }
+    }
+
+    /* ALERTS */
+
+    /// Retrieve the alert flags.
+    pub fn alert(&self) -> Alert {
+        Alert {
+            warn: self.get_bool(&["net", "alert", "warn_alert"]),
+            cut: self.get_bool(&["net", "alert", "cut_alert"]),
+            cut_iface: self.get_string(&["net", "alert", "cut_iface"]),
+            cut_override: self.get_bool(&["net", "alert", "cut_override"]),
+        }
+    }
+
+    /// Set the warning and cutoff alert flags.
+    pub fn set_alert_flags(&self, warn: bool, cut: bool) -> Result<(), PeachError> {
+        self.set(&["net", "alert", "warn_alert"], Value::Bool(warn))?;
+        self.set(&["net", "alert", "cut_alert"], Value::Bool(cut))
+    }
+
+    /// Record the uplink whose traffic is blocked by cutoff enforcement
+    /// (`None` once traffic has been restored).
+    pub fn set_cut_iface(&self, iface: Option<&str>) -> Result<(), PeachError> {
+        let iface = iface.unwrap_or_default().to_string();
+        self.set(&["net", "alert", "cut_iface"], Value::String(iface))
+    }
+
+    /// Set whether cutoff enforcement has been overridden by the user.
+    pub fn set_cut_override(&self, cut_override: bool) -> Result<(), PeachError> {
+        self.set(&["net", "alert", "cut_override"], Value::Bool(cut_override))
+    }
+
+    /// Retrieve the notification channels through which the named alert
+    /// (`warn` or `cut`) has been sent during the given billing cycle.
+    pub fn alert_sent(&self, name: &str, cycle: &str) -> Vec<String> {
+        self.get_object(&["net", "alert", &format!("{}_sent", name)])
+            .into_iter()
+            .filter(|(_, sent)| matches!(sent, Value::String(sent) if sent == cycle))
+            .map(|(channel, _)| channel)
+            .collect()
+    }
+
+    /// Record the notification channels through which the named alert has
+    /// been sent during the given billing cycle.
+    pub fn set_alert_sent(
+        &self,
+        name: &str,
+        cycle: &str,
+        channels: &[String],
+    ) -> Result<(), PeachError> {
+        let path = ["net", "alert", &format!("{}_sent", name)];
+        let mut sent = self.get_object(&path);
+        for channel in channels {
+            sent.insert(channel.to_string(), Value::String(cycle.to_string()));
+        }
+        self.set_object(&path, sent)
+    }
+
+    /* TRAFFIC */
+
+    /// Retrieve the usage total (bytes).
+    pub fn total(&self) -> u64 {
+        self.get_uint(&["net", "traffic", "total"])
+    }
+
+    /// Set the usage total (bytes).
+    pub fn set_total(&self, total: u64) -> Result<(), PeachError> {
+        self.set(&["net", "traffic", "total"], Value::Uint(total))
+    }
+
+    /// Retrieve the stored counters of all interfaces.
+    pub fn counters(&self) -> BTreeMap<String, Counters> {
+        let mut counters = BTreeMap::new();
+        for (iface, value) in self.get_object(&["net", "traffic", "ifaces"]) {
+            if let Value::Object(object) = value {
+                let uint = |field: &str| match object.get(field) {
+                    Some(Value::Uint(val)) => *val,
+                    _ => 0,
+                };
+                let boot_id = match object.get("boot_id") {
+                    Some(Value::String(boot_id)) => boot_id.to_string(),
+                    _ => String::new(),
+                };
+                counters.insert(
+                    iface,
+                    Counters {
+                        rx: uint("rx"),
+                        tx: uint("tx"),
+                        total_rx: uint("total_rx"),
+                        total_tx: uint("total_tx"),
+                        boot_id,
+                    },
+                );
+            }
+        }
+        counters
+    }
+
+    /// Store the counters of all interfaces.
+    pub fn set_counters(&self, counters: &BTreeMap<String, Counters>) -> Result<(), PeachError> {
+        let mut ifaces = BTreeMap::new();
+        for (iface, c) in counters {
+            let mut object = BTreeMap::new();
+            object.insert("boot_id".to_string(), Value::String(c.boot_id.clone()));
+            object.insert("rx".to_string(), Value::Uint(c.rx));
+            object.insert("tx".to_string(), Value::Uint(c.tx));
+            object.insert("total_rx".to_string(), Value::Uint(c.total_rx));
+            object.insert("total_tx".to_string(), Value::Uint(c.total_tx));
+            ifaces.insert(
+                iface.to_string(),
+                Value::Object(object.into_iter().collect()),
+            );
+        }
+        self.set_object(&["net", "traffic", "ifaces"], ifaces)
+    }
+
+    /* HISTORY */
+
+    /// Retrieve the start date (`YYYY-MM-DD`) of the current billing cycle.
+    pub fn cycle_start(&self) -> Option<String> {
+        self.get_string(&["net", "history", "cycle_start"])
+    }
+
+    /// Set the start date of the current billing cycle.
+    pub fn set_cycle_start(&self, start: &str) -> Result<(), PeachError> {
+        self.set(
+            &["net", "history", "cycle_start"],
+            Value::String(start.to_string()),
+        )
+    }
+
+    /// Retrieve the usage history.
+    pub fn history(&self) -> History {
+        History {
+            daily: self.buckets(Period::Daily),
+            monthly: self.buckets(Period::Monthly),
+            cycle: self.buckets(Period::Cycle),
+        }
+    }
+
+    /// Retrieve the buckets of one kind, ordered by period.
+    pub fn buckets(&self, period: Period) -> Vec<Bucket> {
+        let mut buckets = Vec::new();
+        for (name, value) in self.get_object(&["net", "history", period.key()]) {
+            if let Value::Object(bucket) = value {
+                let uint = |field: &str| match bucket.get(field) {
+                    Some(Value::Uint(val)) => *val,
+                    _ => 0,
+                };
+                buckets.push(Bucket {
+                    rx: ByteSize(uint("rx")),
+                    tx: ByteSize(uint("tx")),
+                    period: name,
+                });
+            }
+        }
+        buckets
+    }
+
+    /// Add traffic to the bucket for the named period (eg. `2021-11-04`),
+    /// dropping the oldest buckets beyond `retain`.
+    pub fn add_to_bucket(
+        &self,
+        period: Period,
+        name: &str,
+        rx: u64,
+        tx: u64,
+        retain: usize,
+    ) -> Result<(), PeachError> {
+        let mut buckets = self.get_object(&["net", "history", period.key()]);
+        let mut bucket = match buckets.remove(name) {
+            Some(Value::Object(bucket)) => bucket,
+            _ => Default::default(),
+        };
+        let uint = |field: &str| match bucket.get(field) {
+            Some(Value::Uint(val)) => *val,
+            _ => 0,
+        };
+        let (rx, tx) = (uint("rx") + rx, uint("tx") + tx);
+        bucket.insert("rx".to_string(), Value::Uint(rx));
+        bucket.insert("tx".to_string(), Value::Uint(tx));
+        buckets.insert(name.to_string(), Value::Object(bucket));
+
+        // periods are ISO dates, so lexical order is chronological order
+        while buckets.len() > retain {
+            let oldest = buckets.keys().next().cloned().unwrap_or_default();
+            buckets.remove(&oldest);
+        }
+
+        self.set_object(&["net", "history", period.key()], buckets)
+    }
+
+    /* HEALTH */
+
+    /// Retrieve the health alert thresholds and flags, falling back to the
+    /// defaults for any value which has not been set.
+    pub fn health_threshold(&self) -> HealthThreshold {
+        let default = HealthThreshold::default();
+        HealthThreshold {
+            disk: self.get_uint_or(&["health", "notify", "disk"], default.disk),
+            temp: self.get_uint_or(&["health", "notify", "temp"], default.temp),
+            mem: self.get_uint_or(&["health", "notify", "mem"], default.mem),
+            disk_flag: self.get_bool_or(&["health", "notify", "disk_flag"], default.disk_flag),
+            temp_flag: self.get_bool_or(&["health", "notify", "temp_flag"], default.temp_flag),
+            mem_flag: self.get_bool_or(&["health", "notify", "mem_flag"], default.mem_flag),
+            service_flag: self
+                .get_bool_or(&["health", "notify", "service_flag"], default.service_flag),
+        }
+    }
+
+    /// Store the health alert thresholds and flags.
+    pub fn set_health_threshold(&self, threshold: &HealthThreshold) -> Result<(), PeachError> {
+        self.set(&["health", "notify", "disk"], Value::Uint(threshold.disk))?;
+        self.set(&["health", "notify", "temp"], Value::Uint(threshold.temp))?;
+        self.set(&["health", "notify", "mem"], Value::Uint(threshold.mem))?;
+        self.set(
+            &["health", "notify", "disk_flag"],
+            Value::Bool(threshold.disk_flag),
+        )?;
+        self.set(
+            &["health", "notify", "temp_flag"],
+            Value::Bool(threshold.temp_flag),
+        )?;
+        self.set(
+            &["health", "notify", "mem_flag"],
+            Value::Bool(threshold.mem_flag),
+        )?;
+        self.set(
+            &["health", "notify", "service_flag"],
+            Value::Bool(threshold.service_flag),
+        )
+    }
+
+    /// Retrieve the health alert flags.
+    pub fn health_alert(&self) -> HealthAlert {
+        HealthAlert {
+            disk: self.get_bool(&["health", "alert", "disk_alert"]),
+            temp: self.get_bool(&["health", "alert", "temp_alert"]),
+            mem: self.get_bool(&["health", "alert", "mem_alert"]),
+            services: self.get_strings(&["health", "alert", "failed_services"]),
+        }
+    }
+
+    /// Store the health alert flags.
+    pub fn set_health_alert(&self, alert: &HealthAlert) -> Result<(), PeachError> {
+        self.set(&["health", "alert", "disk_alert"], Value::Bool(alert.disk))?;
+        self.set(&["health", "alert", "temp_alert"], Value::Bool(alert.temp))?;
+        self.set(&["health", "alert", "mem_alert"], Value::Bool(alert.mem))?;
+        self.set_strings(&["health", "alert", "failed_services"], &alert.services)
+    }
+
+    /// Retrieve the notification channels through which the named health
+    /// alert (`disk`, `temp` or `mem`) has been sent since it last turned on.
+    pub fn health_sent(&self, name: &str) -> Vec<String> {
+        self.get_strings(&["health", "alert", &format!("{}_sent", name)])
+    }
+
+    /// Record the notification channels through which the named health alert
+    /// has been sent.
+    pub fn set_health_sent(&self, name: &str, channels: &[String]) -> Result<(), PeachError> {
+        self.set_strings(&["health", "alert", &format!("{}_sent", name)], channels)
+    }
+
+    /// Retrieve the failed services which the user has been notified of,
+    /// keyed by notification channel.
+    pub fn services_sent(&self) -> BTreeMap<String, Vec<String>> {
+        self.get_object(&["health", "alert", "services_sent"])
+            .into_iter()
+            .map(|(channel, services)| {
+                let services = match services {
+                    Value::Array(services) => services
+                        .into_iter()
+                        .filter_map(|service| match service {
+                            Value::String(service) => Some(service),
+                            _ => None,
+                        })
+                        .collect(),
+                    _ => Vec::new(),
+                };
+                (channel, services)
+            })
+            .collect()
+    }
+
+    /// Record the failed services which the user has been notified of, keyed
+    /// by notification channel.
+    pub fn set_services_sent(
+        &self,
+        services: &BTreeMap<String, Vec<String>>,
+    ) -> Result<(), PeachError> {
+        let sent = services
+            .iter()
+            .map(|(channel, services)| {
+                let services = services.iter().cloned().map(Value::String).collect();
+                (channel.to_string(), Value::Array(services))
+            })
+            .collect();
+        self.set_object(&["health", "alert", "services_sent"], sent)
+    }
+}
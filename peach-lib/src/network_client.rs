@@ -19,6 +19,7 @@ use jsonrpc_client_http::HttpTransport;
 use log::{debug, info};
 use serde::{Deserialize, Serialize};
 
+use crate::byte_size::ByteSize;
 use crate::error::PeachError;
 use crate::stats_client::Traffic;
 
@@ -29,8 +30,31 @@ pub struct AccessPoint {
     pub state: String,
 }
 
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Interface {
+    pub name: String,
+    pub kind: String,
+    pub operstate: String,
+    pub mac: Option<String>,
+    pub ipv4: Vec<String>,
+    pub ipv6: Vec<String>,
+    pub speed: Option<u32>,
+    pub uplink: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct NetConfig {
+    pub iface: String,
+    pub dhcp: bool,
+    pub address: Option<String>,
+    pub gateway: Option<String>,
+    pub dns: Vec<String>,
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Networks {
+    pub id: Option<String>,
+    pub priority: Option<i32>,
     pub ssid: String,
 }
 
@@ -42,6 +66,17 @@ pub struct Scan {
     pub ssid: String,
 }
 
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Station {
+    pub mac: String,
+    pub ip: Option<String>,
+    pub hostname: Option<String>,
+    pub signal: Option<i32>,
+    pub connected_time: Option<u64>,
+    pub rx_bytes: Option<ByteSize>,
+    pub tx_bytes: Option<ByteSize>,
+}
+
 /// Creates a JSON-RPC client with http transport and calls the `peach-network`
 /// `activate_ap` method.
 pub fn activate_ap() -> std::result::Result<String, PeachError> {
@@ -147,6 +182,29 @@ pub fn connect(id: &str, iface: &str) -> std::result::Result<String, PeachError>
     Ok(response)
 }
 
+/// Creates a JSON-RPC client with http transport and calls the `peach-network`
+/// `deauthenticate` method, which disconnects a station from the access point.
+///
+/// # Arguments
+///
+/// * `iface` - A string slice containing the network interface identifier.
+/// * `mac` - A string slice containing the MAC address of a station.
+pub fn deauthenticate(iface: &str, mac: &str) -> std::result::Result<String, PeachError> {
+    debug!("Creating HTTP transport for network client.");
+    let transport = HttpTransport::new().standalone()?;
+    let http_addr =
+        env::var("PEACH_NETWORK_SERVER").unwrap_or_else(|_| "127.0.0.1:5110".to_string());
+    let http_server = format!("http://{}", http_addr);
+    debug!("Creating HTTP transport handle on {}.", http_server);
+    let transport_handle = transport.handle(&http_server)?;
+    info!("Creating client for peach_network service.");
+    let mut client = PeachNetworkClient::new(transport_handle);
+
+    let response = client.deauthenticate(iface, mac).call()?;
+
+    Ok(response)
+}
+
 /// Creates a JSON-RPC client with http transport and calls the `peach-network`
 /// `id` and `disable` methods.
 ///
@@ -253,6 +311,50 @@ pub fn ip(iface: &str) -> std::result::Result<String, PeachError> {
     Ok(response)
 }
 
+/// Creates a JSON-RPC client with http transport and calls the `peach-network`
+/// `list_interfaces` method, which returns the kind, state and addresses of
+/// every network interface.
+pub fn list_interfaces() -> std::result::Result<Vec<Interface>, PeachError> {
+    debug!("Creating HTTP transport for network client.");
+    let transport = HttpTransport::new().standalone()?;
+    let http_addr =
+        env::var("PEACH_NETWORK_SERVER").unwrap_or_else(|_| "127.0.0.1:5110".to_string());
+    let http_server = format!("http://{}", http_addr);
+    debug!("Creating HTTP transport handle on {}.", http_server);
+    let transport_handle = transport.handle(&http_server)?;
+    info!("Creating client for peach_network service.");
+    let mut client = PeachNetworkClient::new(transport_handle);
+
+    let response = client.list_interfaces().call()?;
+    let interfaces: Vec<Interface> = serde_json::from_str(&response)?;
+
+    Ok(interfaces)
+}
+
+/// Creates a JSON-RPC client with http transport and calls the `peach-network`
+/// `net_config` method, which returns the systemd-networkd configuration for
+/// the given interface.
+///
+/// # Arguments
+///
+/// * `iface` - A string slice containing the network interface identifier.
+pub fn net_config(iface: &str) -> std::result::Result<NetConfig, PeachError> {
+    debug!("Creating HTTP transport for network client.");
+    let transport = HttpTransport::new().standalone()?;
+    let http_addr =
+        env::var("PEACH_NETWORK_SERVER").unwrap_or_else(|_| "127.0.0.1:5110".to_string());
+    let http_server = format!("http://{}", http_addr);
+    debug!("Creating HTTP transport handle on {}.", http_server);
+    let transport_handle = transport.handle(&http_server)?;
+    info!("Creating client for peach_network service.");
+    let mut client = PeachNetworkClient::new(transport_handle);
+
+    let response = client.net_config(iface).call()?;
+    let config: NetConfig = serde_json::from_str(&response)?;
+
+    Ok(config)
+}
+
 /// Creates a JSON-RPC client with http transport and calls the `peach-network`
 /// `ping` method, which serves as a means of determining availability of the
 /// microservice (ie. there will be no response if `peach-network` is not
@@ -290,6 +392,43 @@ pub fn reconfigure() -> std::result::Result<String, PeachError> {
     Ok(response)
 }
 
+/// Creates a JSON-RPC client with http transport and calls the `peach-network`
+/// `id`, `reorder` and `save` methods. The saved networks are assigned
+/// descending priorities in the order given, so that the first SSID becomes
+/// the preferred network.
+///
+/// # Arguments
+///
+/// * `iface` - A string slice containing the network interface identifier.
+/// * `ssids` - A slice of SSIDs, ordered from most to least preferred.
+pub fn reorder(iface: &str, ssids: &[String]) -> std::result::Result<String, PeachError> {
+    debug!("Creating HTTP transport for network client.");
+    let transport = HttpTransport::new().standalone()?;
+    let http_addr =
+        env::var("PEACH_NETWORK_SERVER").unwrap_or_else(|_| "127.0.0.1:5110".to_string());
+    let http_server = format!("http://{}", http_addr);
+    debug!("Creating HTTP transport handle on {}.", http_server);
+    let transport_handle = transport.handle(&http_server)?;
+    info!("Creating client for peach_network service.");
+    let mut client = PeachNetworkClient::new(transport_handle);
+
+    // map each ssid to its network id, retaining the given order
+    let mut ids = Vec::new();
+    for ssid in ssids {
+        info!("Performing id call to peach-network microservice.");
+        let id = client.id(iface, ssid).call()?;
+        ids.push(id);
+    }
+    info!("Performing reorder call to peach-network microservice.");
+    client.reorder(iface, &ids).call()?;
+    info!("Performing save call to peach-network microservice.");
+    client.save().call()?;
+
+    let response = "success".to_string();
+
+    Ok(response)
+}
+
 /// Creates a JSON-RPC client with http transport and calls the `peach-network`
 /// `rssi` method.
 ///
@@ -395,6 +534,100 @@ pub fn saved_networks() -> std::result::Result<String, PeachError> {
     Ok(response)
 }
 
+/// Creates a JSON-RPC client with http transport and calls the `peach-network`
+/// `set_cutoff` method, which blocks or restores traffic between the device and
+/// the internet. The device remains reachable from the LAN and the access point
+/// while traffic is blocked.
+///
+/// # Arguments
+///
+/// * `cut` - A boolean; `true` to block internet traffic and `false` to restore it.
+pub fn set_cutoff(cut: bool) -> std::result::Result<String, PeachError> {
+    debug!("Creating HTTP transport for network client.");
+    let transport = HttpTransport::new().standalone()?;
+    let http_addr =
+        env::var("PEACH_NETWORK_SERVER").unwrap_or_else(|_| "127.0.0.1:5110".to_string());
+    let http_server = format!("http://{}", http_addr);
+    debug!("Creating HTTP transport handle on {}.", http_server);
+    let transport_handle = transport.handle(&http_server)?;
+    info!("Creating client for peach_network service.");
+    let mut client = PeachNetworkClient::new(transport_handle);
+
+    let response = client.set_cutoff(cut).call()?;
+
+    Ok(response)
+}
+
+/// Creates a JSON-RPC client with http transport and calls the `peach-network`
+/// `set_net_config` method, which writes the systemd-networkd configuration
+/// for an interface. The method returns once systemd-networkd has been
+/// restarted; the previous configuration is restored by `peach-network` if
+/// connectivity is not confirmed within `timeout` seconds.
+///
+/// # Arguments
+///
+/// * `config` - A reference to the `NetConfig` to be applied.
+/// * `timeout` - Number of seconds to wait for connectivity to be confirmed.
+pub fn set_net_config(config: &NetConfig, timeout: u64) -> std::result::Result<String, PeachError> {
+    debug!("Creating HTTP transport for network client.");
+    let transport = HttpTransport::new().standalone()?;
+    let http_addr =
+        env::var("PEACH_NETWORK_SERVER").unwrap_or_else(|_| "127.0.0.1:5110".to_string());
+    let http_server = format!("http://{}", http_addr);
+    debug!("Creating HTTP transport handle on {}.", http_server);
+    let transport_handle = transport.handle(&http_server)?;
+    info!("Creating client for peach_network service.");
+    let mut client = PeachNetworkClient::new(transport_handle);
+
+    let response = client
+        .set_net_config(
+            &config.iface,
+            config.dhcp,
+            config.address.as_deref(),
+            config.gateway.as_deref(),
+            &config.dns,
+            timeout,
+        )
+        .call()?;
+
+    Ok(response)
+}
+
+/// Creates a JSON-RPC client with http transport and calls the `peach-network`
+/// `id`, `set_priority` and `save` methods.
+///
+/// # Arguments
+///
+/// * `iface` - A string slice containing the network interface identifier.
+/// * `ssid` - A string slice containing the SSID of a network.
+/// * `priority` - An integer priority; higher values are preferred.
+pub fn set_priority(
+    iface: &str,
+    ssid: &str,
+    priority: i32,
+) -> std::result::Result<String, PeachError> {
+    debug!("Creating HTTP transport for network client.");
+    let transport = HttpTransport::new().standalone()?;
+    let http_addr =
+        env::var("PEACH_NETWORK_SERVER").unwrap_or_else(|_| "127.0.0.1:5110".to_string());
+    let http_server = format!("http://{}", http_addr);
+    debug!("Creating HTTP transport handle on {}.", http_server);
+    let transport_handle = transport.handle(&http_server)?;
+    info!("Creating client for peach_network service.");
+    let mut client = PeachNetworkClient::new(transport_handle);
+
+    info!("Performing id call to peach-network microservice.");
+    let id = client.id(iface, ssid).call()?;
+    info!("Performing set_priority call to peach-network microservice.");
+    client.set_priority(iface, &id, priority).call()?;
+    info!("Performing save call to peach-network microservice.");
+    client.save().call()?;
+
+    let response = "success".to_string();
+
+    Ok(response)
+}
+
 /// Creates a JSON-RPC client with http transport and calls the `peach-network`
 /// `ssid` method.
 ///
@@ -439,6 +672,30 @@ pub fn state(iface: &str) -> std::result::Result<String, PeachError> {
     Ok(response)
 }
 
+/// Creates a JSON-RPC client with http transport and calls the `peach-network`
+/// `stations` method, which returns the stations associated with the access
+/// point.
+///
+/// # Arguments
+///
+/// * `iface` - A string slice containing the network interface identifier.
+pub fn stations(iface: &str) -> std::result::Result<Vec<Station>, PeachError> {
+    debug!("Creating HTTP transport for network client.");
+    let transport = HttpTransport::new().standalone()?;
+    let http_addr =
+        env::var("PEACH_NETWORK_SERVER").unwrap_or_else(|_| "127.0.0.1:5110".to_string());
+    let http_server = format!("http://{}", http_addr);
+    debug!("Creating HTTP transport handle on {}.", http_server);
+    let transport_handle = transport.handle(&http_server)?;
+    info!("Creating client for peach_network service.");
+    let mut client = PeachNetworkClient::new(transport_handle);
+
+    let response = client.stations(iface).call()?;
+    let stations: Vec<Station> = serde_json::from_str(&response)?;
+
+    Ok(stations)
+}
+
 /// Creates a JSON-RPC client with http transport and calls the `peach-network`
 /// `status` method.
 ///
@@ -528,6 +785,31 @@ pub fn update(iface: &str, ssid: &str, pass: &str) -> std::result::Result<String
     Ok(response)
 }
 
+/// Creates a JSON-RPC client with http transport and calls the `peach-network`
+/// `uplink` method, which returns the name of the interface holding the
+/// default route.
+pub fn uplink() -> std::result::Result<String, PeachError> {
+    debug!("Creating HTTP transport for network client.");
+    let transport = HttpTransport::new().standalone()?;
+    let http_addr =
+        env::var("PEACH_NETWORK_SERVER").unwrap_or_else(|_| "127.0.0.1:5110".to_string());
+    let http_server = format!("http://{}", http_addr);
+    debug!("Creating HTTP transport handle on {}.", http_server);
+    let transport_handle = transport.handle(&http_server)?;
+    info!("Creating client for peach_network service.");
+    let mut client = PeachNetworkClient::new(transport_handle);
+
+    let response = client.uplink().call()?;
+
+    Ok(response)
+}
+
+/// Returns the name of the interface currently providing uplink, falling back
+/// to `wlan0` if `peach-network` is unavailable or no default route exists.
+pub fn uplink_iface() -> String {
+    uplink().unwrap_or_else(|_| "wlan0".to_string())
+}
+
 jsonrpc_client!(pub struct PeachNetworkClient {
     /// JSON-RPC request to activate the access point.
     pub fn activate_ap(&mut self) -> RpcRequest<String>;
@@ -544,6 +826,9 @@ jsonrpc_client!(pub struct PeachNetworkClient {
     /// JSON-RPC request to connect the network for the given interface and ID.
     pub fn connect(&mut self, id: &str, iface: &str) -> RpcRequest<String>;
 
+    /// JSON-RPC request to deauthenticate a station from the access point.
+    pub fn deauthenticate(&mut self, iface: &str, mac: &str) -> RpcRequest<String>;
+
     /// JSON-RPC request to delete the credentials for the given network from the wpa_supplicant config.
     pub fn delete(&mut self, id: &str, iface: &str) -> RpcRequest<String>;
 
@@ -562,6 +847,12 @@ jsonrpc_client!(pub struct PeachNetworkClient {
     /// JSON-RPC request to set a new network password for the given interface and ID.
     //pub fn modify(&mut self, id: &str, iface: &str, pass: &str) -> RpcRequest<String>;
 
+    /// JSON-RPC request to list all network interfaces.
+    pub fn list_interfaces(&mut self) -> RpcRequest<String>;
+
+    /// JSON-RPC request to get the systemd-networkd configuration for the given interface.
+    pub fn net_config(&mut self, iface: &str) -> RpcRequest<String>;
+
     /// JSON-RPC request to check peach-network availability.
     pub fn ping(&mut self) -> RpcRequest<String>;
 
@@ -571,6 +862,9 @@ jsonrpc_client!(pub struct PeachNetworkClient {
     /// JSON-RPC request to reconnect WiFi for the given interface.
     //pub fn reconnect(&mut self, iface: &str) -> RpcRequest<String>;
 
+    /// JSON-RPC request to set descending priorities for the given interface and ordered list of IDs.
+    pub fn reorder(&mut self, iface: &str, ids: &[String]) -> RpcRequest<String>;
+
     /// JSON-RPC request to get the average signal strength (dBm) for the given interface.
     pub fn rssi(&mut self, iface: &str) -> RpcRequest<String>;
 
@@ -583,15 +877,30 @@ jsonrpc_client!(pub struct PeachNetworkClient {
     /// JSON-RPC request to list all networks saved in `wpa_supplicant.conf`.
     pub fn saved_networks(&mut self) -> RpcRequest<String>;
 
+    /// JSON-RPC request to block or restore internet traffic.
+    pub fn set_cutoff(&mut self, cut: bool) -> RpcRequest<String>;
+
+    /// JSON-RPC request to write the systemd-networkd configuration for the given interface.
+    pub fn set_net_config(&mut self, iface: &str, dhcp: bool, address: Option<&str>, gateway: Option<&str>, dns: &[String], timeout: u64) -> RpcRequest<String>;
+
+    /// JSON-RPC request to set the priority of the network for the given interface and ID.
+    pub fn set_priority(&mut self, iface: &str, id: &str, priority: i32) -> RpcRequest<String>;
+
     /// JSON-RPC request to get the SSID of the currently-connected network for the given interface.
     pub fn ssid(&mut self, iface: &str) -> RpcRequest<String>;
 
     /// JSON-RPC request to get the state for the given interface.
     pub fn state(&mut self, iface: &str) -> RpcRequest<String>;
 
+    /// JSON-RPC request to list the stations associated with the access point.
+    pub fn stations(&mut self, iface: &str) -> RpcRequest<String>;
+
     /// JSON-RPC request to get the status of the given interface.
     pub fn status(&mut self, iface: &str) -> RpcRequest<String>;
 
     /// JSON-RPC request to get the network traffic for the given interface.
     pub fn traffic(&mut self, iface: &str) -> RpcRequest<String>;
+
+    /// JSON-RPC request to get the name of the interface providing uplink.
+    pub fn uplink(&mut self) -> RpcRequest<String>;
 });
@@ -3,9 +3,211 @@ use std::env;
 use jsonrpc_client_core::{expand_params, jsonrpc_client};
 use jsonrpc_client_http::HttpTransport;
 use log::{debug, info};
+use serde::{Deserialize, Serialize};
 
 use crate::error::PeachError;
 
+/// Name of the layer in which alerts are shown, above the menu. The layer is
+/// removed when the alert is dismissed.
+pub const ALERT_LAYER: &str = "alert";
+
+/// The frame currently shown on the OLED display.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Snapshot {
+    /// `bitmap` (8 pixels per byte, most significant bit first, row by row)
+    /// or `png`.
+    pub format: String,
+    pub width: u32,
+    pub height: u32,
+    /// Whether the display is turned on.
+    pub on: bool,
+    pub bytes: Vec<u8>,
+}
+
+/// Creates a JSON-RPC client with http transport and calls the `peach-oled`
+/// `chart` method.
+///
+/// # Arguments
+///
+/// * `x_coord` - A 32 byte signed int.
+/// * `y_coord` - A 32 byte signed int.
+/// * `width` - A 32 byte unsigned int.
+/// * `height` - A 32 byte unsigned int.
+/// * `values` - A Vec of 32 byte floats.
+/// * `style` - A string slice: `bar` or `sparkline`
+/// * `max` - An optional 32 byte float (bar charts are scaled to the largest value if `None`)
+pub fn chart(
+    x_coord: i32,
+    y_coord: i32,
+    width: u32,
+    height: u32,
+    values: Vec<f32>,
+    style: &str,
+    max: Option<f32>,
+) -> std::result::Result<(), PeachError> {
+    debug!("Creating HTTP transport for OLED client.");
+    let transport = HttpTransport::new().standalone()?;
+    let http_addr = env::var("PEACH_OLED_SERVER").unwrap_or_else(|_| "127.0.0.1:5112".to_string());
+    let http_server = format!("http://{}", http_addr);
+    debug!("Creating HTTP transport handle on {}.", http_server);
+    let transport_handle = transport.handle(&http_server)?;
+    info!("Creating client for peach_oled service.");
+    let mut client = PeachOledClient::new(transport_handle);
+
+    client
+        .chart(x_coord, y_coord, width, height, values, style, max)
+        .call()?;
+    debug!("Drew a chart to the OLED display.");
+
+    Ok(())
+}
+
+/// Creates a JSON-RPC client with http transport and calls the `peach-oled`
+/// `circle` method.
+///
+/// # Arguments
+///
+/// * `x_coord` - A 32 byte signed int.
+/// * `y_coord` - A 32 byte signed int.
+/// * `radius` - A 32 byte unsigned int.
+/// * `filled` - A boolean expression
+pub fn circle(
+    x_coord: i32,
+    y_coord: i32,
+    radius: u32,
+    filled: bool,
+) -> std::result::Result<(), PeachError> {
+    debug!("Creating HTTP transport for OLED client.");
+    let transport = HttpTransport::new().standalone()?;
+    let http_addr = env::var("PEACH_OLED_SERVER").unwrap_or_else(|_| "127.0.0.1:5112".to_string());
+    let http_server = format!("http://{}", http_addr);
+    debug!("Creating HTTP transport handle on {}.", http_server);
+    let transport_handle = transport.handle(&http_server)?;
+    info!("Creating client for peach_oled service.");
+    let mut client = PeachOledClient::new(transport_handle);
+
+    client.circle(x_coord, y_coord, radius, filled).call()?;
+    debug!("Drew a circle to the OLED display.");
+
+    Ok(())
+}
+
+/// A draw operation in a scene rendered by the `peach-oled` `render` method.
+/// Each operation takes the same parameters as the equivalent method.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum Op {
+    Chart {
+        x_coord: i32,
+        y_coord: i32,
+        width: u32,
+        height: u32,
+        values: Vec<f32>,
+        style: String,
+        max: Option<f32>,
+    },
+    Circle {
+        x_coord: i32,
+        y_coord: i32,
+        radius: u32,
+        filled: bool,
+    },
+    Draw {
+        bytes: Vec<u8>,
+        width: u32,
+        height: u32,
+        x_coord: i32,
+        y_coord: i32,
+    },
+    Line {
+        x0: i32,
+        y0: i32,
+        x1: i32,
+        y1: i32,
+    },
+    Progress {
+        x_coord: i32,
+        y_coord: i32,
+        width: u32,
+        height: u32,
+        percent: u8,
+    },
+    Rectangle {
+        x_coord: i32,
+        y_coord: i32,
+        width: u32,
+        height: u32,
+        filled: bool,
+    },
+    Text {
+        x_coord: i32,
+        y_coord: i32,
+        width: u32,
+        height: u32,
+        string: String,
+        font_size: String,
+        overflow: String,
+        offset: usize,
+    },
+    Write {
+        x_coord: i32,
+        y_coord: i32,
+        string: String,
+        font_size: String,
+    },
+    WriteInverted {
+        x_coord: i32,
+        y_coord: i32,
+        string: String,
+        font_size: String,
+    },
+}
+
+impl Op {
+    /// Write a message within the given box, with text which does not fit on
+    /// one line either wrapped (`wrap`) or scrolled (`scroll`).
+    pub fn text(
+        x_coord: i32,
+        y_coord: i32,
+        width: u32,
+        height: u32,
+        string: &str,
+        font_size: &str,
+        overflow: &str,
+    ) -> Op {
+        Op::Text {
+            x_coord,
+            y_coord,
+            width,
+            height,
+            string: string.to_string(),
+            font_size: font_size.to_string(),
+            overflow: overflow.to_string(),
+            offset: 0,
+        }
+    }
+
+    /// Write a message at the given co-ordinates using the given font size.
+    pub fn write(x_coord: i32, y_coord: i32, string: &str, font_size: &str) -> Op {
+        Op::Write {
+            x_coord,
+            y_coord,
+            string: string.to_string(),
+            font_size: font_size.to_string(),
+        }
+    }
+
+    /// Write a message as dark text on a lit background.
+    pub fn write_inverted(x_coord: i32, y_coord: i32, string: &str, font_size: &str) -> Op {
+        Op::WriteInverted {
+            x_coord,
+            y_coord,
+            string: string.to_string(),
+            font_size: font_size.to_string(),
+        }
+    }
+}
+
 /// Creates a JSON-RPC client with http transport and calls the `peach-oled`
 /// `clear` method.
 pub fn clear() -> std::result::Result<(), PeachError> {
@@ -24,6 +226,28 @@ pub fn clear() -> std::result::Result<(), PeachError> {
     Ok(())
 }
 
+/// Creates a JSON-RPC client with http transport and calls the `peach-oled`
+/// `contrast` method.
+///
+/// # Arguments
+///
+/// * `level` - An 8 byte unsigned int (0-255).
+pub fn contrast(level: u8) -> std::result::Result<(), PeachError> {
+    debug!("Creating HTTP transport for OLED client.");
+    let transport = HttpTransport::new().standalone()?;
+    let http_addr = env::var("PEACH_OLED_SERVER").unwrap_or_else(|_| "127.0.0.1:5112".to_string());
+    let http_server = format!("http://{}", http_addr);
+    debug!("Creating HTTP transport handle on {}.", http_server);
+    let transport_handle = transport.handle(&http_server)?;
+    info!("Creating client for peach_oled service.");
+    let mut client = PeachOledClient::new(transport_handle);
+
+    client.contrast(level).call()?;
+    debug!("Set the contrast of the OLED display.");
+
+    Ok(())
+}
+
 /// Creates a JSON-RPC client with http transport and calls the `peach-oled`
 /// `draw` method.
 ///
@@ -74,6 +298,31 @@ pub fn flush() -> std::result::Result<(), PeachError> {
     Ok(())
 }
 
+/// Creates a JSON-RPC client with http transport and calls the `peach-oled`
+/// `line` method.
+///
+/// # Arguments
+///
+/// * `x0` - A 32 byte signed int.
+/// * `y0` - A 32 byte signed int.
+/// * `x1` - A 32 byte signed int.
+/// * `y1` - A 32 byte signed int.
+pub fn line(x0: i32, y0: i32, x1: i32, y1: i32) -> std::result::Result<(), PeachError> {
+    debug!("Creating HTTP transport for OLED client.");
+    let transport = HttpTransport::new().standalone()?;
+    let http_addr = env::var("PEACH_OLED_SERVER").unwrap_or_else(|_| "127.0.0.1:5112".to_string());
+    let http_server = format!("http://{}", http_addr);
+    debug!("Creating HTTP transport handle on {}.", http_server);
+    let transport_handle = transport.handle(&http_server)?;
+    info!("Creating client for peach_oled service.");
+    let mut client = PeachOledClient::new(transport_handle);
+
+    client.line(x0, y0, x1, y1).call()?;
+    debug!("Drew a line to the OLED display.");
+
+    Ok(())
+}
+
 /// Creates a JSON-RPC client with http transport and calls the `peach-oled`
 /// `ping` method.
 pub fn ping() -> std::result::Result<(), PeachError> {
@@ -114,6 +363,159 @@ pub fn power(on: bool) -> std::result::Result<(), PeachError> {
     Ok(())
 }
 
+/// Creates a JSON-RPC client with http transport and calls the `peach-oled`
+/// `progress` method.
+///
+/// # Arguments
+///
+/// * `x_coord` - A 32 byte signed int.
+/// * `y_coord` - A 32 byte signed int.
+/// * `width` - A 32 byte unsigned int.
+/// * `height` - A 32 byte unsigned int.
+/// * `percent` - An 8 byte unsigned int (0-100).
+pub fn progress(
+    x_coord: i32,
+    y_coord: i32,
+    width: u32,
+    height: u32,
+    percent: u8,
+) -> std::result::Result<(), PeachError> {
+    debug!("Creating HTTP transport for OLED client.");
+    let transport = HttpTransport::new().standalone()?;
+    let http_addr = env::var("PEACH_OLED_SERVER").unwrap_or_else(|_| "127.0.0.1:5112".to_string());
+    let http_server = format!("http://{}", http_addr);
+    debug!("Creating HTTP transport handle on {}.", http_server);
+    let transport_handle = transport.handle(&http_server)?;
+    info!("Creating client for peach_oled service.");
+    let mut client = PeachOledClient::new(transport_handle);
+
+    client
+        .progress(x_coord, y_coord, width, height, percent)
+        .call()?;
+    debug!("Drew a progress bar to the OLED display.");
+
+    Ok(())
+}
+
+/// Creates a JSON-RPC client with http transport and calls the `peach-oled`
+/// `rectangle` method.
+///
+/// # Arguments
+///
+/// * `x_coord` - A 32 byte signed int.
+/// * `y_coord` - A 32 byte signed int.
+/// * `width` - A 32 byte unsigned int.
+/// * `height` - A 32 byte unsigned int.
+/// * `filled` - A boolean expression
+pub fn rectangle(
+    x_coord: i32,
+    y_coord: i32,
+    width: u32,
+    height: u32,
+    filled: bool,
+) -> std::result::Result<(), PeachError> {
+    debug!("Creating HTTP transport for OLED client.");
+    let transport = HttpTransport::new().standalone()?;
+    let http_addr = env::var("PEACH_OLED_SERVER").unwrap_or_else(|_| "127.0.0.1:5112".to_string());
+    let http_server = format!("http://{}", http_addr);
+    debug!("Creating HTTP transport handle on {}.", http_server);
+    let transport_handle = transport.handle(&http_server)?;
+    info!("Creating client for peach_oled service.");
+    let mut client = PeachOledClient::new(transport_handle);
+
+    client
+        .rectangle(x_coord, y_coord, width, height, filled)
+        .call()?;
+    debug!("Drew a rectangle to the OLED display.");
+
+    Ok(())
+}
+
+/// Creates a JSON-RPC client with http transport and calls the `peach-oled`
+/// `render` method: the given operations replace the contents of the layer,
+/// then all layers are drawn and flushed to the display in a single request.
+///
+/// # Arguments
+///
+/// * `layer` - A string slice naming the layer (e.g. `menu`)
+/// * `z` - A 32 byte signed int: layers with a higher index are drawn on top
+/// * `ops` - A Vec of draw operations (an empty Vec removes the layer)
+pub fn render(layer: &str, z: i32, ops: Vec<Op>) -> std::result::Result<(), PeachError> {
+    debug!("Creating HTTP transport for OLED client.");
+    let transport = HttpTransport::new().standalone()?;
+    let http_addr = env::var("PEACH_OLED_SERVER").unwrap_or_else(|_| "127.0.0.1:5112".to_string());
+    let http_server = format!("http://{}", http_addr);
+    debug!("Creating HTTP transport handle on {}.", http_server);
+    let transport_handle = transport.handle(&http_server)?;
+    info!("Creating client for peach_oled service.");
+    let mut client = PeachOledClient::new(transport_handle);
+
+    client.render(layer, z, ops).call()?;
+    debug!("Rendered the {} layer to the OLED display.", layer);
+
+    Ok(())
+}
+
+/// Creates a JSON-RPC client with http transport and calls the `peach-oled`
+/// `snapshot` method.
+///
+/// # Arguments
+///
+/// * `format` - A string slice: `bitmap` or `png`
+pub fn snapshot(format: &str) -> std::result::Result<Snapshot, PeachError> {
+    debug!("Creating HTTP transport for OLED client.");
+    let transport = HttpTransport::new().standalone()?;
+    let http_addr = env::var("PEACH_OLED_SERVER").unwrap_or_else(|_| "127.0.0.1:5112".to_string());
+    let http_server = format!("http://{}", http_addr);
+    debug!("Creating HTTP transport handle on {}.", http_server);
+    let transport_handle = transport.handle(&http_server)?;
+    info!("Creating client for peach_oled service.");
+    let mut client = PeachOledClient::new(transport_handle);
+
+    let snapshot = client.snapshot(format).call()?;
+    debug!("Took a snapshot of the OLED display.");
+
+    Ok(snapshot)
+}
+
+/// Creates a JSON-RPC client with http transport and calls the `peach-oled`
+/// `text` method.
+///
+/// # Arguments
+///
+/// * `x_coord` - A 32 byte signed int.
+/// * `y_coord` - A 32 byte signed int.
+/// * `width` - A 32 byte unsigned int.
+/// * `height` - A 32 byte unsigned int.
+/// * `string` - A reference to a string slice
+/// * `font_size` - A reference to a string slice
+/// * `overflow` - A string slice: `wrap` or `scroll`
+pub fn text(
+    x_coord: i32,
+    y_coord: i32,
+    width: u32,
+    height: u32,
+    string: &str,
+    font_size: &str,
+    overflow: &str,
+) -> std::result::Result<(), PeachError> {
+    debug!("Creating HTTP transport for OLED client.");
+    let transport = HttpTransport::new().standalone()?;
+    let http_addr = env::var("PEACH_OLED_SERVER").unwrap_or_else(|_| "127.0.0.1:5112".to_string());
+    let http_server = format!("http://{}", http_addr);
+    debug!("Creating HTTP transport handle on {}.", http_server);
+    let transport_handle = transport.handle(&http_server)?;
+    info!("Creating client for peach_oled service.");
+    let mut client = PeachOledClient::new(transport_handle);
+
+    client
+        .text(x_coord, y_coord, width, height, string, font_size, overflow)
+        .call()?;
+    debug!("Wrote a text box to the OLED display.");
+
+    Ok(())
+}
+
 /// Creates a JSON-RPC client with http transport and calls the `peach-oled`
 /// `draw` method.
 ///
@@ -144,22 +546,86 @@ pub fn write(
     Ok("success".to_string())
 }
 
+/// Creates a JSON-RPC client with http transport and calls the `peach-oled`
+/// `write_inverted` method.
+///
+/// # Arguments
+///
+/// * `x_coord` - A 32 byte signed int.
+/// * `y_coord` - A 32 byte signed int.
+/// * `string` - A reference to a string slice
+/// * `font_size` - A reference to a string slice
+pub fn write_inverted(
+    x_coord: i32,
+    y_coord: i32,
+    string: &str,
+    font_size: &str,
+) -> std::result::Result<(), PeachError> {
+    debug!("Creating HTTP transport for OLED client.");
+    let transport = HttpTransport::new().standalone()?;
+    let http_addr = env::var("PEACH_OLED_SERVER").unwrap_or_else(|_| "127.0.0.1:5112".to_string());
+    let http_server = format!("http://{}", http_addr);
+    debug!("Creating HTTP transport handle on {}.", http_server);
+    let transport_handle = transport.handle(&http_server)?;
+    info!("Creating client for peach_oled service.");
+    let mut client = PeachOledClient::new(transport_handle);
+
+    client
+        .write_inverted(x_coord, y_coord, string, font_size)
+        .call()?;
+    debug!("Wrote inverted text to the OLED display.");
+
+    Ok(())
+}
+
 jsonrpc_client!(pub struct PeachOledClient {
+    /// Creates a JSON-RPC request to draw a chart to the OLED display.
+    #[allow(clippy::too_many_arguments)]
+    pub fn chart(&mut self, x_coord: i32, y_coord: i32, width: u32, height: u32, values: Vec<f32>, style: &str, max: Option<f32>) -> RpcRequest<String>;
+
+    /// Creates a JSON-RPC request to draw a circle to the OLED display.
+    pub fn circle(&mut self, x_coord: i32, y_coord: i32, radius: u32, filled: bool) -> RpcRequest<String>;
+
     /// Creates a JSON-RPC request to clear the OLED display.
     pub fn clear(&mut self) -> RpcRequest<String>;
 
+    /// Creates a JSON-RPC request to set the contrast of the OLED display.
+    pub fn contrast(&mut self, level: u8) -> RpcRequest<String>;
+
     /// Creates a JSON-RPC request to draw to the OLED display.
     pub fn draw(&mut self, bytes: Vec<u8>, width: u32, height: u32, x_coord: i32, y_coord: i32) -> RpcRequest<String>;
 
     /// Creates a JSON-RPC request to flush the OLED display.
     pub fn flush(&mut self) -> RpcRequest<String>;
 
+    /// Creates a JSON-RPC request to draw a line to the OLED display.
+    pub fn line(&mut self, x0: i32, y0: i32, x1: i32, y1: i32) -> RpcRequest<String>;
+
     /// Creates a JSON-RPC request to ping the OLED microservice.
     pub fn ping(&mut self) -> RpcRequest<String>;
 
 /// Creates a JSON-RPC request to toggle the power of the OLED display.
     pub fn power(&mut self, on: bool) -> RpcRequest<String>;
 
+    /// Creates a JSON-RPC request to draw a progress bar to the OLED display.
+    pub fn progress(&mut self, x_coord: i32, y_coord: i32, width: u32, height: u32, percent: u8) -> RpcRequest<String>;
+
+    /// Creates a JSON-RPC request to draw a rectangle to the OLED display.
+    pub fn rectangle(&mut self, x_coord: i32, y_coord: i32, width: u32, height: u32, filled: bool) -> RpcRequest<String>;
+
+    /// Creates a JSON-RPC request to render a scene to the OLED display.
+    pub fn render(&mut self, layer: &str, z: i32, ops: Vec<Op>) -> RpcRequest<String>;
+
+    /// Creates a JSON-RPC request to take a snapshot of the OLED display.
+    pub fn snapshot(&mut self, format: &str) -> RpcRequest<Snapshot>;
+
+    /// Creates a JSON-RPC request to write a text box to the OLED display.
+    #[allow(clippy::too_many_arguments)]
+    pub fn text(&mut self, x_coord: i32, y_coord: i32, width: u32, height: u32, string: &str, font_size: &str, overflow: &str) -> RpcRequest<String>;
+
     /// Creates a JSON-RPC request to write to the OLED display.
     pub fn write(&mut self, x_coord: i32, y_coord: i32, string: &str, font_size: &str) -> RpcRequest<String>;
+
+    /// Creates a JSON-RPC request to write inverted text to the OLED display.
+    pub fn write_inverted(&mut self, x_coord: i32, y_coord: i32, string: &str, font_size: &str) -> RpcRequest<String>;
 });
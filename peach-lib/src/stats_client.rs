@@ -13,6 +13,7 @@ use jsonrpc_client_http::HttpTransport;
 use log::{debug, info};
 use serde::{Deserialize, Serialize};
 
+use crate::byte_size::{deserialize_kib, ByteSize};
 use crate::error::PeachError;
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -31,12 +32,33 @@ pub struct CpuStatPercentages {
     pub nice: f32,
 }
 
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CpuFreq {
+    pub cpu: String,
+    pub cur_mhz: u32,
+    pub min_mhz: u32,
+    pub max_mhz: u32,
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 pub struct DiskUsage {
     pub filesystem: Option<String>,
-    pub one_k_blocks: u64,
-    pub one_k_blocks_used: u64,
-    pub one_k_blocks_free: u64,
+    // `peach-stats` reports sizes in 1K blocks
+    #[serde(
+        rename(deserialize = "one_k_blocks"),
+        deserialize_with = "deserialize_kib"
+    )]
+    pub size: ByteSize,
+    #[serde(
+        rename(deserialize = "one_k_blocks_used"),
+        deserialize_with = "deserialize_kib"
+    )]
+    pub used: ByteSize,
+    #[serde(
+        rename(deserialize = "one_k_blocks_free"),
+        deserialize_with = "deserialize_kib"
+    )]
+    pub free: ByteSize,
     pub used_percentage: u32,
     pub mountpoint: String,
 }
@@ -56,11 +78,53 @@ pub struct MemStat {
 }
 
 #[derive(Debug, Deserialize, Serialize)]
+pub struct Point {
+    pub time: u64,
+    pub value: f32,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Series {
+    pub metric: String,
+    pub resolution: u64,
+    pub points: Vec<Point>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ServiceStat {
+    pub name: String,
+    pub state: String,
+    pub processes: u32,
+    pub cpu_percent: f32,
+    pub rss: u64,
+    pub fds: Option<u32>,
+    pub restarts: Option<u32>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Temperature {
+    pub zone: String,
+    pub kind: String,
+    pub celsius: f32,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Throttled {
+    pub under_voltage: bool,
+    pub freq_capped: bool,
+    pub throttled: bool,
+    pub soft_temp_limit: bool,
+    pub under_voltage_occurred: bool,
+    pub freq_capped_occurred: bool,
+    pub throttled_occurred: bool,
+    pub soft_temp_limit_occurred: bool,
+    pub bits: u32,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct Traffic {
-    pub received: u64,
-    pub transmitted: u64,
-    pub rx_unit: Option<String>,
-    pub tx_unit: Option<String>,
+    pub received: ByteSize,
+    pub transmitted: ByteSize,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -87,9 +151,27 @@ pub fn cpu_stats_percent() -> std::result::Result<CpuStatPercentages, PeachError
     Ok(c)
 }
 
+/// Creates a JSON-RPC client with http transport and calls the `peach-stats`
+/// `cpu_freq` method.
+pub fn cpu_freq() -> std::result::Result<Vec<CpuFreq>, PeachError> {
+    debug!("Creating HTTP transport for stats client.");
+    let transport = HttpTransport::new().standalone()?;
+    let http_addr = env::var("PEACH_STATS_SERVER").unwrap_or_else(|_| "127.0.0.1:5113".to_string());
+    let http_server = format!("http://{}", http_addr);
+    debug!("Creating HTTP transport handle on {}.", http_server);
+    let transport_handle = transport.handle(&http_server)?;
+    info!("Creating client for peach_stats service.");
+    let mut client = PeachStatsClient::new(transport_handle);
+
+    let response = client.cpu_freq().call()?;
+    let f: Vec<CpuFreq> = serde_json::from_str(&response)?;
+
+    Ok(f)
+}
+
 /// Creates a JSON-RPC client with http transport and calls the `peach-stats`
 /// `disk_usage` method.
-pub fn disk_usage() -> std::result::Result<String, PeachError> {
+pub fn disk_usage() -> std::result::Result<Vec<DiskUsage>, PeachError> {
     debug!("Creating HTTP transport for stats client.");
     let transport = HttpTransport::new().standalone()?;
     let http_addr = env::var("PEACH_STATS_SERVER").unwrap_or_else(|_| "127.0.0.1:5113".to_string());
@@ -100,8 +182,29 @@ pub fn disk_usage() -> std::result::Result<String, PeachError> {
     let mut client = PeachStatsClient::new(transport_handle);
 
     let response = client.disk_usage().call()?;
+    let d: Vec<DiskUsage> = serde_json::from_str(&response)?;
 
-    Ok(response)
+    Ok(d)
+}
+
+/// Creates a JSON-RPC client with http transport and calls the `peach-stats`
+/// `history` method. Returns the recorded points of the given metric (`cpu`,
+/// `mem`, `load`, `net_rx`, `net_tx` or `temp`) between `from` and `to`
+/// (seconds since the Unix epoch).
+pub fn history(metric: &str, from: u64, to: u64) -> std::result::Result<Series, PeachError> {
+    debug!("Creating HTTP transport for stats client.");
+    let transport = HttpTransport::new().standalone()?;
+    let http_addr = env::var("PEACH_STATS_SERVER").unwrap_or_else(|_| "127.0.0.1:5113".to_string());
+    let http_server = format!("http://{}", http_addr);
+    debug!("Creating HTTP transport handle on {}.", http_server);
+    let transport_handle = transport.handle(&http_server)?;
+    info!("Creating client for peach_stats service.");
+    let mut client = PeachStatsClient::new(transport_handle);
+
+    let response = client.history(metric, from, to).call()?;
+    let s: Series = serde_json::from_str(&response)?;
+
+    Ok(s)
 }
 
 /// Creates a JSON-RPC client with http transport and calls the `peach-stats`
@@ -157,6 +260,60 @@ pub fn ping() -> std::result::Result<String, PeachError> {
     Ok(response)
 }
 
+/// Creates a JSON-RPC client with http transport and calls the `peach-stats`
+/// `service_stats` method.
+pub fn service_stats() -> std::result::Result<Vec<ServiceStat>, PeachError> {
+    debug!("Creating HTTP transport for stats client.");
+    let transport = HttpTransport::new().standalone()?;
+    let http_addr = env::var("PEACH_STATS_SERVER").unwrap_or_else(|_| "127.0.0.1:5113".to_string());
+    let http_server = format!("http://{}", http_addr);
+    debug!("Creating HTTP transport handle on {}.", http_server);
+    let transport_handle = transport.handle(&http_server)?;
+    info!("Creating client for peach_stats service.");
+    let mut client = PeachStatsClient::new(transport_handle);
+
+    let response = client.service_stats().call()?;
+    let s: Vec<ServiceStat> = serde_json::from_str(&response)?;
+
+    Ok(s)
+}
+
+/// Creates a JSON-RPC client with http transport and calls the `peach-stats`
+/// `temperature` method.
+pub fn temperature() -> std::result::Result<Vec<Temperature>, PeachError> {
+    debug!("Creating HTTP transport for stats client.");
+    let transport = HttpTransport::new().standalone()?;
+    let http_addr = env::var("PEACH_STATS_SERVER").unwrap_or_else(|_| "127.0.0.1:5113".to_string());
+    let http_server = format!("http://{}", http_addr);
+    debug!("Creating HTTP transport handle on {}.", http_server);
+    let transport_handle = transport.handle(&http_server)?;
+    info!("Creating client for peach_stats service.");
+    let mut client = PeachStatsClient::new(transport_handle);
+
+    let response = client.temperature().call()?;
+    let t: Vec<Temperature> = serde_json::from_str(&response)?;
+
+    Ok(t)
+}
+
+/// Creates a JSON-RPC client with http transport and calls the `peach-stats`
+/// `throttled` method.
+pub fn throttled() -> std::result::Result<Throttled, PeachError> {
+    debug!("Creating HTTP transport for stats client.");
+    let transport = HttpTransport::new().standalone()?;
+    let http_addr = env::var("PEACH_STATS_SERVER").unwrap_or_else(|_| "127.0.0.1:5113".to_string());
+    let http_server = format!("http://{}", http_addr);
+    debug!("Creating HTTP transport handle on {}.", http_server);
+    let transport_handle = transport.handle(&http_server)?;
+    info!("Creating client for peach_stats service.");
+    let mut client = PeachStatsClient::new(transport_handle);
+
+    let response = client.throttled().call()?;
+    let t: Throttled = serde_json::from_str(&response)?;
+
+    Ok(t)
+}
+
 /// Creates a JSON-RPC client with http transport and calls the `peach-stats`
 /// `uptime` method. If a successful response is returned, the uptime value (in
 /// seconds) is converted to minutes before being returned to the caller.
@@ -181,9 +338,15 @@ jsonrpc_client!(pub struct PeachStatsClient {
     /// JSON-RPC request to get measurement of current CPU statistics.
     pub fn cpu_stats_percent(&mut self) -> RpcRequest<String>;
 
+    /// JSON-RPC request to get the current frequency of each CPU core.
+    pub fn cpu_freq(&mut self) -> RpcRequest<String>;
+
     /// JSON-RPC request to get measurement of current disk usage statistics.
     pub fn disk_usage(&mut self) -> RpcRequest<String>;
 
+    /// JSON-RPC request to get the recorded history of a metric.
+    pub fn history(&mut self, metric: &str, from: u64, to: u64) -> RpcRequest<String>;
+
     /// JSON-RPC request to get measurement of current load average statistics.
     pub fn load_average(&mut self) -> RpcRequest<String>;
 
@@ -193,6 +356,15 @@ jsonrpc_client!(pub struct PeachStatsClient {
     /// JSON-RPC request to check availability of the `peach-stats` microservice.
     pub fn ping(&mut self) -> RpcRequest<String>;
 
+    /// JSON-RPC request to get the resource usage of each PeachCloud service.
+    pub fn service_stats(&mut self) -> RpcRequest<String>;
+
+    /// JSON-RPC request to get the temperature of each thermal zone.
+    pub fn temperature(&mut self) -> RpcRequest<String>;
+
+    /// JSON-RPC request to get the undervoltage and throttling flags.
+    pub fn throttled(&mut self) -> RpcRequest<String>;
+
     /// JSON-RPC request to get system uptime.
     pub fn uptime(&mut self) -> RpcRequest<String>;
 });
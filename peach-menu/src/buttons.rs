@@ -14,7 +14,21 @@ pub struct Press {
 struct ButtonMsg {
     jsonrpc: String,
     method: String,
-    params: Vec<u8>,
+    // button code, event kind (short, long or repeat) and duration in ms
+    params: (u8, String, u64),
+}
+
+/// Whether a button event should be passed to the state machine. Only short
+/// presses act on the menu, so that holding a button does not repeat an
+/// action (such as rebooting); repeats are let through for the up and down
+/// buttons only, to scroll through lists.
+fn forward(button_code: u8, kind: &str) -> bool {
+    match kind {
+        "short" => true,
+        // up and down
+        "repeat" => button_code == 3 || button_code == 4,
+        _ => false,
+    }
 }
 
 /// Websocket client for `peach_buttons`.
@@ -50,9 +64,20 @@ impl<'a> Handler for Client<'a> {
                 error!("Problem serializing button_code msg: {}", err);
                 process::exit(1);
             });
-            debug!("Sending button code to state_changer.");
+            let (button_code, kind, duration) = bm.params;
+            if !forward(button_code, &kind) {
+                debug!(
+                    "Ignoring {} press of button code {} ({}ms).",
+                    kind, button_code, duration
+                );
+                return Ok(());
+            }
+            debug!(
+                "Sending button code to state_changer ({} press, {}ms).",
+                kind, duration
+            );
             // send the button_code parameter to state_changer
-            self.s.send(bm.params[0]).unwrap_or_else(|err| {
+            self.s.send(button_code).unwrap_or_else(|err| {
                 error!("Problem sending button_code over channel: {}", err);
                 process::exit(1);
             });
@@ -80,3 +105,18 @@ impl<'a> Handler for Client<'a> {
         error!("The server encountered an error: {:?}", err);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_short_presses_and_scroll_repeats_are_forwarded() {
+        assert!(forward(5, "short"));
+        assert!(!forward(5, "long"));
+        assert!(!forward(5, "repeat"));
+        assert!(forward(3, "repeat"));
+        assert!(forward(4, "repeat"));
+        assert!(!forward(4, "long"));
+    }
+}
@@ -6,6 +6,7 @@
 //! output data for display.
 //!
 pub mod buttons;
+pub mod screen;
 pub mod state_machine;
 mod states;
 mod structs;
@@ -0,0 +1,192 @@
+//! Power management for the OLED display.
+//!
+//! After a period without button presses the display is dimmed, and after a
+//! longer period it is turned off. The next button press wakes the display
+//! and is otherwise ignored, so that it does not also trigger a menu action.
+//!
+//! The idle periods are read from the `screen_dim_timeout` and
+//! `screen_off_timeout` values (in seconds) of the PeachCloud config. A value
+//! of `0` disables the corresponding step.
+
+use std::time::{Duration, Instant};
+
+use log::{info, warn};
+
+use peach_lib::config_manager;
+use peach_lib::error::PeachError;
+use peach_lib::oled_client;
+
+/// Contrast of the display while it is in use (the SSD1306 default).
+const CONTRAST_NORMAL: u8 = 0x8f;
+/// Contrast of the display once it has been dimmed.
+const CONTRAST_DIM: u8 = 0x01;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// The power states of the display.
+pub enum Power {
+    On,
+    Dimmed,
+    Off,
+}
+
+/// Tracks button activity and dims or turns off the display when idle.
+#[derive(Debug)]
+pub struct Screen {
+    dim_after: Option<Duration>,
+    off_after: Option<Duration>,
+    power: Power,
+    last_activity: Instant,
+}
+
+impl Screen {
+    /// Creates a `Screen` with the idle periods stored in the PeachCloud
+    /// config (or the default periods, if the config cannot be loaded).
+    pub fn from_config() -> Screen {
+        let (dim_secs, off_secs) = match config_manager::load_peach_config() {
+            Ok(config) => (config.screen_dim_timeout, config.screen_off_timeout),
+            Err(e) => {
+                warn!("Failed to load screen timeouts from config: {:?}", e);
+                (30, 300)
+            }
+        };
+        info!(
+            "Dimming the display after {}s and turning it off after {}s.",
+            dim_secs, off_secs
+        );
+        Screen::new(dim_secs, off_secs)
+    }
+
+    /// Creates a `Screen` which dims the display after `dim_secs` and turns it
+    /// off after `off_secs` seconds without activity (`0` to never do so).
+    pub fn new(dim_secs: u64, off_secs: u64) -> Screen {
+        let period = |secs| match secs {
+            0 => None,
+            secs => Some(Duration::from_secs(secs)),
+        };
+        Screen {
+            dim_after: period(dim_secs),
+            off_after: period(off_secs),
+            power: Power::On,
+            last_activity: Instant::now(),
+        }
+    }
+
+    /// Returns the time remaining until the display should next be dimmed
+    /// or turned off, or `None` if there is nothing left to do.
+    pub fn timeout(&self) -> Option<Duration> {
+        let next = match self.power {
+            Power::On => self.dim_after.or(self.off_after),
+            Power::Dimmed => self.off_after,
+            Power::Off => None,
+        }?;
+        Some(
+            next.checked_sub(self.last_activity.elapsed())
+                .unwrap_or_default(),
+        )
+    }
+
+    /// Dims or turns off the display, once the idle period for the next step
+    /// has elapsed.
+    pub fn idle(&mut self) -> Result<(), PeachError> {
+        if self.timeout() != Some(Duration::from_secs(0)) {
+            return Ok(());
+        }
+        // the state is advanced even if the display cannot be reached, so
+        // that a failure is not retried without waiting for the next step
+        let idle = self.last_activity.elapsed();
+        match self.off_after {
+            Some(off_after) if idle >= off_after => {
+                info!("Turning the display off after {}s idle.", idle.as_secs());
+                self.power = Power::Off;
+                oled_client::power(false)?;
+            }
+            _ => {
+                info!("Dimming the display after {}s idle.", idle.as_secs());
+                self.power = Power::Dimmed;
+                oled_client::contrast(CONTRAST_DIM)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Records button activity and restores the display if it was dimmed or
+    /// turned off. Returns `true` if the display was woken, in which case the
+    /// button press should not be acted upon.
+    pub fn wake(&mut self) -> Result<bool, PeachError> {
+        self.last_activity = Instant::now();
+        let power = self.power;
+        self.power = Power::On;
+        match power {
+            Power::On => return Ok(false),
+            Power::Dimmed => info!("Restoring the display brightness."),
+            Power::Off => {
+                info!("Waking the display.");
+                oled_client::power(true)?;
+            }
+        }
+        oled_client::contrast(CONTRAST_NORMAL)?;
+        Ok(true)
+    }
+
+    /// Marks the display as turned off, for example when it has been turned
+    /// off from the menu, so that it is no longer dimmed or turned off.
+    pub fn set_off(&mut self) {
+        self.power = Power::Off;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a screen which has been idle for the given number of seconds
+    fn idle_for(dim_secs: u64, off_secs: u64, power: Power, idle_secs: u64) -> Screen {
+        let mut screen = Screen::new(dim_secs, off_secs);
+        screen.power = power;
+        screen.last_activity = Instant::now() - Duration::from_secs(idle_secs);
+        screen
+    }
+
+    #[test]
+    fn timeout_follows_the_next_step() {
+        let near = |timeout: Option<Duration>, secs: u64| {
+            let timeout = timeout.unwrap();
+            timeout <= Duration::from_secs(secs) && timeout > Duration::from_secs(secs - 1)
+        };
+        assert!(near(idle_for(30, 300, Power::On, 10).timeout(), 20));
+        assert!(near(idle_for(30, 300, Power::Dimmed, 100).timeout(), 200));
+        assert_eq!(idle_for(30, 300, Power::Off, 400).timeout(), None);
+        // a disabled step is skipped
+        assert!(near(idle_for(0, 300, Power::On, 100).timeout(), 200));
+        assert_eq!(idle_for(30, 0, Power::Dimmed, 100).timeout(), None);
+        assert_eq!(idle_for(0, 0, Power::On, 100).timeout(), None);
+        // an overdue step is due now
+        assert_eq!(
+            idle_for(30, 300, Power::On, 60).timeout(),
+            Some(Duration::from_secs(0))
+        );
+    }
+
+    #[test]
+    fn idle_waits_for_the_timeout() {
+        let mut screen = idle_for(30, 300, Power::On, 10);
+        screen.idle().unwrap();
+        assert_eq!(screen.power, Power::On);
+    }
+
+    #[test]
+    fn wake_records_activity_without_waking_a_screen_which_is_on() {
+        let mut screen = idle_for(30, 300, Power::On, 20);
+        assert!(!screen.wake().unwrap());
+        assert_eq!(screen.power, Power::On);
+        assert!(screen.timeout().unwrap() > Duration::from_secs(29));
+    }
+
+    #[test]
+    fn set_off_stops_the_timeouts() {
+        let mut screen = idle_for(30, 300, Power::On, 0);
+        screen.set_off();
+        assert_eq!(screen.power, Power::Off);
+        assert_eq!(screen.timeout(), None);
+    }
+}
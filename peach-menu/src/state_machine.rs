@@ -6,6 +6,7 @@ use log::{error, info, warn};
 use peach_lib::error::PeachError;
 use peach_lib::oled_client;
 
+use crate::screen::Screen;
 use crate::states::*;
 
 #[derive(Debug, Clone, Copy)]
@@ -51,11 +52,38 @@ pub fn state_changer(r: Receiver<u8>) {
             Err(e) => warn!("State machine error: {:?}", e),
         };
 
+        let mut screen = Screen::from_config();
+
         loop {
-            let button_code = r.recv().unwrap_or_else(|err| {
-                error!("Problem receiving button code from server: {}", err);
-                process::exit(1);
-            });
+            // wait for a button press, or until the display should be dimmed
+            // or turned off
+            let received = match screen.timeout() {
+                Some(timeout) => r.recv_timeout(timeout),
+                None => r.recv().map_err(|_| RecvTimeoutError::Disconnected),
+            };
+            let button_code = match received {
+                Ok(button_code) => button_code,
+                Err(RecvTimeoutError::Timeout) => {
+                    if let Err(e) = screen.idle() {
+                        warn!("Display power management error: {:?}", e);
+                    }
+                    continue;
+                }
+                Err(err) => {
+                    error!("Problem receiving button code from server: {}", err);
+                    process::exit(1);
+                }
+            };
+            // a press dismisses any alert shown above the menu
+            if let Err(e) = oled_client::render(oled_client::ALERT_LAYER, 1, Vec::new()) {
+                warn!("Failed to dismiss alert: {:?}", e);
+            }
+            // the press which wakes the display is not passed to the menu
+            match screen.wake() {
+                Ok(true) => continue,
+                Ok(false) => (),
+                Err(e) => warn!("Display power management error: {:?}", e),
+            }
             let event = match button_code {
                 0 => Event::Center,
                 1 => Event::Left,
@@ -69,8 +97,17 @@ pub fn state_changer(r: Receiver<u8>) {
             state = state.next(event);
             match state.run() {
                 Ok(_) => (),
-                Err(e) => warn!("State machine error: {:?}", e),
+                Err(e) => {
+                    warn!("State machine error: {:?}", e);
+                    // show the error in place of the state (if the display
+                    // can be reached at all)
+                    let _ = state_error(&e);
+                }
             };
+            // the display has been turned off from the menu
+            if state == State::OledPower(0) {
+                screen.set_off();
+            }
         }
     });
 }
@@ -5,40 +5,65 @@ use log::info;
 
 use peach_lib::error::PeachError;
 use peach_lib::network_client;
-use peach_lib::oled_client;
+use peach_lib::oled_client::{self, Op};
 use peach_lib::stats_client;
 
+// render a screen of the menu in a single request, replacing the last one
+fn render(ops: Vec<Op>) -> Result<(), PeachError> {
+    oled_client::render("menu", 0, ops)
+}
+
+// render a list of entries with a "> " marker beside the selected one
+fn list(entries: &[&str], y_coord: i32, selected: usize) -> Vec<Op> {
+    entries
+        .iter()
+        .enumerate()
+        .map(|(i, entry)| {
+            let marker = if i == selected { "> " } else { "  " };
+            let line = format!("{}{}", marker, entry);
+            Op::write(0, y_coord + 9 * i as i32, &line, "6x8")
+        })
+        .collect()
+}
+
+// write a line which scrolls if it is too long to fit on the display
+fn scroll(y_coord: i32, line: &str) -> Op {
+    Op::text(0, y_coord, 128, 8, line, "6x8", "scroll")
+}
+
+pub fn state_error(e: &PeachError) -> Result<(), PeachError> {
+    let message = e.to_string();
+    render(vec![
+        Op::write(0, 0, "ERROR", "6x8"),
+        Op::text(0, 18, 128, 46, &message, "6x8", "wrap"),
+    ])
+}
+
 pub fn state_network_mode(mode: u8) -> Result<(), PeachError> {
     match mode {
         0 => {
-            oled_client::clear()?;
-            oled_client::write(24, 16, "ACTIVATING", "6x8")?;
-            oled_client::write(24, 27, "WIRELESS", "6x8")?;
-            oled_client::write(24, 38, "CONNECTION...", "6x8")?;
-            oled_client::flush()?;
+            render(vec![
+                Op::write(24, 16, "ACTIVATING", "6x8"),
+                Op::write(24, 27, "WIRELESS", "6x8"),
+                Op::write(24, 38, "CONNECTION...", "6x8"),
+            ])?;
 
             network_client::activate_client()?;
 
-            oled_client::clear()?;
-            oled_client::write(0, 0, "> Client mode", "6x8")?;
-            oled_client::write(0, 9, "  Access point mode", "6x8")?;
-            oled_client::flush()?;
+            render(list(&["Client mode", "Access point mode"], 0, 0))?;
 
             Ok(())
         }
         1 => {
-            oled_client::clear()?;
-            oled_client::write(27, 16, "DEPLOYING", "6x8")?;
-            oled_client::write(27, 27, "ACCESS", "6x8")?;
-            oled_client::write(27, 38, "POINT...", "6x8")?;
-            oled_client::flush()?;
+            render(vec![
+                Op::write(27, 16, "DEPLOYING", "6x8"),
+                Op::write(27, 27, "ACCESS", "6x8"),
+                Op::write(27, 38, "POINT...", "6x8"),
+            ])?;
 
             network_client::activate_ap()?;
 
-            oled_client::clear()?;
-            oled_client::write(0, 0, "  Client mode", "6x8")?;
-            oled_client::write(0, 9, "> Access point mode", "6x8")?;
-            oled_client::flush()?;
+            render(list(&["Client mode", "Access point mode"], 0, 1))?;
 
             Ok(())
         }
@@ -48,133 +73,100 @@ pub fn state_network_mode(mode: u8) -> Result<(), PeachError> {
 
 pub fn state_home(selected: u8) -> Result<(), PeachError> {
     // match on `selected`
-    match selected {
-        // Home: root
-        0 => {
-            let dt: DateTime<Local> = Local::now();
-            let t = format!("{}", dt.time().format("%H:%M"));
-
-            oled_client::clear()?;
-            oled_client::write(96, 0, &t, "6x8")?;
-            oled_client::write(0, 0, "PeachCloud", "6x8")?;
-            oled_client::write(0, 18, "> Networking", "6x8")?;
-            oled_client::write(0, 27, "  System Stats", "6x8")?;
-            oled_client::write(0, 36, "  Display Off", "6x8")?;
-            oled_client::write(0, 45, "  Reboot", "6x8")?;
-            oled_client::write(0, 54, "  Shutdown", "6x8")?;
-            oled_client::write(100, 54, "v0.2", "6x8")?;
-            oled_client::flush()?;
-
-            Ok(())
-        }
-        // Home: networking
-        1 => {
-            oled_client::write(0, 18, "> ", "6x8")?;
-            oled_client::write(0, 27, "  ", "6x8")?;
-            oled_client::write(0, 36, "  ", "6x8")?;
-            oled_client::write(0, 45, "  ", "6x8")?;
-            oled_client::write(0, 54, "  ", "6x8")?;
-            oled_client::flush()?;
-
-            Ok(())
-        }
-        // Home: system stats
-        2 => {
-            oled_client::write(0, 18, "  ", "6x8")?;
-            oled_client::write(0, 27, "> ", "6x8")?;
-            oled_client::write(0, 36, "  ", "6x8")?;
-            oled_client::write(0, 45, "  ", "6x8")?;
-            oled_client::write(0, 54, "  ", "6x8")?;
-            oled_client::flush()?;
-
-            Ok(())
-        }
-        // Home: display off
-        3 => {
-            oled_client::write(0, 18, "  ", "6x8")?;
-            oled_client::write(0, 27, "  ", "6x8")?;
-            oled_client::write(0, 36, "> ", "6x8")?;
-            oled_client::write(0, 45, "  ", "6x8")?;
-            oled_client::write(0, 54, "  ", "6x8")?;
-            oled_client::flush()?;
-
-            Ok(())
-        }
-        // Home: reboot
-        4 => {
-            oled_client::write(0, 18, "  ", "6x8")?;
-            oled_client::write(0, 27, "  ", "6x8")?;
-            oled_client::write(0, 36, "  ", "6x8")?;
-            oled_client::write(0, 45, "> ", "6x8")?;
-            oled_client::write(0, 54, "  ", "6x8")?;
-            oled_client::flush()?;
-
-            Ok(())
-        }
-        // Home: shutdown
-        5 => {
-            oled_client::write(0, 18, "  ", "6x8")?;
-            oled_client::write(0, 27, "  ", "6x8")?;
-            oled_client::write(0, 36, "  ", "6x8")?;
-            oled_client::write(0, 45, "  ", "6x8")?;
-            oled_client::write(0, 54, "> ", "6x8")?;
-            oled_client::flush()?;
-
-            Ok(())
-        }
+    // 0: root, 1: networking, 2: system stats, 3: display off, 4: reboot,
+    // 5: shutdown (the root selects networking)
+    if selected > 5 {
         // outlier
-        _ => Ok(()),
+        return Ok(());
     }
+    let dt: DateTime<Local> = Local::now();
+    let t = format!("{}", dt.time().format("%H:%M"));
+
+    let mut ops = vec![
+        Op::write(96, 0, &t, "6x8"),
+        Op::write(0, 0, "PeachCloud", "6x8"),
+    ];
+    ops.extend(list(
+        &[
+            "Networking",
+            "System Stats",
+            "Display Off",
+            "Reboot",
+            "Shutdown",
+        ],
+        18,
+        selected.max(1) as usize - 1,
+    ));
+    ops.push(Op::write(100, 54, "v0.2", "6x8"));
+    render(ops)
 }
 
 pub fn state_logo() -> Result<(), PeachError> {
     let bytes = PEACH_LOGO.to_vec();
-    oled_client::clear()?;
-    oled_client::draw(bytes, 64, 64, 32, 0)?;
-    oled_client::flush()?;
-
-    Ok(())
+    render(vec![Op::Draw {
+        bytes,
+        width: 64,
+        height: 64,
+        x_coord: 32,
+        y_coord: 0,
+    }])
 }
 
 pub fn state_network() -> Result<(), PeachError> {
-    let status = match network_client::state("wlan0") {
+    // show the interface holding the default route, falling back to wlan0
+    let uplink = network_client::list_interfaces()
+        .ok()
+        .and_then(|interfaces| interfaces.into_iter().find(|iface| iface.uplink));
+    let (iface, kind) = match uplink {
+        Some(uplink) => (uplink.name, uplink.kind),
+        None => ("wlan0".to_string(), "wifi".to_string()),
+    };
+    let status = match network_client::state(&iface) {
         Ok(state) => state,
         Err(_) => "Error".to_string(),
     };
     match status.as_ref() {
-        // wlan0 is up or dormant
-        // Network: Client mode
+        // uplink is up or dormant
+        // Network: Ethernet or Client mode
         "up" | "dormant" => {
             let show_status = format!("STATUS {}", status);
-            let ip = match network_client::ip("wlan0") {
+            let ip = match network_client::ip(&iface) {
                 Ok(ip) => ip,
                 Err(_) => "x.x.x.x".to_string(),
             };
             let show_ip = format!("IP {}", ip);
-            let ssid = match network_client::ssid("wlan0") {
-                Ok(ssid) => ssid,
-                Err(_) => "Not connected".to_string(),
-            };
-            let show_ssid = format!("NETWORK {}", ssid);
-            let rssi = match network_client::rssi("wlan0") {
-                Ok(rssi) => rssi,
-                Err(_) => "_".to_string(),
-            };
-            let show_rssi = format!("SIGNAL {}dBm", rssi);
             let config = "> Configuration";
 
-            oled_client::clear()?;
-            oled_client::write(0, 0, "MODE Client", "6x8")?;
-            oled_client::write(0, 9, &show_status, "6x8")?;
-            oled_client::write(0, 18, &show_ssid, "6x8")?;
-            oled_client::write(0, 27, &show_ip, "6x8")?;
-            oled_client::write(0, 36, &show_rssi, "6x8")?;
-            oled_client::write(0, 54, config, "6x8")?;
-            oled_client::flush()?;
-
-            Ok(())
+            let mut ops = Vec::new();
+            if kind == "ethernet" {
+                let show_iface = format!("IFACE {}", iface);
+
+                ops.push(Op::write(0, 0, "MODE Ethernet", "6x8"));
+                ops.push(Op::write(0, 9, &show_status, "6x8"));
+                ops.push(Op::write(0, 18, &show_iface, "6x8"));
+                ops.push(scroll(27, &show_ip));
+            } else {
+                let ssid = match network_client::ssid(&iface) {
+                    Ok(ssid) => ssid,
+                    Err(_) => "Not connected".to_string(),
+                };
+                let show_ssid = format!("NETWORK {}", ssid);
+                let rssi = match network_client::rssi(&iface) {
+                    Ok(rssi) => rssi,
+                    Err(_) => "_".to_string(),
+                };
+                let show_rssi = format!("SIGNAL {}dBm", rssi);
+
+                ops.push(Op::write(0, 0, "MODE Client", "6x8"));
+                ops.push(Op::write(0, 9, &show_status, "6x8"));
+                ops.push(scroll(18, &show_ssid));
+                ops.push(scroll(27, &show_ip));
+                ops.push(Op::write(0, 36, &show_rssi, "6x8"));
+            }
+            ops.push(Op::write(0, 54, config, "6x8"));
+            render(ops)
         }
-        // wlan0 is down
+        // uplink is down
         // Network: AP mode
         "down" => {
             let status = match network_client::state("ap0") {
@@ -191,15 +183,13 @@ pub fn state_network() -> Result<(), PeachError> {
             let show_ssid = format!("NETWORK {}", ssid);
             let config = "> Configuration";
 
-            oled_client::clear()?;
-            oled_client::write(0, 0, "MODE Access Point", "6x8")?;
-            oled_client::write(0, 9, &show_status, "6x8")?;
-            oled_client::write(0, 18, &show_ssid, "6x8")?;
-            oled_client::write(0, 27, &show_ip, "6x8")?;
-            oled_client::write(0, 54, config, "6x8")?;
-            oled_client::flush()?;
-
-            Ok(())
+            render(vec![
+                Op::write(0, 0, "MODE Access Point", "6x8"),
+                Op::write(0, 9, &show_status, "6x8"),
+                scroll(18, &show_ssid),
+                scroll(27, &show_ip),
+                Op::write(0, 54, config, "6x8"),
+            ])
         }
         // outlier
         // TODO: account for iface states other than 'up' and 'down'
@@ -209,42 +199,23 @@ pub fn state_network() -> Result<(), PeachError> {
 
 pub fn state_network_conf(selected: u8) -> Result<(), PeachError> {
     // match on `selected`
-    match selected {
-        // NetworkConf: root
-        0 => {
-            oled_client::clear()?;
-            oled_client::write(0, 0, "> Client Mode", "6x8")?;
-            oled_client::write(0, 9, "  Access Point Mode", "6x8")?;
-            oled_client::flush()?;
-
-            Ok(())
-        }
-        // NetworkConf: client
-        1 => {
-            oled_client::write(0, 0, "> ", "6x8")?;
-            oled_client::write(0, 9, "  ", "6x8")?;
-            oled_client::flush()?;
-
-            Ok(())
-        }
-        // NetworkConf: ap
-        2 => {
-            oled_client::write(0, 0, "  ", "6x8")?;
-            oled_client::write(0, 9, "> ", "6x8")?;
-            oled_client::flush()?;
-
-            Ok(())
-        }
+    // 0: root, 1: client, 2: ap (the root selects client mode)
+    if selected > 2 {
         // outlier
-        _ => Ok(()),
+        return Ok(());
     }
+    render(list(
+        &["Client Mode", "Access Point Mode"],
+        0,
+        selected.max(1) as usize - 1,
+    ))
 }
 
 pub fn state_reboot() -> Result<(), PeachError> {
-    oled_client::clear()?;
-    oled_client::write(27, 16, "REBOOTING", "6x8")?;
-    oled_client::write(27, 27, "DEVICE...", "6x8")?;
-    oled_client::flush()?;
+    render(vec![
+        Op::write(27, 16, "REBOOTING", "6x8"),
+        Op::write(27, 27, "DEVICE...", "6x8"),
+    ])?;
 
     let three_secs = time::Duration::from_millis(3000);
     thread::sleep(three_secs);
@@ -262,11 +233,11 @@ pub fn state_reboot() -> Result<(), PeachError> {
 }
 
 pub fn state_shutdown() -> Result<(), PeachError> {
-    oled_client::clear()?;
-    oled_client::write(27, 16, "SHUTTING", "6x8")?;
-    oled_client::write(27, 27, "DOWN", "6x8")?;
-    oled_client::write(27, 38, "DEVICE...", "6x8")?;
-    oled_client::flush()?;
+    render(vec![
+        Op::write(27, 16, "SHUTTING", "6x8"),
+        Op::write(27, 27, "DOWN", "6x8"),
+        Op::write(27, 38, "DEVICE...", "6x8"),
+    ])?;
 
     let three_secs = time::Duration::from_millis(3000);
     thread::sleep(three_secs);
@@ -296,22 +267,18 @@ pub fn state_stats() -> Result<(), PeachError> {
     let load_stats = format!("LOAD {} {} {}", load.one, load.five, load.fifteen);
     let uptime = stats_client::uptime()?;
     let uptime_stats = format!("UPTIME {} mins", uptime);
-    let traffic = network_client::traffic("wlan0")?;
-    let rx = traffic.received / 1024 / 1024;
-    let rx_stats = format!("DATA RX {}MB", rx);
-    let tx = traffic.transmitted / 1024 / 1024;
-    let tx_stats = format!("DATA TX {}MB", tx);
-
-    oled_client::clear()?;
-    oled_client::write(0, 0, &cpu_stats, "6x8")?;
-    oled_client::write(0, 9, &mem_stats, "6x8")?;
-    oled_client::write(0, 18, &load_stats, "6x8")?;
-    oled_client::write(0, 27, &uptime_stats, "6x8")?;
-    oled_client::write(0, 36, &rx_stats, "6x8")?;
-    oled_client::write(0, 45, &tx_stats, "6x8")?;
-    oled_client::flush()?;
-
-    Ok(())
+    let traffic = network_client::traffic(&network_client::uplink_iface())?;
+    let rx_stats = format!("DATA RX {}", traffic.received);
+    let tx_stats = format!("DATA TX {}", traffic.transmitted);
+
+    render(vec![
+        Op::write(0, 0, &cpu_stats, "6x8"),
+        Op::write(0, 9, &mem_stats, "6x8"),
+        Op::write(0, 18, &load_stats, "6x8"),
+        Op::write(0, 27, &uptime_stats, "6x8"),
+        Op::write(0, 36, &rx_stats, "6x8"),
+        Op::write(0, 45, &tx_stats, "6x8"),
+    ])
 }
 
 const PEACH_LOGO: [u8; 512] = [
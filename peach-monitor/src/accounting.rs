@@ -0,0 +1,234 @@
+//! Monotonic per-interface traffic counters and usage history.
+//!
+//! The kernel counters read from `/proc/net/dev` start from zero on every
+//! boot. For each interface, the last sample is stored together with the
+//! kernel boot ID, which allows the traffic since the previous sample to be
+//! calculated without double-counting when `--save` runs more than once per
+//! boot. A change of boot ID (or a counter which has gone backwards) means
+//! the counters were reset, in which case the whole sample is new traffic.
+//!
+//! Traffic on the monitored interface is added to the usage total used for
+//! alerts and to daily (`YYYY-MM-DD`), monthly (`YYYY-MM`) and billing-cycle
+//! (start date of the cycle) history buckets.
+
+use std::{fs, io};
+
+use chrono::{Datelike, Local, NaiveDate};
+use peach_lib::error::PeachError;
+use peach_lib::monitor::{Counters, MonitorStore, Period};
+use probes::network;
+
+/// Number of daily buckets to retain.
+const DAILY_BUCKETS: usize = 62;
+/// Number of monthly buckets to retain.
+const MONTHLY_BUCKETS: usize = 24;
+/// Number of billing-cycle buckets to retain.
+const CYCLE_BUCKETS: usize = 12;
+
+/// Received and transmitted bytes since the previous sample.
+#[derive(Debug, Default)]
+struct Delta {
+    rx: u64,
+    tx: u64,
+}
+
+/// Read the kernel boot ID, which changes on every boot.
+fn boot_id() -> String {
+    fs::read_to_string("/proc/sys/kernel/random/boot_id")
+        .map(|id| id.trim().to_string())
+        .unwrap_or_default()
+}
+
+/// Calculate the traffic since the previous sample of an interface and
+/// update its counters.
+fn sample(counters: &mut Counters, boot: &str, rx: u64, tx: u64) -> Delta {
+    let mut delta = Delta { rx, tx };
+    // counters only grow within a boot; otherwise they were reset
+    if counters.boot_id == boot && rx >= counters.rx && tx >= counters.tx {
+        delta.rx = rx - counters.rx;
+        delta.tx = tx - counters.tx;
+    }
+
+    counters.boot_id = boot.to_string();
+    counters.rx = rx;
+    counters.tx = tx;
+    counters.total_rx += delta.rx;
+    counters.total_tx += delta.tx;
+
+    delta
+}
+
+/// Calculate the start date of the billing cycle containing `today`.
+pub fn cycle_start(today: NaiveDate, reset_day: u32) -> NaiveDate {
+    if today.day() >= reset_day {
+        NaiveDate::from_ymd(today.year(), today.month(), reset_day)
+    } else if today.month() == 1 {
+        NaiveDate::from_ymd(today.year() - 1, 12, reset_day)
+    } else {
+        NaiveDate::from_ymd(today.year(), today.month() - 1, reset_day)
+    }
+}
+
+/// Determine the start date (`YYYY-MM-DD`) of the billing cycle containing
+/// `today`, and whether a new cycle has begun since `stored_start` so that
+/// the usage total should be reset. Cycles follow calendar months unless the
+/// user has set a reset day, and the total is only reset if they have.
+fn current_cycle(
+    today: NaiveDate,
+    reset_day: Option<u32>,
+    stored_start: Option<&str>,
+) -> (String, bool) {
+    let start = cycle_start(today, reset_day.unwrap_or(1))
+        .format("%Y-%m-%d")
+        .to_string();
+    let new_cycle = match stored_start {
+        Some(stored_start) => stored_start != start,
+        None => false,
+    };
+
+    (start, new_cycle && reset_day.is_some())
+}
+
+/// Sample the traffic counters of all interfaces and add the traffic of
+/// `iface` since the previous sample to the usage total and history.
+///
+/// When a billing-cycle reset day has been set and a new cycle has begun,
+/// the usage total and alert flags are reset before the traffic is added.
+pub fn record(store: &MonitorStore, iface: &str) -> Result<(), PeachError> {
+    let network = network::read().map_err(|err| PeachError::StdIoError {
+        source: io::Error::other(err.to_string()),
+        msg: "Failed to read network statistics".to_string(),
+    })?;
+    let boot = boot_id();
+    let today = Local::today().naive_local();
+
+    // the whole update is a single transaction so that a concurrent reset
+    // from peach-web cannot be lost between reading and writing the total
+    store.transaction(|tx| {
+        let mut ifaces = tx.counters();
+        let mut monitored = Delta::default();
+        for (interface, data) in network.interfaces {
+            if interface == "lo" {
+                continue;
+            }
+            let counters = ifaces.entry(interface.clone()).or_default();
+            let delta = sample(counters, &boot, data.received, data.transmitted);
+            if interface == iface {
+                monitored = delta;
+            }
+        }
+        tx.set_counters(&ifaces)?;
+
+        let (start, reset) = current_cycle(today, tx.reset_day(), tx.cycle_start().as_deref());
+        if reset {
+            // a new billing cycle has begun: start counting from zero
+            tx.set_total(0)?;
+            tx.set_alert_flags(false, false)?;
+        }
+        tx.set_cycle_start(&start)?;
+
+        tx.set_total(tx.total() + monitored.rx + monitored.tx)?;
+
+        let day = today.format("%Y-%m-%d").to_string();
+        let month = today.format("%Y-%m").to_string();
+        for (period, name, retain) in [
+            (Period::Daily, &day, DAILY_BUCKETS),
+            (Period::Monthly, &month, MONTHLY_BUCKETS),
+            (Period::Cycle, &start, CYCLE_BUCKETS),
+        ] {
+            tx.add_to_bucket(period, name, monitored.rx, monitored.tx, retain)?;
+        }
+
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd(year, month, day)
+    }
+
+    #[test]
+    fn first_sample_counts_all_traffic() {
+        let mut counters = Counters::default();
+        let delta = sample(&mut counters, "boot-a", 100, 50);
+        assert_eq!((delta.rx, delta.tx), (100, 50));
+        assert_eq!((counters.total_rx, counters.total_tx), (100, 50));
+        assert_eq!(counters.boot_id, "boot-a");
+    }
+
+    #[test]
+    fn runs_in_one_boot_count_only_new_traffic() {
+        let mut counters = Counters::default();
+        sample(&mut counters, "boot-a", 100, 50);
+        let delta = sample(&mut counters, "boot-a", 150, 60);
+        assert_eq!((delta.rx, delta.tx), (50, 10));
+        // a run without new traffic adds nothing
+        let delta = sample(&mut counters, "boot-a", 150, 60);
+        assert_eq!((delta.rx, delta.tx), (0, 0));
+        assert_eq!((counters.rx, counters.tx), (150, 60));
+        assert_eq!((counters.total_rx, counters.total_tx), (150, 60));
+    }
+
+    #[test]
+    fn reboot_counts_the_whole_sample() {
+        // a new boot ID, even though the counters have grown
+        let mut counters = Counters::default();
+        sample(&mut counters, "boot-a", 100, 50);
+        let delta = sample(&mut counters, "boot-b", 300, 70);
+        assert_eq!((delta.rx, delta.tx), (300, 70));
+        assert_eq!((counters.total_rx, counters.total_tx), (400, 120));
+
+        // counters which have gone backwards within the same boot ID
+        let delta = sample(&mut counters, "boot-b", 20, 80);
+        assert_eq!((delta.rx, delta.tx), (20, 80));
+        assert_eq!((counters.total_rx, counters.total_tx), (420, 200));
+    }
+
+    #[test]
+    fn cycles_start_on_the_reset_day() {
+        assert_eq!(cycle_start(date(2021, 5, 20), 15), date(2021, 5, 15));
+        assert_eq!(cycle_start(date(2021, 5, 15), 15), date(2021, 5, 15));
+        assert_eq!(cycle_start(date(2021, 5, 14), 15), date(2021, 4, 15));
+        assert_eq!(cycle_start(date(2021, 5, 1), 1), date(2021, 5, 1));
+    }
+
+    #[test]
+    fn cycles_roll_over_into_the_previous_year() {
+        assert_eq!(cycle_start(date(2021, 1, 10), 15), date(2020, 12, 15));
+        assert_eq!(cycle_start(date(2021, 1, 15), 15), date(2021, 1, 15));
+        assert_eq!(cycle_start(date(2021, 12, 31), 28), date(2021, 12, 28));
+    }
+
+    #[test]
+    fn total_is_only_reset_with_a_reset_day() {
+        // without a reset day, cycles follow calendar months and the total
+        // is never reset
+        assert_eq!(
+            current_cycle(date(2021, 6, 2), None, Some("2021-05-01")),
+            ("2021-06-01".to_string(), false)
+        );
+        // with a reset day, a new cycle resets the total
+        assert_eq!(
+            current_cycle(date(2021, 6, 16), Some(15), Some("2021-05-15")),
+            ("2021-06-15".to_string(), true)
+        );
+        // but not within the same cycle, or before a cycle has been stored
+        assert_eq!(
+            current_cycle(date(2021, 6, 14), Some(15), Some("2021-05-15")),
+            ("2021-05-15".to_string(), false)
+        );
+        assert_eq!(
+            current_cycle(date(2021, 6, 16), Some(15), None),
+            ("2021-06-15".to_string(), false)
+        );
+        // a changed reset day starts a new cycle
+        assert_eq!(
+            current_cycle(date(2021, 6, 16), Some(10), Some("2021-06-01")),
+            ("2021-06-10".to_string(), true)
+        );
+    }
+}
@@ -0,0 +1,247 @@
+//! System health checks.
+//!
+//! Each pass measures the usage of the root filesystem (via `peach-stats`),
+//! the CPU temperature (from `/sys/class/thermal`), memory pressure (from
+//! `/proc/meminfo`) and the state of each of the PeachCloud `SERVICES`
+//! defined by `peach-config`. The measurements are compared against the
+//! user-defined thresholds, the health alert flags are set in the data store
+//! and newly-raised alerts are sent through the same notification channels
+//! as the traffic alerts.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::process::Command;
+
+use peach_config::constants::SERVICES;
+use peach_lib::error::PeachError;
+use peach_lib::monitor::{HealthAlert, MonitorStore};
+use peach_lib::stats_client;
+
+use crate::notify::{self, Notifier};
+
+/// Latest health measurements. Values which could not be read are `None`.
+#[derive(Debug, Default)]
+pub struct Health {
+    pub disk: Option<u64>,           // root filesystem usage (percent)
+    pub temp: Option<u64>,           // highest thermal zone temperature (degrees Celsius)
+    pub mem: Option<u64>,            // memory in use (percent)
+    pub failed: Option<Vec<String>>, // services in the `failed` state
+}
+
+/// Retrieve the usage of the root filesystem from `peach-stats`.
+fn disk_usage() -> Option<u64> {
+    stats_client::disk_usage()
+        .ok()?
+        .into_iter()
+        .find(|disk| disk.mountpoint == "/")
+        .map(|disk| u64::from(disk.used_percentage))
+}
+
+/// Read the highest temperature of all thermal zones (the Raspberry Pi has a
+/// single zone for the SoC).
+fn cpu_temp() -> Option<u64> {
+    fs::read_dir("/sys/class/thermal")
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            entry
+                .file_name()
+                .to_string_lossy()
+                .starts_with("thermal_zone")
+        })
+        .filter_map(|entry| fs::read_to_string(entry.path().join("temp")).ok())
+        // temperatures are given in millidegrees Celsius
+        .filter_map(|temp| temp.trim().parse::<i64>().ok())
+        .max()
+        .map(|temp| (temp.max(0) / 1000) as u64)
+}
+
+/// Calculate the memory in use as a percentage of total memory. Memory which
+/// the kernel is able to reclaim (`MemAvailable`) is not counted as in use.
+fn mem_pressure() -> Option<u64> {
+    let meminfo = fs::read_to_string("/proc/meminfo").ok()?;
+    let field = |name: &str| {
+        meminfo
+            .lines()
+            .find(|line| line.starts_with(name))
+            .and_then(|line| line.split_whitespace().nth(1))
+            .and_then(|kb| kb.parse::<u64>().ok())
+    };
+    let total = field("MemTotal:")?;
+    let available = field("MemAvailable:")?;
+    if total == 0 {
+        return None;
+    }
+    Some(total.saturating_sub(available) * 100 / total)
+}
+
+/// Check whether a service has entered the `failed` state. Services which
+/// are not installed (and entries which are not units, such as
+/// `peach-config`) have a `LoadState` of `not-found` and are skipped. An
+/// error is returned if the state could not be retrieved from systemd.
+fn is_failed(service: &str) -> io::Result<bool> {
+    let output = Command::new("systemctl")
+        .arg("show")
+        .arg("--property=LoadState,ActiveState")
+        .arg(service)
+        .output()?;
+    if !output.status.success() {
+        return Err(io::Error::other(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Ok(stdout.contains("LoadState=loaded") && stdout.contains("ActiveState=failed"))
+}
+
+/// Retrieve the services which are in the `failed` state, or `None` if the
+/// state of any service could not be retrieved.
+fn failed_services() -> Option<Vec<String>> {
+    let mut failed = Vec::new();
+    for service in SERVICES {
+        match is_failed(service) {
+            Ok(true) => failed.push(service.to_string()),
+            Ok(false) => (),
+            Err(e) => {
+                eprintln!("Failed to retrieve the state of {}: {}", service, e);
+                return None;
+            }
+        }
+    }
+    Some(failed)
+}
+
+/// Take the latest health measurements.
+pub fn measure() -> Health {
+    Health {
+        disk: disk_usage(),
+        temp: cpu_temp(),
+        mem: mem_pressure(),
+        failed: failed_services(),
+    }
+}
+
+/// Evaluate a measurement against its threshold. A threshold of 0 disables
+/// the check, while a measurement which could not be read leaves the flag
+/// unchanged (so that a brief `peach-stats` outage does not clear an alert
+/// and cause it to be sent again).
+fn exceeds(value: Option<u64>, threshold: u64, previous: bool) -> bool {
+    match value {
+        _ if threshold == 0 => false,
+        Some(value) => value > threshold,
+        None => previous,
+    }
+}
+
+/// Format a measurement for an alert message.
+fn reading(value: Option<u64>) -> String {
+    value.map_or_else(|| "unknown".to_string(), |value| value.to_string())
+}
+
+/// Measure system health, set the health alert flags and notify the user of
+/// newly-raised alerts. Each alert is sent once each time it turns on, while
+/// a notification is sent for each service the first time it is seen in the
+/// `failed` state.
+pub fn check(store: &MonitorStore, notifiers: &[Box<dyn Notifier>]) -> Result<(), PeachError> {
+    // measurements are taken without holding the store lock
+    let health = measure();
+
+    let (threshold, alert) = store.transaction(|tx| {
+        let threshold = tx.health_threshold();
+        let previous = tx.health_alert();
+        let alert = HealthAlert {
+            disk: exceeds(health.disk, threshold.disk, previous.disk),
+            temp: exceeds(health.temp, threshold.temp, previous.temp),
+            mem: exceeds(health.mem, threshold.mem, previous.mem),
+            services: health.failed.clone().unwrap_or(previous.services),
+        };
+        tx.set_health_alert(&alert)?;
+        Ok((threshold, alert))
+    })?;
+
+    let disk = notify::Alert {
+        name: "disk".to_string(),
+        title: "DISK USAGE WARNING".to_string(),
+        message: format!(
+            "The root filesystem is {}% full, passing the threshold of {}%.",
+            reading(health.disk),
+            threshold.disk
+        ),
+    };
+    let temp = notify::Alert {
+        name: "temp".to_string(),
+        title: "CPU TEMPERATURE WARNING".to_string(),
+        message: format!(
+            "The CPU temperature is {} C, passing the threshold of {} C.",
+            reading(health.temp),
+            threshold.temp
+        ),
+    };
+    let mem = notify::Alert {
+        name: "mem".to_string(),
+        title: "MEMORY WARNING".to_string(),
+        message: format!(
+            "{}% of memory is in use, passing the threshold of {}%.",
+            reading(health.mem),
+            threshold.mem
+        ),
+    };
+
+    for (name, active, enabled, notification) in [
+        ("disk", alert.disk, threshold.disk_flag, disk),
+        ("temp", alert.temp, threshold.temp_flag, temp),
+        ("mem", alert.mem, threshold.mem_flag, mem),
+    ] {
+        let mut sent = store.transaction(|tx| Ok(tx.health_sent(name)))?;
+        if !active {
+            // the alert is sent again the next time it turns on
+            if !sent.is_empty() {
+                store.transaction(|tx| tx.set_health_sent(name, &[]))?;
+            }
+        // channels which could not deliver the alert are retried on the next pass
+        } else if enabled && notify::send(notifiers, &notification, &mut sent) {
+            store.transaction(|tx| tx.set_health_sent(name, &sent))?;
+        }
+    }
+
+    // forget services which have recovered, so that a later failure of the
+    // same service is reported again
+    let mut notified: BTreeMap<String, Vec<String>> = store
+        .transaction(|tx| Ok(tx.services_sent()))?
+        .into_iter()
+        .map(|(channel, services)| {
+            let services = services
+                .into_iter()
+                .filter(|service| alert.services.contains(service))
+                .collect();
+            (channel, services)
+        })
+        .collect();
+    if threshold.service_flag {
+        // each channel is notified of the failures it has not yet delivered
+        for notifier in notifiers {
+            let notified = notified.entry(notifier.name().to_string()).or_default();
+            let failed: Vec<String> = alert
+                .services
+                .iter()
+                .filter(|service| !notified.contains(service))
+                .cloned()
+                .collect();
+            if failed.is_empty() {
+                continue;
+            }
+            let notification = notify::Alert {
+                name: "service".to_string(),
+                title: "SERVICE FAILURE".to_string(),
+                message: format!("Failed: {}.", failed.join(", ")),
+            };
+            if notify::deliver(&**notifier, &notification) {
+                notified.extend(failed);
+            }
+        }
+    }
+    store.transaction(|tx| tx.set_services_sent(&notified))?;
+
+    Ok(())
+}
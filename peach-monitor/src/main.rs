@@ -1,28 +1,32 @@
-use std::convert::TryInto;
+mod accounting;
+mod health;
+mod notify;
+
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::{thread, time};
 
-use nest::{Error, Store, Value};
-use probes::network;
-use serde_json::json;
+use peach_lib::byte_size::ByteSize;
+use peach_lib::error::PeachError;
+use peach_lib::monitor::{MonitorStore, Threshold};
+use peach_lib::network_client;
 use structopt::StructOpt;
 
 #[derive(StructOpt, Debug)]
 #[structopt(
     name = "peach-monitor",
-    about = "Monitor data usage and set alert flags"
+    about = "Monitor data usage, set alert flags and enforce the cutoff threshold"
 )]
 struct Opt {
     /// Run daemon
     #[structopt(short, long)]
     daemon: bool,
 
-    /// Define network interface
-    #[structopt(short, long, default_value = "wlan0")]
-    iface: String,
+    /// Define network interface (defaults to the interface providing uplink)
+    #[structopt(short, long)]
+    iface: Option<String>,
 
-    /// Save latest usage totals to file
+    /// Save latest usage totals and history to file
     #[structopt(short, long)]
     save: bool,
 
@@ -35,143 +39,144 @@ struct Opt {
     update: bool,
 }
 
-/// Network traffic total (bytes)
-#[derive(Debug)]
-struct Traffic {
-    total: u64, // bytes
+/// Evaluate the usage total against alert thresholds and set flags
+fn set_alert_flags(store: &MonitorStore, threshold: &Threshold) -> Result<(), PeachError> {
+    store.transaction(|tx| {
+        let total = ByteSize(tx.total());
+        tx.set_alert_flags(total > threshold.warn, total > threshold.cut)
+    })
 }
 
-impl Traffic {
-    /// Retrieve latest statistics for traffic
-    fn get(iface: &str) -> Option<Traffic> {
-        let network = network::read().expect("IO error when executing network command");
-        for (interface, data) in network.interfaces {
-            if interface == iface {
-                let rx = data.received;
-                let tx = data.transmitted;
-                let total = rx + tx;
-                let t = Traffic { total };
-                return Some(t);
-            };
+/// Block internet traffic while the cutoff alert is active and enforcement is
+/// enabled, and restore it once the cutoff no longer applies (counters reset,
+/// threshold raised, enforcement disabled or overridden by the user). Traffic
+/// to the local networks is never blocked, so the device remains reachable for
+/// the user to override the cutoff. The block is reapplied on every pass, since
+/// networkd removes routes it does not manage when it is restarted. Routes are
+/// changed while holding the store lock, so that an override made by the user
+/// meanwhile is not undone
+fn enforce_cutoff(store: &MonitorStore, iface: Option<&str>) -> Result<(), PeachError> {
+    store.transaction(|tx| {
+        let alert = tx.alert();
+
+        if alert.cut && tx.threshold().cut_flag && !alert.cut_override {
+            match network_client::set_cutoff(true) {
+                Ok(_) if alert.cut_iface.is_none() => {
+                    let iface = iface
+                        .map(str::to_string)
+                        .unwrap_or_else(network_client::uplink_iface);
+                    println!("Cutoff threshold reached: blocked traffic on {}", iface);
+                    tx.set_cut_iface(Some(&iface))?;
+                }
+                Ok(_) => (),
+                Err(e) => eprintln!("Failed to block traffic: {}", e),
+            }
+        } else if let Some(iface) = alert.cut_iface {
+            match network_client::set_cutoff(false) {
+                Ok(_) => {
+                    println!("Cutoff lifted: restored traffic on {}", iface);
+                    tx.set_cut_iface(None)?;
+                }
+                // leave the interface recorded so that the next pass retries
+                Err(e) => eprintln!("Failed to restore traffic: {}", e),
+            }
         }
-        None
-    }
-}
-
-/// Warning and cutoff network traffic threshold (bytes)
-struct Threshold {
-    warn: u64, // warning threshold (bytes)
-    cut: u64,  // cutoff threshold (bytes)
-}
 
-impl Threshold {
-    /// Retrieve latest alert threshold from the data store
-    fn get(store: &Store) -> Threshold {
-        let mut threshold = Vec::new();
-
-        let warn_val = store
-            .get(&["net", "notify", "warn"])
-            .unwrap_or(Value::Uint(0));
-        if let Value::Uint(val) = warn_val {
-            threshold.push(val);
-        };
-
-        let cut_val = store
-            .get(&["net", "notify", "cut"])
-            .unwrap_or(Value::Uint(0));
-        if let Value::Uint(val) = cut_val {
-            threshold.push(val);
-        };
-
-        Threshold {
-            warn: threshold[0],
-            cut: threshold[1],
+        // an override only applies until the cutoff alert clears
+        if !alert.cut && alert.cut_override {
+            tx.set_cut_override(false)?;
         }
-    }
-}
 
-/// Convert a megabyte value to bytes
-fn to_bytes(val: u64) -> u64 {
-    (val * 1024) * 1024
+        Ok(())
+    })
 }
 
-/// Evaluate traffic values against alert thresholds and set flags
-fn set_alert_flags(store: &Store, threshold: &Threshold) -> Result<(), Error> {
-    let stored_total = store.get(&["net", "traffic", "total"])?;
-    if let Value::Uint(total) = stored_total {
-        // total is in bytes while warn is in megabytes
-        if total > to_bytes(threshold.warn) {
-            store.set(&["net", "alert", "warn_alert"], &Value::Bool(true))?;
-        } else {
-            store.set(&["net", "alert", "warn_alert"], &Value::Bool(false))?;
+/// Notify the user of active alerts. Each alert is sent through each channel
+/// once per billing cycle: the start date of the cycle is recorded for every
+/// channel which has delivered it
+fn send_alerts(
+    store: &MonitorStore,
+    threshold: &Threshold,
+    notifiers: &[Box<dyn notify::Notifier>],
+) -> Result<(), PeachError> {
+    let (cycle, total, alert) = store
+        .transaction(|tx| Ok((tx.cycle_start().unwrap_or_default(), tx.total(), tx.alert())))?;
+    let total = ByteSize(total);
+
+    let warn = notify::Alert {
+        name: "data_warn".to_string(),
+        title: "DATA USAGE WARNING".to_string(),
+        message: format!(
+            "{} of data used, passing the warning threshold of {}.",
+            total, threshold.warn
+        ),
+    };
+    let cut = notify::Alert {
+        name: "data_cut".to_string(),
+        title: "DATA USAGE CUTOFF".to_string(),
+        message: match alert.cut_iface {
+            Some(iface) => format!(
+                "{} of data used, passing the cutoff threshold of {}. Internet access through {} has been blocked.",
+                total, threshold.cut, iface
+            ),
+            None => format!(
+                "{} of data used, passing the cutoff threshold of {}.",
+                total, threshold.cut
+            ),
+        },
+    };
+
+    for (name, active, enabled, notification) in [
+        ("warn", alert.warn, threshold.warn_flag, warn),
+        ("cut", alert.cut, threshold.cut_flag, cut),
+    ] {
+        if !(active && enabled) {
+            continue;
         }
-        if total > to_bytes(threshold.cut) {
-            store.set(&["net", "alert", "cut_alert"], &Value::Bool(true))?;
-        } else {
-            store.set(&["net", "alert", "cut_alert"], &Value::Bool(false))?;
+        // channels which could not deliver the alert are retried on the next pass
+        let mut sent = store.transaction(|tx| Ok(tx.alert_sent(name, &cycle)))?;
+        if notify::send(notifiers, &notification, &mut sent) {
+            store.transaction(|tx| tx.set_alert_sent(name, &cycle, &sent))?;
         }
     }
 
     Ok(())
 }
 
-/// Calculate and store the latest network transmission totals
-fn update_transmission_totals(iface: &str, store: &Store) -> Result<(), Error> {
-    // retrieve previous network traffic statistics
-    let stored_total = match store.get(&["net", "traffic", "total"]) {
-        Ok(total) => total,
-        // return 0 if no value exists
-        Err(_) => Value::Uint(u64::MIN),
-    };
-
-    // retrieve latest network traffic statistics
-    let traffic = Traffic::get(iface).expect("Error while retrieving network traffic statistics");
-
-    // store updated network traffic statistics (totals)
-    if let Value::Uint(total) = stored_total {
-        let updated_total = total + traffic.total;
-        let total_value = Value::Uint(updated_total);
-        store.set(&["net", "traffic", "total"], &total_value)?;
-    };
-
-    Ok(())
-}
-
-fn main() -> Result<(), Error> {
+fn main() -> Result<(), PeachError> {
     // parse cli arguments
     let opt = Opt::from_args();
 
-    // define the path
-    let path = xdg::BaseDirectories::new()
-        .unwrap()
-        .create_data_directory("peachcloud")
-        .unwrap();
-
-    // define the schema
-    let schema = json!({
-        "net": {
-            "traffic": "json",
-            "notify": "json",
-            "alert": "json"
-        }
-    })
-    .try_into()?;
-
-    // create the data store
-    let store = Store::new(path, schema);
+    // open the data store (shared with peach-web)
+    let store = MonitorStore::open()?;
 
     // update network transmission totals
     if opt.save {
-        update_transmission_totals(&opt.iface, &store).unwrap();
+        let iface = opt
+            .iface
+            .clone()
+            .unwrap_or_else(network_client::uplink_iface);
+        accounting::record(&store, &iface)?;
     }
 
     // update alert flags
     if opt.update {
         // retrieve alert thresholds
-        let threshold = Threshold::get(&store);
+        let threshold = store.threshold()?;
 
         // test transmission totals against alert thresholds and set flags
         set_alert_flags(&store, &threshold)?;
+
+        // block or restore internet traffic according to the cutoff alert
+        enforce_cutoff(&store, opt.iface.as_deref())?;
+
+        let notifiers = notify::notifiers();
+
+        // notify the user of newly-active alerts
+        send_alerts(&store, &threshold, &notifiers)?;
+
+        // check system health, set health alert flags and notify the user
+        health::check(&store, &notifiers)?;
     }
 
     if opt.daemon {
@@ -183,14 +188,46 @@ fn main() -> Result<(), Error> {
         .expect("Error setting Ctrl-C handler");
 
         let interval = time::Duration::from_secs(opt.interval);
+        let notifiers = notify::notifiers();
 
-        // run loop until SIGINT or SIGTERM is received
+        // run loop until SIGINT or SIGTERM is received. Errors are logged
+        // rather than ending the loop, so that a step which fails (for example
+        // while peach-network is restarting) is retried on the next pass
         while running.load(Ordering::SeqCst) {
-            // retrieve alert thresholds
-            let threshold = Threshold::get(&store);
+            // add traffic since the previous pass to the totals and history
+            let iface = opt
+                .iface
+                .clone()
+                .unwrap_or_else(network_client::uplink_iface);
+            if let Err(e) = accounting::record(&store, &iface) {
+                eprintln!("Failed to record network usage: {}", e);
+            }
 
-            // test transmission totals against alert threshold and set flags
-            set_alert_flags(&store, &threshold)?;
+            // retrieve alert thresholds
+            match store.threshold() {
+                Ok(threshold) => {
+                    // test transmission totals against alert threshold and set flags
+                    if let Err(e) = set_alert_flags(&store, &threshold) {
+                        eprintln!("Failed to update alert flags: {}", e);
+                    }
+
+                    // block or restore internet traffic according to the cutoff alert
+                    if let Err(e) = enforce_cutoff(&store, opt.iface.as_deref()) {
+                        eprintln!("Failed to enforce cutoff: {}", e);
+                    }
+
+                    // notify the user of newly-active alerts
+                    if let Err(e) = send_alerts(&store, &threshold, &notifiers) {
+                        eprintln!("Failed to send alerts: {}", e);
+                    }
+                }
+                Err(e) => eprintln!("Failed to read alert thresholds: {}", e),
+            }
+
+            // check system health, set health alert flags and notify the user
+            if let Err(e) = health::check(&store, &notifiers) {
+                eprintln!("Failed to check system health: {}", e);
+            }
 
             thread::sleep(interval);
         }
@@ -0,0 +1,200 @@
+//! Deliver alerts to the user through one or more notification channels.
+//!
+//! Each channel implements the `Notifier` trait. The available channels are
+//! a Scuttlebutt private message to every configured admin, a message on the
+//! OLED display and (if `alert_webhook` is set in the PeachCloud config) a
+//! JSON `POST` request to a local webhook.
+
+use std::io::{self, prelude::*};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+use peach_lib::config_manager;
+use peach_lib::error::PeachError;
+use peach_lib::oled_client::{self, Op, ALERT_LAYER};
+use peach_lib::sbot_client;
+use serde_json::json;
+
+/// Connection, read and write timeout for webhook requests.
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// An alert to be delivered to the user.
+#[derive(Debug)]
+pub struct Alert {
+    /// Machine-readable name of the alert (eg. `data_warn`)
+    pub name: String,
+    /// Short title, shown as the first line on the OLED display
+    pub title: String,
+    /// Full description of the alert
+    pub message: String,
+}
+
+/// A channel through which alerts are delivered.
+pub trait Notifier {
+    /// Name of the channel, used in log messages.
+    fn name(&self) -> &str;
+
+    /// Deliver the alert.
+    fn notify(&self, alert: &Alert) -> Result<(), PeachError>;
+}
+
+/// Send a Scuttlebutt private message to every configured admin. A message
+/// which can't be delivered to one admin is still sent to the others.
+pub struct SsbNotifier;
+
+impl Notifier for SsbNotifier {
+    fn name(&self) -> &str {
+        "ssb"
+    }
+
+    fn notify(&self, alert: &Alert) -> Result<(), PeachError> {
+        let msg = format!("{}\n\n{}", alert.title, alert.message);
+        let peach_config = config_manager::load_peach_config()?;
+        let mut result = Ok(());
+        for ssb_admin_id in peach_config.ssb_admin_ids {
+            if let Err(e) = sbot_client::private_message(&msg, &ssb_admin_id) {
+                eprintln!("Failed to send alert to {}: {}", ssb_admin_id, e);
+                result = Err(e);
+            }
+        }
+        result
+    }
+}
+
+/// Show the alert on the OLED display, in a layer above the menu. The layer
+/// is removed by the menu the next time a button is pressed.
+pub struct OledNotifier;
+
+impl Notifier for OledNotifier {
+    fn name(&self) -> &str {
+        "oled"
+    }
+
+    fn notify(&self, alert: &Alert) -> Result<(), PeachError> {
+        // text boxes are cleared before drawing, so the layer hides the
+        // menu below it
+        let ops = vec![
+            Op::text(0, 0, 128, 16, &alert.title, "6x8", "wrap"),
+            Op::text(0, 16, 128, 48, &alert.message, "6x8", "wrap"),
+        ];
+        oled_client::render(ALERT_LAYER, 1, ops)
+    }
+}
+
+/// Send the alert as JSON in an HTTP `POST` request to a local webhook.
+/// Only plain `http://` URLs are supported.
+pub struct WebhookNotifier {
+    pub url: String,
+}
+
+impl Notifier for WebhookNotifier {
+    fn name(&self) -> &str {
+        "webhook"
+    }
+
+    fn notify(&self, alert: &Alert) -> Result<(), PeachError> {
+        let body = json!({
+            "name": alert.name,
+            "title": alert.title,
+            "message": alert.message,
+        })
+        .to_string();
+        post(&self.url, &body).map_err(|source| PeachError::StdIoError {
+            source,
+            msg: format!("Failed to send alert to webhook {}", self.url),
+        })
+    }
+}
+
+/// Build the list of notification channels from the PeachCloud config.
+pub fn notifiers() -> Vec<Box<dyn Notifier>> {
+    let mut notifiers: Vec<Box<dyn Notifier>> = vec![Box::new(SsbNotifier), Box::new(OledNotifier)];
+    if let Ok(peach_config) = config_manager::load_peach_config() {
+        if !peach_config.alert_webhook.is_empty() {
+            notifiers.push(Box::new(WebhookNotifier {
+                url: peach_config.alert_webhook,
+            }));
+        }
+    }
+    notifiers
+}
+
+/// Deliver the alert through a single channel. Returns `true` if the channel
+/// delivered the alert.
+pub fn deliver(notifier: &dyn Notifier, alert: &Alert) -> bool {
+    match notifier.notify(alert) {
+        Ok(_) => {
+            println!("Sent {} alert via {}", alert.name, notifier.name());
+            true
+        }
+        Err(e) => {
+            eprintln!(
+                "Failed to send {} alert via {}: {}",
+                alert.name,
+                notifier.name(),
+                e
+            );
+            false
+        }
+    }
+}
+
+/// Deliver the alert through every channel which is not listed in `sent`,
+/// adding each channel which delivers it to the list. Channels which fail can
+/// then be retried later without repeating the alert on the others. Returns
+/// `true` if the alert was delivered through any channel.
+pub fn send(notifiers: &[Box<dyn Notifier>], alert: &Alert, sent: &mut Vec<String>) -> bool {
+    let mut delivered = false;
+    for notifier in notifiers {
+        if !sent.iter().any(|channel| channel == notifier.name()) && deliver(&**notifier, alert) {
+            sent.push(notifier.name().to_string());
+            delivered = true;
+        }
+    }
+    delivered
+}
+
+/// Make an HTTP/1.1 `POST` request with a JSON body and check for a `2xx`
+/// response status.
+fn post(url: &str, body: &str) -> io::Result<()> {
+    let invalid = || {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "expected http://host[:port]/path",
+        )
+    };
+    let rest = url.strip_prefix("http://").ok_or_else(invalid)?;
+    let (authority, path) = match rest.find('/') {
+        Some(i) => (&rest[..i], &rest[i..]),
+        None => (rest, "/"),
+    };
+    let addr = if authority.contains(':') {
+        authority.to_string()
+    } else {
+        format!("{}:80", authority)
+    };
+    let addr = addr.to_socket_addrs()?.next().ok_or_else(invalid)?;
+
+    let mut stream = TcpStream::connect_timeout(&addr, WEBHOOK_TIMEOUT)?;
+    stream.set_read_timeout(Some(WEBHOOK_TIMEOUT))?;
+    stream.set_write_timeout(Some(WEBHOOK_TIMEOUT))?;
+    write!(
+        stream,
+        "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        path,
+        authority,
+        body.len(),
+        body
+    )?;
+
+    // status line, eg. `HTTP/1.1 200 OK`
+    let mut response = String::new();
+    io::BufReader::new(stream).read_line(&mut response)?;
+    match response.split_whitespace().nth(1) {
+        Some(code) if code.starts_with('2') => Ok(()),
+        _ => Err(io::Error::other(format!(
+            "unexpected response: {}",
+            response.trim()
+        ))),
+    }
+}
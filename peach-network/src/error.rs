@@ -92,6 +92,9 @@ pub enum NetworkError {
     #[snafu(display("Failed to reconnect with WiFi network for interface: {}", iface))]
     Reconnect { iface: String },
 
+    #[snafu(display("Failed to reorder saved networks for interface: {}", iface))]
+    Reorder { iface: String },
+
     #[snafu(display("Regex command failed"))]
     Regex { source: regex::Error },
 
@@ -107,6 +110,9 @@ pub enum NetworkError {
     #[snafu(display("Failed to save configuration changes to file"))]
     Save,
 
+    #[snafu(display("Failed to set priority for network {} on interface: {}", id, iface))]
+    SetPriority { id: String, iface: String },
+
     #[snafu(display("Failed to connect to network {} for interface: {}", id, iface))]
     Connect { id: String, iface: String },
 
@@ -119,6 +125,58 @@ pub enum NetworkError {
     #[snafu(display("JSON serialization failed: {}", source))]
     SerdeSerialize { source: SerdeError },
 
+    #[snafu(display("No systemd-networkd configuration found for interface: {}", iface))]
+    NetworkFile { iface: String },
+
+    #[snafu(display("Invalid network configuration for {}: {}", iface, err_msg))]
+    InvalidConfig { iface: String, err_msg: String },
+
+    #[snafu(display("Failed to read network configuration file {}: {}", path, source))]
+    ReadConfig { path: String, source: io::Error },
+
+    #[snafu(display("Failed to write network configuration file {}: {}", path, source))]
+    WriteConfig { path: String, source: io::Error },
+
+    #[snafu(display("Failed to restart systemd-networkd service: {}", source))]
+    ReloadNetworkd { source: io::Error },
+
+    #[snafu(display(
+        "Lost connectivity on {} within {} seconds. Previous configuration restored",
+        iface,
+        timeout
+    ))]
+    ConfigRollback { iface: String, timeout: u64 },
+
+    #[snafu(display("Failed to list network interfaces: {}", source))]
+    ListInterfaces { source: io::Error },
+
+    #[snafu(display("Failed to read routing table: {}", source))]
+    NoRoutes { source: io::Error },
+
+    #[snafu(display("No interface is currently providing a default route"))]
+    NoUplink,
+
+    #[snafu(display("No such network interface: {}", iface))]
+    NoSuchInterface { iface: String },
+
+    #[snafu(display("Permission denied when accessing {}", path))]
+    PermissionDenied { path: String },
+
+    #[snafu(display("Failed to read {}: {}", path, source))]
+    ReadSysfs { path: String, source: io::Error },
+
+    #[snafu(display("Failed to deauthenticate station {} on interface: {}", mac, iface))]
+    Deauthenticate { iface: String, mac: String },
+
+    #[snafu(display("Invalid MAC address: {}", mac))]
+    InvalidMac { mac: String },
+
+    #[snafu(display("Failed to retrieve stations for interface: {}", iface))]
+    Stations { iface: String },
+
+    #[snafu(display("Failed to set data usage cutoff: {}", source))]
+    SetCutoff { source: io::Error },
+
     #[snafu(display("Failed to open control interface for wpasupplicant"))]
     WpaCtrlOpen {
         #[snafu(source(from(failure::Error, std::convert::Into::into)))]
@@ -285,6 +343,11 @@ impl From<NetworkError> for Error {
                 message: format!("Failed to reconnect with WiFi network for {}", iface),
                 data: None,
             },
+            NetworkError::Reorder { iface } => Error {
+                code: ErrorCode::ServerError(-32037),
+                message: format!("Failed to reorder saved networks for {}", iface),
+                data: None,
+            },
             NetworkError::Regex { source } => Error {
                 code: ErrorCode::ServerError(-32010),
                 message: format!("Regex command error: {}", source),
@@ -313,6 +376,11 @@ impl From<NetworkError> for Error {
                 message: "Failed to save configuration changes to file".to_string(),
                 data: None,
             },
+            NetworkError::SetPriority { id, iface } => Error {
+                code: ErrorCode::ServerError(-32038),
+                message: format!("Failed to set priority for network {} on {}", id, iface),
+                data: None,
+            },
             NetworkError::Connect { id, iface } => Error {
                 code: ErrorCode::ServerError(-32027),
                 message: format!("Failed to connect to network {} for {}", id, iface),
@@ -333,6 +401,101 @@ impl From<NetworkError> for Error {
                 message: format!("JSON serialization failed: {}", source),
                 data: None,
             },
+            NetworkError::NetworkFile { iface } => Error {
+                code: ErrorCode::ServerError(-32039),
+                message: format!("No systemd-networkd configuration found for {}", iface),
+                data: None,
+            },
+            NetworkError::InvalidConfig { iface, err_msg } => Error {
+                code: ErrorCode::ServerError(-32040),
+                message: format!(
+                    "Validation error: invalid network configuration for {}: {}",
+                    iface, err_msg
+                ),
+                data: None,
+            },
+            NetworkError::ReadConfig { path, source } => Error {
+                code: ErrorCode::ServerError(-32041),
+                message: format!(
+                    "Failed to read network configuration file {}: {}",
+                    path, source
+                ),
+                data: None,
+            },
+            NetworkError::WriteConfig { path, source } => Error {
+                code: ErrorCode::ServerError(-32042),
+                message: format!(
+                    "Failed to write network configuration file {}: {}",
+                    path, source
+                ),
+                data: None,
+            },
+            NetworkError::ReloadNetworkd { source } => Error {
+                code: ErrorCode::ServerError(-32043),
+                message: format!("Failed to restart systemd-networkd service: {}", source),
+                data: None,
+            },
+            NetworkError::ConfigRollback { iface, timeout } => Error {
+                code: ErrorCode::ServerError(-32044),
+                message: format!(
+                    "Lost connectivity on {} within {} seconds. Previous configuration restored",
+                    iface, timeout
+                ),
+                data: None,
+            },
+            NetworkError::ListInterfaces { source } => Error {
+                code: ErrorCode::ServerError(-32045),
+                message: format!("Failed to list network interfaces: {}", source),
+                data: None,
+            },
+            NetworkError::NoRoutes { source } => Error {
+                code: ErrorCode::ServerError(-32046),
+                message: format!("Failed to read routing table: {}", source),
+                data: None,
+            },
+            NetworkError::NoUplink => Error {
+                code: ErrorCode::ServerError(-32047),
+                message: "No interface is currently providing a default route".to_string(),
+                data: None,
+            },
+            NetworkError::NoSuchInterface { iface } => Error {
+                code: ErrorCode::ServerError(-32048),
+                message: format!("No such network interface: {}", iface),
+                data: None,
+            },
+            NetworkError::PermissionDenied { path } => Error {
+                code: ErrorCode::ServerError(-32049),
+                message: format!("Permission denied when accessing {}", path),
+                data: None,
+            },
+            NetworkError::ReadSysfs { path, source } => Error {
+                code: ErrorCode::ServerError(-32050),
+                message: format!("Failed to read {}: {}", path, source),
+                data: None,
+            },
+            NetworkError::Deauthenticate { iface, mac } => Error {
+                code: ErrorCode::ServerError(-32051),
+                message: format!("Failed to deauthenticate station {} on {}", mac, iface),
+                data: None,
+            },
+            NetworkError::InvalidMac { mac } => Error {
+                code: ErrorCode::ServerError(-32052),
+                message: format!("Validation error: invalid MAC address: {}", mac),
+                data: None,
+            },
+            NetworkError::Stations { iface } => Error {
+                code: ErrorCode::ServerError(-32053),
+                message: format!(
+                    "Failed to retrieve stations for {}. Access point may not be active",
+                    iface
+                ),
+                data: None,
+            },
+            NetworkError::SetCutoff { source } => Error {
+                code: ErrorCode::ServerError(-32054),
+                message: format!("Failed to set data usage cutoff: {}", source),
+                data: None,
+            },
             NetworkError::WpaCtrlOpen { source } => Error {
                 code: ErrorCode::ServerError(-32013),
                 message: format!(
@@ -7,14 +7,19 @@
 //! The `src/network.rs` module contains the core networking logic and data
 //! types for interacting with the `wpa_supplicant` process and related parts of
 //! the operating system, while the `src/error.rs` module contains
-//! error-handling data types and methods.
+//! error-handling data types and methods. The `src/networkd.rs` module reads
+//! and writes the systemd-networkd configuration (DHCP or static addressing,
+//! gateway and DNS servers) for each interface, and the `src/netlink.rs`
+//! module retrieves interface addresses and link events via rtnetlink.
 //!
 //! `src/main.rs` initializes the logger, starts the application and catches
 //! application errors, while `src/lib.rs` contains the JSON-RPC server, RPC
 //! methods, HTTP server and tests.
 //!
 mod error;
+pub mod netlink;
 pub mod network;
+pub mod networkd;
 mod utils;
 
 use std::env;
@@ -28,7 +33,10 @@ use log::info;
 use serde_json::json;
 
 use crate::error::{BoxError, NetworkError};
-use crate::network::{Iface, IfaceId, IfaceIdPass, IfaceSsid, WiFi};
+use crate::network::{
+    Cutoff, Iface, IfaceId, IfaceIdPass, IfaceIdPriority, IfaceIds, IfaceMac, IfaceSsid, WiFi,
+};
+use crate::networkd::NetConfigUpdate;
 
 /// Create JSON-RPC I/O handler, add RPC methods and launch HTTP server.
 pub fn run() -> Result<(), BoxError> {
@@ -82,6 +90,25 @@ pub fn run() -> Result<(), BoxError> {
         }
     });
 
+    io.add_method("list_interfaces", move |_| {
+        let list = network::list_interfaces()?;
+
+        Ok(Value::String(list))
+    });
+
+    io.add_method("net_config", move |params: Params| {
+        let i: Result<Iface, Error> = params.parse();
+        match i {
+            Ok(i) => {
+                let iface = i.iface;
+                let config = networkd::net_config(&iface)?;
+                let json_config = json!(config);
+                Ok(Value::String(json_config.to_string()))
+            }
+            Err(e) => Err(Error::from(NetworkError::MissingParams { e })),
+        }
+    });
+
     io.add_method("ping", |_| Ok(Value::String("success".to_string())));
 
     io.add_method("rssi", move |params: Params| {
@@ -148,6 +175,20 @@ pub fn run() -> Result<(), BoxError> {
         }
     });
 
+    io.add_method("stations", move |params: Params| {
+        let i: Result<Iface, Error> = params.parse();
+        match i {
+            Ok(i) => {
+                let iface = i.iface;
+                match network::stations(&iface) {
+                    Ok(list) => Ok(Value::String(list)),
+                    Err(_) => Err(Error::from(NetworkError::Stations { iface })),
+                }
+            }
+            Err(e) => Err(Error::from(NetworkError::MissingParams { e })),
+        }
+    });
+
     io.add_method("status", move |params: Params| {
         let i: Result<Iface, Error> = params.parse();
         match i {
@@ -179,6 +220,11 @@ pub fn run() -> Result<(), BoxError> {
         }
     });
 
+    io.add_method("uplink", move |_| match network::uplink()? {
+        Some(iface) => Ok(Value::String(iface)),
+        None => Err(Error::from(NetworkError::NoUplink)),
+    });
+
     /* SET - All RPC methods for modifying state */
 
     io.add_method("activate_ap", move |_| {
@@ -210,6 +256,18 @@ pub fn run() -> Result<(), BoxError> {
         Ok(Value::String("success".to_string()))
     });
 
+    io.add_method("deauthenticate", move |params: Params| {
+        let m: Result<IfaceMac, Error> = params.parse();
+        match m {
+            Ok(m) => {
+                network::deauthenticate(&m.iface, &m.mac)?;
+
+                Ok(Value::String("success".to_string()))
+            }
+            Err(e) => Err(Error::from(NetworkError::MissingParams { e })),
+        }
+    });
+
     io.add_method("delete", move |params: Params| {
         let i: Result<IfaceId, Error> = params.parse();
         match i {
@@ -303,11 +361,67 @@ pub fn run() -> Result<(), BoxError> {
         }
     });
 
+    io.add_method("reorder", move |params: Params| {
+        let i: Result<IfaceIds, Error> = params.parse();
+        match i {
+            Ok(i) => {
+                let iface = i.iface;
+                let ids = i.ids;
+                match network::reorder(&iface, &ids) {
+                    Ok(_) => Ok(Value::String("success".to_string())),
+                    Err(_) => Err(Error::from(NetworkError::Reorder { iface })),
+                }
+            }
+            Err(e) => Err(Error::from(NetworkError::MissingParams { e })),
+        }
+    });
+
     io.add_method("save", move |_| match network::save() {
         Ok(_) => Ok(Value::String("success".to_string())),
         Err(_) => Err(Error::from(NetworkError::Save)),
     });
 
+    io.add_method("set_cutoff", move |params: Params| {
+        let c: Result<Cutoff, Error> = params.parse();
+        match c {
+            Ok(c) => {
+                network::set_cutoff(c.cut)?;
+
+                Ok(Value::String("success".to_string()))
+            }
+            Err(e) => Err(Error::from(NetworkError::MissingParams { e })),
+        }
+    });
+
+    io.add_method("set_net_config", move |params: Params| {
+        let u: Result<NetConfigUpdate, Error> = params.parse();
+        match u {
+            Ok(u) => {
+                let (config, timeout) = u.into_parts();
+                networkd::set_net_config(&config, timeout)?;
+
+                Ok(Value::String("success".to_string()))
+            }
+            Err(e) => Err(Error::from(NetworkError::MissingParams { e })),
+        }
+    });
+
+    io.add_method("set_priority", move |params: Params| {
+        let i: Result<IfaceIdPriority, Error> = params.parse();
+        match i {
+            Ok(i) => {
+                let id = i.id;
+                let iface = i.iface;
+                let priority = i.priority;
+                match network::set_priority(&id, &iface, priority) {
+                    Ok(_) => Ok(Value::String("success".to_string())),
+                    Err(_) => Err(Error::from(NetworkError::SetPriority { id, iface })),
+                }
+            }
+            Err(e) => Err(Error::from(NetworkError::MissingParams { e })),
+        }
+    });
+
     io.add_method("connect", move |params: Params| {
         let i: Result<IfaceId, Error> = params.parse();
         match i {
@@ -836,6 +950,28 @@ mod tests {
         );
     }
 
+    // test to ensure correct Reorder error response
+    #[test]
+    fn rpc_reorder_error() {
+        let rpc = {
+            let mut io = IoHandler::new();
+            io.add_method("rpc_reorder_error", |_| {
+                Err(Error::from(NetworkError::Reorder {
+                    iface: "wlan0".to_string(),
+                }))
+            });
+            test::Rpc::from(io)
+        };
+
+        assert_eq!(
+            rpc.request("rpc_reorder_error", &()),
+            r#"{
+  "code": -32037,
+  "message": "Failed to reorder saved networks for wlan0"
+}"#
+        );
+    }
+
     // test to ensure correct Regex error response
     #[test]
     fn rpc_regex_error() {
@@ -940,6 +1076,382 @@ mod tests {
         );
     }
 
+    // test to ensure correct SetPriority error response
+    #[test]
+    fn rpc_setpriority_error() {
+        let rpc = {
+            let mut io = IoHandler::new();
+            io.add_method("rpc_setpriority_error", |_| {
+                Err(Error::from(NetworkError::SetPriority {
+                    id: "1".to_string(),
+                    iface: "wlan0".to_string(),
+                }))
+            });
+            test::Rpc::from(io)
+        };
+
+        assert_eq!(
+            rpc.request("rpc_setpriority_error", &()),
+            r#"{
+  "code": -32038,
+  "message": "Failed to set priority for network 1 on wlan0"
+}"#
+        );
+    }
+
+    // test to ensure correct NetworkFile error response
+    #[test]
+    fn rpc_networkfile_error() {
+        let rpc = {
+            let mut io = IoHandler::new();
+            io.add_method("rpc_networkfile_error", |_| {
+                Err(Error::from(NetworkError::NetworkFile {
+                    iface: "eth1".to_string(),
+                }))
+            });
+            test::Rpc::from(io)
+        };
+
+        assert_eq!(
+            rpc.request("rpc_networkfile_error", &()),
+            r#"{
+  "code": -32039,
+  "message": "No systemd-networkd configuration found for eth1"
+}"#
+        );
+    }
+
+    // test to ensure correct InvalidConfig error response
+    #[test]
+    fn rpc_invalidconfig_error() {
+        let rpc = {
+            let mut io = IoHandler::new();
+            io.add_method("rpc_invalidconfig_error", |_| {
+                Err(Error::from(NetworkError::InvalidConfig {
+                    iface: "eth0".to_string(),
+                    err_msg: "gateway must be an IP address".to_string(),
+                }))
+            });
+            test::Rpc::from(io)
+        };
+
+        assert_eq!(
+            rpc.request("rpc_invalidconfig_error", &()),
+            r#"{
+  "code": -32040,
+  "message": "Validation error: invalid network configuration for eth0: gateway must be an IP address"
+}"#
+        );
+    }
+
+    // test to ensure correct ReadConfig error response
+    #[test]
+    fn rpc_readconfig_error() {
+        let rpc = {
+            let mut io = IoHandler::new();
+            io.add_method("rpc_readconfig_error", |_| {
+                Err(Error::from(NetworkError::ReadConfig {
+                    path: "/run/systemd/netif/links/2".to_string(),
+                    source: IoError::new(ErrorKind::NotFound, "oh no!"),
+                }))
+            });
+            test::Rpc::from(io)
+        };
+
+        assert_eq!(
+            rpc.request("rpc_readconfig_error", &()),
+            r#"{
+  "code": -32041,
+  "message": "Failed to read network configuration file /run/systemd/netif/links/2: oh no!"
+}"#
+        );
+    }
+
+    // test to ensure correct WriteConfig error response
+    #[test]
+    fn rpc_writeconfig_error() {
+        let rpc = {
+            let mut io = IoHandler::new();
+            io.add_method("rpc_writeconfig_error", |_| {
+                Err(Error::from(NetworkError::WriteConfig {
+                    path: "/etc/systemd/network/04-wired.network.d/50-peach.conf".to_string(),
+                    source: IoError::new(ErrorKind::PermissionDenied, "oh no!"),
+                }))
+            });
+            test::Rpc::from(io)
+        };
+
+        assert_eq!(
+            rpc.request("rpc_writeconfig_error", &()),
+            r#"{
+  "code": -32042,
+  "message": "Failed to write network configuration file /etc/systemd/network/04-wired.network.d/50-peach.conf: oh no!"
+}"#
+        );
+    }
+
+    // test to ensure correct ReloadNetworkd error response
+    #[test]
+    fn rpc_reloadnetworkd_error() {
+        let rpc = {
+            let mut io = IoHandler::new();
+            io.add_method("rpc_reloadnetworkd_error", |_| {
+                let source = IoError::new(ErrorKind::NotFound, "oh no!");
+                Err(Error::from(NetworkError::ReloadNetworkd { source }))
+            });
+            test::Rpc::from(io)
+        };
+
+        assert_eq!(
+            rpc.request("rpc_reloadnetworkd_error", &()),
+            r#"{
+  "code": -32043,
+  "message": "Failed to restart systemd-networkd service: oh no!"
+}"#
+        );
+    }
+
+    // test to ensure correct ConfigRollback error response
+    #[test]
+    fn rpc_configrollback_error() {
+        let rpc = {
+            let mut io = IoHandler::new();
+            io.add_method("rpc_configrollback_error", |_| {
+                Err(Error::from(NetworkError::ConfigRollback {
+                    iface: "eth0".to_string(),
+                    timeout: 30,
+                }))
+            });
+            test::Rpc::from(io)
+        };
+
+        assert_eq!(
+            rpc.request("rpc_configrollback_error", &()),
+            r#"{
+  "code": -32044,
+  "message": "Lost connectivity on eth0 within 30 seconds. Previous configuration restored"
+}"#
+        );
+    }
+
+    // test to ensure correct ListInterfaces error response
+    #[test]
+    fn rpc_listinterfaces_error() {
+        let rpc = {
+            let mut io = IoHandler::new();
+            io.add_method("rpc_listinterfaces_error", |_| {
+                let source = IoError::new(ErrorKind::NotFound, "oh no!");
+                Err(Error::from(NetworkError::ListInterfaces { source }))
+            });
+            test::Rpc::from(io)
+        };
+
+        assert_eq!(
+            rpc.request("rpc_listinterfaces_error", &()),
+            r#"{
+  "code": -32045,
+  "message": "Failed to list network interfaces: oh no!"
+}"#
+        );
+    }
+
+    // test to ensure correct NoRoutes error response
+    #[test]
+    fn rpc_noroutes_error() {
+        let rpc = {
+            let mut io = IoHandler::new();
+            io.add_method("rpc_noroutes_error", |_| {
+                let source = IoError::new(ErrorKind::NotFound, "oh no!");
+                Err(Error::from(NetworkError::NoRoutes { source }))
+            });
+            test::Rpc::from(io)
+        };
+
+        assert_eq!(
+            rpc.request("rpc_noroutes_error", &()),
+            r#"{
+  "code": -32046,
+  "message": "Failed to read routing table: oh no!"
+}"#
+        );
+    }
+
+    // test to ensure correct NoUplink error response
+    #[test]
+    fn rpc_nouplink_error() {
+        let rpc = {
+            let mut io = IoHandler::new();
+            io.add_method("rpc_nouplink_error", |_| {
+                Err(Error::from(NetworkError::NoUplink))
+            });
+            test::Rpc::from(io)
+        };
+
+        assert_eq!(
+            rpc.request("rpc_nouplink_error", &()),
+            r#"{
+  "code": -32047,
+  "message": "No interface is currently providing a default route"
+}"#
+        );
+    }
+
+    // test to ensure correct NoSuchInterface error response
+    #[test]
+    fn rpc_nosuchinterface_error() {
+        let rpc = {
+            let mut io = IoHandler::new();
+            io.add_method("rpc_nosuchinterface_error", |_| {
+                Err(Error::from(NetworkError::NoSuchInterface {
+                    iface: "wlan9".to_string(),
+                }))
+            });
+            test::Rpc::from(io)
+        };
+
+        assert_eq!(
+            rpc.request("rpc_nosuchinterface_error", &()),
+            r#"{
+  "code": -32048,
+  "message": "No such network interface: wlan9"
+}"#
+        );
+    }
+
+    // test to ensure correct PermissionDenied error response
+    #[test]
+    fn rpc_permissiondenied_error() {
+        let rpc = {
+            let mut io = IoHandler::new();
+            io.add_method("rpc_permissiondenied_error", |_| {
+                Err(Error::from(NetworkError::PermissionDenied {
+                    path: "/sys/class/net/wlan0/operstate".to_string(),
+                }))
+            });
+            test::Rpc::from(io)
+        };
+
+        assert_eq!(
+            rpc.request("rpc_permissiondenied_error", &()),
+            r#"{
+  "code": -32049,
+  "message": "Permission denied when accessing /sys/class/net/wlan0/operstate"
+}"#
+        );
+    }
+
+    // test to ensure correct ReadSysfs error response
+    #[test]
+    fn rpc_readsysfs_error() {
+        let rpc = {
+            let mut io = IoHandler::new();
+            io.add_method("rpc_readsysfs_error", |_| {
+                Err(Error::from(NetworkError::ReadSysfs {
+                    path: "/sys/class/net/eth0/speed".to_string(),
+                    source: IoError::new(ErrorKind::InvalidInput, "oh no!"),
+                }))
+            });
+            test::Rpc::from(io)
+        };
+
+        assert_eq!(
+            rpc.request("rpc_readsysfs_error", &()),
+            r#"{
+  "code": -32050,
+  "message": "Failed to read /sys/class/net/eth0/speed: oh no!"
+}"#
+        );
+    }
+
+    // test to ensure correct Deauthenticate error response
+    #[test]
+    fn rpc_deauthenticate_error() {
+        let rpc = {
+            let mut io = IoHandler::new();
+            io.add_method("rpc_deauthenticate_error", |_| {
+                Err(Error::from(NetworkError::Deauthenticate {
+                    iface: "ap0".to_string(),
+                    mac: "b8:27:eb:12:34:56".to_string(),
+                }))
+            });
+            test::Rpc::from(io)
+        };
+
+        assert_eq!(
+            rpc.request("rpc_deauthenticate_error", &()),
+            r#"{
+  "code": -32051,
+  "message": "Failed to deauthenticate station b8:27:eb:12:34:56 on ap0"
+}"#
+        );
+    }
+
+    // test to ensure correct InvalidMac error response
+    #[test]
+    fn rpc_invalidmac_error() {
+        let rpc = {
+            let mut io = IoHandler::new();
+            io.add_method("rpc_invalidmac_error", |_| {
+                Err(Error::from(NetworkError::InvalidMac {
+                    mac: "b8:27:eb".to_string(),
+                }))
+            });
+            test::Rpc::from(io)
+        };
+
+        assert_eq!(
+            rpc.request("rpc_invalidmac_error", &()),
+            r#"{
+  "code": -32052,
+  "message": "Validation error: invalid MAC address: b8:27:eb"
+}"#
+        );
+    }
+
+    // test to ensure correct Stations error response
+    #[test]
+    fn rpc_stations_error() {
+        let rpc = {
+            let mut io = IoHandler::new();
+            io.add_method("rpc_stations_error", |_| {
+                Err(Error::from(NetworkError::Stations {
+                    iface: "ap0".to_string(),
+                }))
+            });
+            test::Rpc::from(io)
+        };
+
+        assert_eq!(
+            rpc.request("rpc_stations_error", &()),
+            r#"{
+  "code": -32053,
+  "message": "Failed to retrieve stations for ap0. Access point may not be active"
+}"#
+        );
+    }
+
+    // test to ensure correct SetCutoff error response
+    #[test]
+    fn rpc_setcutoff_error() {
+        let rpc = {
+            let mut io = IoHandler::new();
+            io.add_method("rpc_setcutoff_error", |_| {
+                Err(Error::from(NetworkError::SetCutoff {
+                    source: IoError::new(ErrorKind::PermissionDenied, "oh no!"),
+                }))
+            });
+            test::Rpc::from(io)
+        };
+
+        assert_eq!(
+            rpc.request("rpc_setcutoff_error", &()),
+            r#"{
+  "code": -32054,
+  "message": "Failed to set data usage cutoff: oh no!"
+}"#
+        );
+    }
+
     // test to ensure correct WpaCtrlOpen error response
     #[test]
     fn rpc_wpactrlopen_error() {
@@ -0,0 +1,672 @@
+//! Retrieve interface addresses and link events via rtnetlink.
+//!
+//! This module contains a minimal `NETLINK_ROUTE` client which talks to the
+//! kernel directly over a netlink socket, avoiding the need to spawn `ip` or
+//! similar tools. Address dumps (`RTM_GETADDR`) are used to retrieve the IPv4
+//! and IPv6 addresses assigned to each interface, while a socket subscribed to
+//! the `RTMGRP_LINK` multicast group delivers link state changes as they
+//! happen. Traffic beyond the local networks is blocked with `RTM_NEWROUTE`
+//! requests.
+//!
+//! Message layouts and constants are defined in `linux/netlink.h`,
+//! `linux/rtnetlink.h`, `linux/if_addr.h` and `linux/if_link.h`.
+//!
+use std::{
+    collections::VecDeque,
+    io, mem,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    os::unix::io::RawFd,
+    time::{Duration, Instant},
+};
+
+// netlink message types and flags
+const NLMSG_ERROR: u16 = 2;
+const NLMSG_DONE: u16 = 3;
+const NLM_F_REQUEST: u16 = 0x01;
+const NLM_F_ACK: u16 = 0x04;
+const NLM_F_DUMP: u16 = 0x300;
+const NLM_F_REPLACE: u16 = 0x100;
+const NLM_F_CREATE: u16 = 0x400;
+
+// rtnetlink message types and multicast groups
+const RTM_NEWLINK: u16 = 16;
+const RTM_DELLINK: u16 = 17;
+const RTM_NEWADDR: u16 = 20;
+const RTM_GETADDR: u16 = 22;
+const RTM_NEWROUTE: u16 = 24;
+const RTM_DELROUTE: u16 = 25;
+const RTMGRP_LINK: u32 = 0x1;
+
+// address and link attribute types
+const IFA_ADDRESS: u16 = 1;
+const IFA_LOCAL: u16 = 2;
+const IFLA_IFNAME: u16 = 3;
+const IFLA_OPERSTATE: u16 = 16;
+const RTA_DST: u16 = 1;
+
+// routing table, protocol and type of the blocking routes
+const RT_TABLE_MAIN: u8 = 254;
+const RTPROT_STATIC: u8 = 4;
+const RTN_PROHIBIT: u8 = 8;
+
+// lengths of the fixed-size message headers
+const NLMSG_HDRLEN: usize = 16;
+const IFADDRMSG_LEN: usize = 8;
+const IFINFOMSG_LEN: usize = 16;
+const RTMSG_LEN: usize = 12;
+const RTA_HDRLEN: usize = 4;
+
+const RECV_BUF_LEN: usize = 32_768;
+// how long to wait for the kernel to reply to a request
+const REPLY_TIMEOUT: Duration = Duration::from_secs(5);
+
+// destinations of the blocking routes: each half of the IPv4 and IPv6 address
+// spaces is more specific than a default route, but less specific than the
+// route to any local network, so only traffic leaving the device is blocked
+const BLOCKED_ROUTES: [(i32, &[u8]); 4] = [
+    (libc::AF_INET, &[0, 0, 0, 0]),
+    (libc::AF_INET, &[128, 0, 0, 0]),
+    (libc::AF_INET6, &[0; 16]),
+    (
+        libc::AF_INET6,
+        &[0x80, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+    ),
+];
+
+/// IP address assigned to the interface with the given index.
+#[derive(Debug)]
+pub struct Address {
+    pub index: u32,
+    pub ip: IpAddr,
+}
+
+/// Link state change reported by the kernel.
+#[derive(Debug)]
+pub struct LinkEvent {
+    pub index: u32,
+    pub iface: String,
+    // operational state as reported in `/sys/class/net/{iface}/operstate`
+    pub operstate: String,
+    // true if the interface has been removed
+    pub removed: bool,
+}
+
+/// Subscription to link state changes for all interfaces.
+pub struct LinkMonitor {
+    socket: Socket,
+    buf: Vec<u8>,
+    pending: VecDeque<LinkEvent>,
+}
+
+impl LinkMonitor {
+    /// Open a netlink socket subscribed to link notifications.
+    pub fn new() -> io::Result<LinkMonitor> {
+        let socket = Socket::open(RTMGRP_LINK)?;
+
+        Ok(LinkMonitor {
+            socket,
+            buf: vec![0; RECV_BUF_LEN],
+            pending: VecDeque::new(),
+        })
+    }
+
+    /// Wait up to `timeout` for the next link event. `None` is returned if no
+    /// event arrives before the timeout expires.
+    pub fn next_event(&mut self, timeout: Duration) -> io::Result<Option<LinkEvent>> {
+        if let Some(event) = self.pending.pop_front() {
+            return Ok(Some(event));
+        }
+        self.socket.set_timeout(timeout)?;
+        let len = match self.socket.recv(&mut self.buf) {
+            Ok(len) => len,
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        for (msg_type, payload) in messages(&self.buf[..len])? {
+            if msg_type == RTM_NEWLINK || msg_type == RTM_DELLINK {
+                if let Some(event) = parse_link(msg_type, payload) {
+                    self.pending.push_back(event);
+                }
+            }
+        }
+
+        Ok(self.pending.pop_front())
+    }
+
+    /// Wait up to `timeout` for a link event on the given interface.
+    pub fn wait_for(&mut self, iface: &str, timeout: Duration) -> io::Result<Option<LinkEvent>> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let now = Instant::now();
+            if now >= deadline {
+                return Ok(None);
+            }
+            match self.next_event(deadline - now)? {
+                Some(event) if event.iface == iface => return Ok(Some(event)),
+                Some(_) => continue,
+                None => return Ok(None),
+            }
+        }
+    }
+}
+
+/// Retrieve the IPv4 and IPv6 addresses of all interfaces.
+///
+/// For IPv4, the local address is preferred over the peer address reported
+/// for point-to-point links.
+pub fn addresses() -> io::Result<Vec<Address>> {
+    let socket = Socket::open(0)?;
+    socket.set_timeout(REPLY_TIMEOUT)?;
+    // request header followed by an empty ifaddrmsg (AF_UNSPEC: all families)
+    let len = NLMSG_HDRLEN + IFADDRMSG_LEN;
+    let mut request = vec![0u8; len];
+    request[0..4].copy_from_slice(&(len as u32).to_ne_bytes());
+    request[4..6].copy_from_slice(&RTM_GETADDR.to_ne_bytes());
+    request[6..8].copy_from_slice(&(NLM_F_REQUEST | NLM_F_DUMP).to_ne_bytes());
+    request[8..12].copy_from_slice(&1u32.to_ne_bytes());
+    socket.send(&request)?;
+
+    let mut addresses = Vec::new();
+    let mut buf = vec![0; RECV_BUF_LEN];
+    // a dump may span several datagrams and is terminated by NLMSG_DONE
+    loop {
+        let len = socket.recv_reply(&mut buf)?;
+        for (msg_type, payload) in messages(&buf[..len])? {
+            match msg_type {
+                NLMSG_DONE => return Ok(addresses),
+                RTM_NEWADDR => {
+                    if let Some(address) = parse_address(payload) {
+                        addresses.push(address);
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+}
+
+/// Add (`block` is `true`) or remove prohibit routes covering every destination
+/// beyond the local networks. While they are in place, traffic to and from the
+/// internet is rejected but the device remains reachable from the LAN and the
+/// access point. Adding routes which are already present, or removing routes
+/// which are absent, is not an error.
+///
+/// This is equivalent to `ip route add|del prohibit 0.0.0.0/1` (and so on for
+/// the other halves) and requires the `CAP_NET_ADMIN` capability.
+pub fn set_blocked(block: bool) -> io::Result<()> {
+    let socket = Socket::open(0)?;
+    socket.set_timeout(REPLY_TIMEOUT)?;
+    let mut buf = vec![0; RECV_BUF_LEN];
+    for &(family, dst) in BLOCKED_ROUTES.iter() {
+        socket.send(&route_request(block, family, dst))?;
+        let len = socket.recv_reply(&mut buf)?;
+        match messages(&buf[..len]) {
+            Ok(_) => (),
+            // the route was never added or has already been removed
+            Err(ref err) if !block && err.raw_os_error() == Some(libc::ESRCH) => (),
+            // IPv6 is disabled, so there is no IPv6 traffic to block
+            Err(ref err)
+                if family == libc::AF_INET6 && err.raw_os_error() == Some(libc::EAFNOSUPPORT) => {}
+            Err(err) => return Err(err),
+        }
+    }
+
+    Ok(())
+}
+
+/// Build a request which adds or deletes the prohibit route for `dst`/1 in the
+/// main routing table.
+fn route_request(add: bool, family: i32, dst: &[u8]) -> Vec<u8> {
+    let (msg_type, flags) = if add {
+        (RTM_NEWROUTE, NLM_F_CREATE | NLM_F_REPLACE)
+    } else {
+        (RTM_DELROUTE, 0)
+    };
+    // request header, rtmsg and a single RTA_DST attribute
+    let attr_len = RTA_HDRLEN + dst.len();
+    let len = NLMSG_HDRLEN + RTMSG_LEN + align(attr_len);
+    let mut request = vec![0u8; len];
+    request[0..4].copy_from_slice(&(len as u32).to_ne_bytes());
+    request[4..6].copy_from_slice(&msg_type.to_ne_bytes());
+    request[6..8].copy_from_slice(&(NLM_F_REQUEST | NLM_F_ACK | flags).to_ne_bytes());
+    request[8..12].copy_from_slice(&1u32.to_ne_bytes());
+    request[16] = family as u8;
+    // prefix length of the destination
+    request[17] = 1;
+    request[20] = RT_TABLE_MAIN;
+    request[21] = RTPROT_STATIC;
+    request[23] = RTN_PROHIBIT;
+    let attr = NLMSG_HDRLEN + RTMSG_LEN;
+    request[attr..attr + 2].copy_from_slice(&(attr_len as u16).to_ne_bytes());
+    request[attr + 2..attr + 4].copy_from_slice(&RTA_DST.to_ne_bytes());
+    request[attr + RTA_HDRLEN..attr + attr_len].copy_from_slice(dst);
+
+    request
+}
+
+/// Split a datagram into `(type, payload)` pairs, surfacing kernel errors.
+/// A truncated or malformed message is an error, rather than being skipped,
+/// so that a dump is not left waiting for a message which was lost.
+fn messages(buf: &[u8]) -> io::Result<Vec<(u16, &[u8])>> {
+    let mut messages = Vec::new();
+    let mut offset = 0;
+    while offset < buf.len() {
+        let truncated = || {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("truncated netlink message at offset {}", offset),
+            )
+        };
+        if offset + NLMSG_HDRLEN > buf.len() {
+            return Err(truncated());
+        }
+        let len = read_u32(buf, offset) as usize;
+        let msg_type = read_u16(buf, offset + 4);
+        if len < NLMSG_HDRLEN || offset + len > buf.len() {
+            return Err(truncated());
+        }
+        let payload = &buf[offset + NLMSG_HDRLEN..offset + len];
+        if msg_type == NLMSG_ERROR && payload.len() >= 4 {
+            // the payload starts with a negated errno (0 is an acknowledgement)
+            let errno = i32::from_ne_bytes([payload[0], payload[1], payload[2], payload[3]]);
+            if errno != 0 {
+                return Err(io::Error::from_raw_os_error(-errno));
+            }
+        } else {
+            messages.push((msg_type, payload));
+        }
+        offset += align(len);
+    }
+
+    Ok(messages)
+}
+
+/// Parse an `RTM_NEWADDR` payload (ifaddrmsg followed by attributes).
+fn parse_address(payload: &[u8]) -> Option<Address> {
+    if payload.len() < IFADDRMSG_LEN {
+        return None;
+    }
+    let family = i32::from(payload[0]);
+    let index = read_u32(payload, 4);
+    let mut local = None;
+    let mut address = None;
+    for (attr_type, data) in attributes(&payload[IFADDRMSG_LEN..]) {
+        let ip = match (family, data.len()) {
+            (libc::AF_INET, 4) => IpAddr::V4(Ipv4Addr::new(data[0], data[1], data[2], data[3])),
+            (libc::AF_INET6, 16) => {
+                let mut octets = [0u8; 16];
+                octets.copy_from_slice(data);
+                IpAddr::V6(Ipv6Addr::from(octets))
+            }
+            _ => continue,
+        };
+        match attr_type {
+            IFA_LOCAL => local = Some(ip),
+            IFA_ADDRESS => address = Some(ip),
+            _ => (),
+        }
+    }
+
+    local.or(address).map(|ip| Address { index, ip })
+}
+
+/// Parse an `RTM_NEWLINK` or `RTM_DELLINK` payload (ifinfomsg followed by
+/// attributes).
+fn parse_link(msg_type: u16, payload: &[u8]) -> Option<LinkEvent> {
+    if payload.len() < IFINFOMSG_LEN {
+        return None;
+    }
+    let index = read_u32(payload, 4);
+    let mut iface = None;
+    let mut operstate = "unknown";
+    for (attr_type, data) in attributes(&payload[IFINFOMSG_LEN..]) {
+        match attr_type {
+            IFLA_IFNAME => {
+                // the interface name is nul-terminated
+                let name = data.split(|&b| b == 0).next().unwrap_or_default();
+                iface = Some(String::from_utf8_lossy(name).to_string());
+            }
+            IFLA_OPERSTATE if !data.is_empty() => {
+                operstate = match data[0] {
+                    1 => "notpresent",
+                    2 => "down",
+                    3 => "lowerlayerdown",
+                    4 => "testing",
+                    5 => "dormant",
+                    6 => "up",
+                    _ => "unknown",
+                }
+            }
+            _ => (),
+        }
+    }
+
+    iface.map(|iface| LinkEvent {
+        index,
+        iface,
+        operstate: operstate.to_string(),
+        removed: msg_type == RTM_DELLINK,
+    })
+}
+
+/// Split a sequence of route attributes into `(type, data)` pairs.
+fn attributes(buf: &[u8]) -> Vec<(u16, &[u8])> {
+    let mut attributes = Vec::new();
+    let mut offset = 0;
+    while offset + RTA_HDRLEN <= buf.len() {
+        let len = read_u16(buf, offset) as usize;
+        let attr_type = read_u16(buf, offset + 2);
+        if len < RTA_HDRLEN || offset + len > buf.len() {
+            break;
+        }
+        attributes.push((attr_type, &buf[offset + RTA_HDRLEN..offset + len]));
+        offset += align(len);
+    }
+
+    attributes
+}
+
+/// Round a message or attribute length up to the 4-byte netlink alignment.
+fn align(len: usize) -> usize {
+    (len + 3) & !3
+}
+
+fn read_u16(buf: &[u8], offset: usize) -> u16 {
+    u16::from_ne_bytes([buf[offset], buf[offset + 1]])
+}
+
+fn read_u32(buf: &[u8], offset: usize) -> u32 {
+    u32::from_ne_bytes([
+        buf[offset],
+        buf[offset + 1],
+        buf[offset + 2],
+        buf[offset + 3],
+    ])
+}
+
+/// Raw `NETLINK_ROUTE` socket, closed on drop.
+struct Socket {
+    fd: RawFd,
+}
+
+impl Socket {
+    /// Open a socket and bind it to the given multicast groups (0 for none).
+    fn open(groups: u32) -> io::Result<Socket> {
+        let fd = unsafe {
+            libc::socket(
+                libc::AF_NETLINK,
+                libc::SOCK_RAW | libc::SOCK_CLOEXEC,
+                libc::NETLINK_ROUTE,
+            )
+        };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let socket = Socket { fd };
+
+        let mut addr: libc::sockaddr_nl = unsafe { mem::zeroed() };
+        addr.nl_family = libc::AF_NETLINK as libc::sa_family_t;
+        addr.nl_groups = groups;
+        let res = unsafe {
+            libc::bind(
+                socket.fd,
+                &addr as *const libc::sockaddr_nl as *const libc::sockaddr,
+                mem::size_of::<libc::sockaddr_nl>() as libc::socklen_t,
+            )
+        };
+        if res < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(socket)
+    }
+
+    /// Send a request to the kernel.
+    fn send(&self, buf: &[u8]) -> io::Result<()> {
+        let mut addr: libc::sockaddr_nl = unsafe { mem::zeroed() };
+        addr.nl_family = libc::AF_NETLINK as libc::sa_family_t;
+        let res = unsafe {
+            libc::sendto(
+                self.fd,
+                buf.as_ptr() as *const libc::c_void,
+                buf.len(),
+                0,
+                &addr as *const libc::sockaddr_nl as *const libc::sockaddr,
+                mem::size_of::<libc::sockaddr_nl>() as libc::socklen_t,
+            )
+        };
+        if res < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(())
+    }
+
+    /// Receive a single datagram, returning the number of bytes read.
+    fn recv(&self, buf: &mut [u8]) -> io::Result<usize> {
+        let res =
+            unsafe { libc::recv(self.fd, buf.as_mut_ptr() as *mut libc::c_void, buf.len(), 0) };
+        if res < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(res as usize)
+    }
+
+    /// Receive a reply to a request, failing if none arrives before the
+    /// receive timeout expires.
+    fn recv_reply(&self, buf: &mut [u8]) -> io::Result<usize> {
+        self.recv(buf).map_err(|e| match e.kind() {
+            io::ErrorKind::WouldBlock => io::Error::new(
+                io::ErrorKind::TimedOut,
+                "timed out waiting for a netlink reply",
+            ),
+            _ => e,
+        })
+    }
+
+    /// Set the receive timeout. A zero timeout would block indefinitely, so
+    /// it is raised to one millisecond.
+    fn set_timeout(&self, timeout: Duration) -> io::Result<()> {
+        let timeout = timeout.max(Duration::from_millis(1));
+        let tv = libc::timeval {
+            tv_sec: timeout.as_secs() as libc::time_t,
+            tv_usec: timeout.subsec_micros() as libc::suseconds_t,
+        };
+        let res = unsafe {
+            libc::setsockopt(
+                self.fd,
+                libc::SOL_SOCKET,
+                libc::SO_RCVTIMEO,
+                &tv as *const libc::timeval as *const libc::c_void,
+                mem::size_of::<libc::timeval>() as libc::socklen_t,
+            )
+        };
+        if res < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for Socket {
+    fn drop(&mut self) {
+        unsafe {
+            libc::close(self.fd);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a netlink message with the given type and payload
+    fn message(msg_type: u16, payload: &[u8]) -> Vec<u8> {
+        let len = NLMSG_HDRLEN + payload.len();
+        let mut buf = vec![0u8; NLMSG_HDRLEN];
+        buf[0..4].copy_from_slice(&(len as u32).to_ne_bytes());
+        buf[4..6].copy_from_slice(&msg_type.to_ne_bytes());
+        buf.extend_from_slice(payload);
+        buf.resize(align(len), 0);
+        buf
+    }
+
+    // a route attribute with the given type and data
+    fn attribute(attr_type: u16, data: &[u8]) -> Vec<u8> {
+        let len = RTA_HDRLEN + data.len();
+        let mut buf = Vec::new();
+        buf.extend_from_slice(&(len as u16).to_ne_bytes());
+        buf.extend_from_slice(&attr_type.to_ne_bytes());
+        buf.extend_from_slice(data);
+        buf.resize(align(len), 0);
+        buf
+    }
+
+    // an ifaddrmsg for the given family and interface index
+    fn ifaddrmsg(family: i32, index: u32) -> Vec<u8> {
+        let mut buf = vec![0u8; IFADDRMSG_LEN];
+        buf[0] = family as u8;
+        buf[4..8].copy_from_slice(&index.to_ne_bytes());
+        buf
+    }
+
+    // an ifinfomsg for the given interface index
+    fn ifinfomsg(index: u32) -> Vec<u8> {
+        let mut buf = vec![0u8; IFINFOMSG_LEN];
+        buf[4..8].copy_from_slice(&index.to_ne_bytes());
+        buf
+    }
+
+    #[test]
+    fn messages_are_split() {
+        let mut buf = message(RTM_NEWADDR, &[1, 2, 3]);
+        // an acknowledgement is not returned
+        buf.extend(message(NLMSG_ERROR, &0i32.to_ne_bytes()));
+        buf.extend(message(NLMSG_DONE, &[]));
+
+        let messages = messages(&buf).unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0], (RTM_NEWADDR, &[1, 2, 3, 0][..3]));
+        assert_eq!(messages[1], (NLMSG_DONE, &[][..]));
+    }
+
+    #[test]
+    fn kernel_errors_are_returned() {
+        let buf = message(NLMSG_ERROR, &(-libc::EACCES).to_ne_bytes());
+        let err = messages(&buf).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+    }
+
+    #[test]
+    fn truncated_messages_are_errors() {
+        let buf = message(RTM_NEWADDR, &[0; 8]);
+        // a message longer than the datagram
+        let err = messages(&buf[..buf.len() - 4]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        // a partial header
+        let err = messages(&buf[..NLMSG_HDRLEN - 1]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        // a length shorter than the header
+        let mut buf = buf;
+        buf[0..4].copy_from_slice(&4u32.to_ne_bytes());
+        assert!(messages(&buf).is_err());
+    }
+
+    #[test]
+    fn malformed_attributes_are_ignored() {
+        let mut buf = attribute(IFA_ADDRESS, &[10, 0, 0, 1]);
+        buf.extend(attribute(IFA_LOCAL, &[10, 0, 0, 2]));
+        assert_eq!(
+            attributes(&buf),
+            vec![
+                (IFA_ADDRESS, &[10, 0, 0, 1][..]),
+                (IFA_LOCAL, &[10, 0, 0, 2][..])
+            ]
+        );
+        // an attribute longer than the buffer ends the list
+        buf[8..10].copy_from_slice(&64u16.to_ne_bytes());
+        assert_eq!(attributes(&buf).len(), 1);
+    }
+
+    #[test]
+    fn ipv4_local_address_is_preferred() {
+        let mut payload = ifaddrmsg(libc::AF_INET, 3);
+        payload.extend(attribute(IFA_ADDRESS, &[10, 0, 0, 1]));
+        payload.extend(attribute(IFA_LOCAL, &[192, 168, 1, 2]));
+
+        let address = parse_address(&payload).unwrap();
+        assert_eq!(address.index, 3);
+        assert_eq!(address.ip, IpAddr::V4(Ipv4Addr::new(192, 168, 1, 2)));
+    }
+
+    #[test]
+    fn ipv6_address_is_parsed() {
+        let ip = Ipv6Addr::new(0xfe80, 0, 0, 0, 0, 0, 0, 1);
+        let mut payload = ifaddrmsg(libc::AF_INET6, 2);
+        payload.extend(attribute(IFA_ADDRESS, &ip.octets()));
+        // an address of the wrong length for the family is skipped
+        payload.extend(attribute(IFA_LOCAL, &[1, 2, 3, 4]));
+
+        let address = parse_address(&payload).unwrap();
+        assert_eq!(address.index, 2);
+        assert_eq!(address.ip, IpAddr::V6(ip));
+
+        assert!(parse_address(&ifaddrmsg(libc::AF_INET, 2)).is_none());
+        assert!(parse_address(&[0; 4]).is_none());
+    }
+
+    #[test]
+    fn link_events_are_parsed() {
+        let mut payload = ifinfomsg(4);
+        payload.extend(attribute(IFLA_IFNAME, b"wlan0\0"));
+        payload.extend(attribute(IFLA_OPERSTATE, &[6]));
+
+        let event = parse_link(RTM_NEWLINK, &payload).unwrap();
+        assert_eq!(event.index, 4);
+        assert_eq!(event.iface, "wlan0");
+        assert_eq!(event.operstate, "up");
+        assert!(!event.removed);
+
+        let mut payload = ifinfomsg(4);
+        payload.extend(attribute(IFLA_IFNAME, b"wlan0\0"));
+        let event = parse_link(RTM_DELLINK, &payload).unwrap();
+        assert_eq!(event.operstate, "unknown");
+        assert!(event.removed);
+
+        // a link without a name is skipped
+        assert!(parse_link(RTM_NEWLINK, &ifinfomsg(4)).is_none());
+        assert!(parse_link(RTM_NEWLINK, &[0; 8]).is_none());
+    }
+
+    #[test]
+    fn route_requests_are_built() {
+        let request = route_request(true, libc::AF_INET, &[128, 0, 0, 0]);
+        let messages = messages(&request).unwrap();
+        assert_eq!(messages.len(), 1);
+        let (msg_type, payload) = messages[0];
+        assert_eq!(msg_type, RTM_NEWROUTE);
+        let flags = read_u16(&request, 6);
+        assert_eq!(
+            flags,
+            NLM_F_REQUEST | NLM_F_ACK | NLM_F_CREATE | NLM_F_REPLACE
+        );
+        assert_eq!(payload[0], libc::AF_INET as u8);
+        assert_eq!(payload[1], 1);
+        assert_eq!(payload[4], RT_TABLE_MAIN);
+        assert_eq!(payload[7], RTN_PROHIBIT);
+        assert_eq!(
+            attributes(&payload[RTMSG_LEN..]),
+            vec![(RTA_DST, &[128, 0, 0, 0][..])]
+        );
+
+        let request = route_request(false, libc::AF_INET6, &[0; 16]);
+        assert_eq!(read_u16(&request, 4), RTM_DELROUTE);
+        assert_eq!(read_u16(&request, 6), NLM_F_REQUEST | NLM_F_ACK);
+        assert_eq!(
+            attributes(&request[NLMSG_HDRLEN + RTMSG_LEN..]),
+            vec![(RTA_DST, &[0; 16][..])]
+        );
+    }
+}
@@ -9,26 +9,32 @@
 //! is used to interact with the `wpasupplicant` process.
 //!
 //! Switching between client mode and access point mode is achieved by making
-//! system calls to systemd (via `systemctl`). Further networking functionality
-//! is provided by making system calls to retrieve interface state and write
-//! access point credentials to `wpa_supplicant-wlan0.conf`.
+//! system calls to systemd (via `systemctl`). Interface state is read directly
+//! from sysfs and addresses are retrieved via rtnetlink (see the `netlink`
+//! module), while access point credentials are written to
+//! `wpa_supplicant-wlan0.conf`.
 //!
 use std::{
+    cmp::Reverse,
+    fs,
     fs::OpenOptions,
-    io::prelude::*,
+    io::{self, prelude::*},
+    path::Path,
     process::{Command, Stdio},
     result::Result,
     str,
 };
 
 use crate::error::{
-    GenWpaPassphrase, NetworkError, NoIp, NoState, NoTraffic, ParseString, SerdeSerialize,
-    StartAp0, StartWlan0, WlanState, WpaCtrlOpen, WpaCtrlRequest,
+    GenWpaPassphrase, ListInterfaces, NetworkError, NoRoutes, NoTraffic, ParseString,
+    SerdeSerialize, SetCutoff, StartAp0, StartWlan0, WpaCtrlOpen, WpaCtrlRequest,
 };
 use probes::network;
 use serde::{Deserialize, Serialize};
 use snafu::ResultExt;
 
+use crate::netlink;
+use crate::networkd;
 use crate::utils;
 
 /// Network interface name.
@@ -52,6 +58,39 @@ pub struct IfaceIdPass {
     pub pass: String,
 }
 
+/// Network interface name, network identifier and priority.
+#[derive(Debug, Deserialize)]
+pub struct IfaceIdPriority {
+    pub iface: String,
+    pub id: String,
+    pub priority: i32,
+}
+
+/// Network interface name and an ordered list of network identifiers.
+#[derive(Debug, Deserialize)]
+pub struct IfaceIds {
+    pub iface: String,
+    pub ids: Vec<String>,
+}
+
+/// Kind of network interface.
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IfaceKind {
+    Ap,
+    Ethernet,
+    Loopback,
+    Wifi,
+    Other,
+}
+
+/// Network interface name and station MAC address.
+#[derive(Debug, Deserialize)]
+pub struct IfaceMac {
+    pub iface: String,
+    pub mac: String,
+}
+
 /// Network interface name and network SSID.
 #[derive(Debug, Deserialize)]
 pub struct IfaceSsid {
@@ -59,9 +98,26 @@ pub struct IfaceSsid {
     pub ssid: String,
 }
 
-/// Network SSID.
+/// Kind, state and addressing data for a network interface.
+#[derive(Debug, Serialize)]
+pub struct Interface {
+    pub name: String,
+    pub kind: IfaceKind,
+    pub operstate: String,
+    pub mac: Option<String>,
+    pub ipv4: Vec<String>,
+    pub ipv6: Vec<String>,
+    // link speed in Mbit/s (not reported by wireless drivers)
+    pub speed: Option<u32>,
+    // true if the interface holds the preferred default route
+    pub uplink: bool,
+}
+
+/// Network identifier, selection priority and SSID of a saved network.
 #[derive(Debug, Serialize)]
 pub struct Network {
+    pub id: String,
+    pub priority: i32,
     pub ssid: String,
 }
 
@@ -74,6 +130,26 @@ pub struct Scan {
     pub ssid: String,
 }
 
+/// Station associated with the access point.
+#[derive(Debug, Serialize)]
+pub struct Station {
+    pub mac: String,
+    pub ip: Option<String>,
+    pub hostname: Option<String>,
+    // average signal strength (dBm)
+    pub signal: Option<i32>,
+    // seconds since association
+    pub connected_time: Option<u64>,
+    pub rx_bytes: Option<u64>,
+    pub tx_bytes: Option<u64>,
+}
+
+/// Whether traffic beyond the local networks should be blocked.
+#[derive(Debug, Deserialize)]
+pub struct Cutoff {
+    pub cut: bool,
+}
+
 /// Status data for a network interface.
 #[derive(Debug, Serialize)]
 pub struct Status {
@@ -229,25 +305,96 @@ pub fn id(iface: &str, ssid: &str) -> Result<Option<String>, NetworkError> {
 ///
 /// * `iface` - A string slice holding the name of a wireless network interface
 ///
-/// If the given interface is found in the list of available interfaces,
-/// an `Ok` `Result` type is returned containing `Some(String)` - where `String`
-/// is the IP address of the interface. If no match is found, a `None` type is
-/// returned in the `Result`. In the event of an error, a `NetworkError` is
-/// returned in the `Result`. The `NetworkError` is then enumerated to a
-/// specific error type and an appropriate JSON RPC response is sent to the
-/// caller.
+/// If the given interface has an address assigned, an `Ok` `Result` type is
+/// returned containing `Some(String)` - where `String` is the IP address of
+/// the interface (IPv4 addresses are preferred over IPv6). If no address is
+/// found, a `None` type is returned in the `Result`. In the event of an error,
+/// a `NetworkError` is returned in the `Result`. The `NetworkError` is then
+/// enumerated to a specific error type and an appropriate JSON RPC response is
+/// sent to the caller.
 ///
 pub fn ip(iface: &str) -> Result<Option<String>, NetworkError> {
-    let net_if: String = iface.to_string();
-    let ifaces = get_if_addrs::get_if_addrs().context(NoIp { iface: net_if })?;
-    let ip = ifaces
+    let index = utils::iface_index(iface)?;
+    let addresses = netlink::addresses().map_err(|source| match source.kind() {
+        io::ErrorKind::PermissionDenied => NetworkError::PermissionDenied {
+            path: "rtnetlink".to_string(),
+        },
+        _ => NetworkError::NoIp {
+            iface: iface.to_string(),
+            source,
+        },
+    })?;
+    let ip = addresses
         .iter()
-        .find(|&i| i.name == iface)
-        .map(|iface| iface.ip().to_string());
+        .filter(|address| address.index == index)
+        .min_by_key(|address| address.ip.is_ipv6())
+        .map(|address| address.ip.to_string());
 
     Ok(ip)
 }
 
+/// Retrieve kind, state and addressing data for all network interfaces.
+///
+/// The interface list, kind, operational state, MAC address and link speed
+/// are read from `/sys/class/net`, while IPv4 and IPv6 addresses are retrieved
+/// from the kernel via rtnetlink. The interface providing the
+/// default route is flagged as the uplink.
+///
+/// If the interfaces are successfully read, an `Ok` `Result` type is returned
+/// containing a `String` - a serialized vector of `Interface` structs sorted
+/// by name. In the event of an error, a
+/// `NetworkError` is returned in the `Result`. The `NetworkError` is then
+/// enumerated to a specific error type and an appropriate JSON RPC response is
+/// sent to the caller.
+///
+pub fn list_interfaces() -> Result<String, NetworkError> {
+    let addresses = netlink::addresses().context(ListInterfaces)?;
+    // an absent default route simply means no interface is flagged as uplink
+    let uplink_iface = uplink()?;
+
+    let mut interfaces = Vec::new();
+    for entry in fs::read_dir("/sys/class/net").context(ListInterfaces)? {
+        let name = entry
+            .context(ListInterfaces)?
+            .file_name()
+            .to_string_lossy()
+            .to_string();
+        let index = utils::iface_index(&name).ok();
+        let mut ipv4 = Vec::new();
+        let mut ipv6 = Vec::new();
+        for address in addresses.iter().filter(|a| Some(a.index) == index) {
+            if address.ip.is_ipv4() {
+                ipv4.push(address.ip.to_string());
+            } else {
+                ipv6.push(address.ip.to_string());
+            }
+        }
+        let interface = Interface {
+            kind: iface_kind(&name),
+            operstate: utils::sys_attr(&name, "operstate")
+                .unwrap_or_else(|_| "unknown".to_string()),
+            // loopback and virtual interfaces report an all-zero address
+            mac: utils::sys_attr(&name, "address")
+                .ok()
+                .filter(|mac| mac != "00:00:00:00:00:00"),
+            ipv4,
+            ipv6,
+            // the kernel reports -1 (or errors) when the link is down
+            speed: utils::sys_attr(&name, "speed")
+                .ok()
+                .and_then(|speed| speed.parse().ok()),
+            uplink: uplink_iface.as_deref() == Some(name.as_str()),
+            name,
+        };
+        interfaces.push(interface);
+    }
+    interfaces.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let list = serde_json::to_string(&interfaces).context(SerdeSerialize)?;
+
+    Ok(list)
+}
+
 /// Retrieve average signal strength (dBm) for the network associated with
 /// a given interface.
 ///
@@ -328,11 +475,13 @@ pub fn rssi_percent(iface: &str) -> Result<Option<String>, NetworkError> {
 /// If the wpasupplicant configuration file contains credentials for one or
 /// more access points, an `Ok` `Result` type is returned containing
 /// `Some(String)` - where `String` is a serialized vector of `Network` structs
-/// containing the SSIDs of all saved networks. If no network credentials are
-/// found, a `None` type is returned in the `Result`. In the event of an error,
-/// a `NetworkError` is returned in the `Result`. The `NetworkError` is then
-/// enumerated to a specific error type and an appropriate JSON RPC response is
-/// sent to the caller.
+/// containing the identifier, priority and SSID of all saved networks. The
+/// vector is ordered from highest to lowest priority, with networks of equal
+/// priority remaining in the order in which they appear in the configuration
+/// file. If no network credentials are found, a `None` type is returned in the
+/// `Result`. In the event of an error, a `NetworkError` is returned in the
+/// `Result`. The `NetworkError` is then enumerated to a specific error type
+/// and an appropriate JSON RPC response is sent to the caller.
 ///
 pub fn saved_networks() -> Result<Option<String>, NetworkError> {
     let mut wpa = wpactrl::WpaCtrl::new().open().context(WpaCtrlOpen)?;
@@ -342,8 +491,17 @@ pub fn saved_networks() -> Result<Option<String>, NetworkError> {
         let v: Vec<&str> = network.split('\t').collect();
         let len = v.len();
         if len > 1 {
+            let id = v[0].trim().to_string();
             let ssid = v[1].trim().to_string();
-            let response = Network { ssid };
+            // networks without an explicit priority default to 0
+            let get_priority = format!("GET_NETWORK {} priority", id);
+            let priority = wpa
+                .request(&get_priority)
+                .context(WpaCtrlRequest)?
+                .trim()
+                .parse::<i32>()
+                .unwrap_or(0);
+            let response = Network { id, priority, ssid };
             ssids.push(response)
         }
     }
@@ -351,6 +509,8 @@ pub fn saved_networks() -> Result<Option<String>, NetworkError> {
     if ssids.is_empty() {
         Ok(None)
     } else {
+        // stable sort: equal priorities retain their configuration file order
+        ssids.sort_by_key(|ssid| Reverse(ssid.priority));
         let results = serde_json::to_string(&ssids).context(SerdeSerialize)?;
         Ok(Some(results))
     }
@@ -398,25 +558,73 @@ pub fn ssid(iface: &str) -> Result<Option<String>, NetworkError> {
 /// an appropriate JSON RPC response is sent to the caller.
 ///
 pub fn state(iface: &str) -> Result<Option<String>, NetworkError> {
-    // construct the interface operstate path
-    let iface_path: String = format!("/sys/class/net/{}/operstate", iface);
-    // execute the cat command and save output, catching any errors
-    let output = Command::new("cat")
-        .arg(iface_path)
-        .output()
-        .context(NoState { iface })?;
-    if !output.stdout.is_empty() {
-        // unwrap the command result and convert to String
-        let mut state = String::from_utf8(output.stdout).unwrap();
-        // remove trailing newline character
-        let len = state.len();
-        state.truncate(len - 1);
+    // read the operstate directly from sysfs
+    let state = utils::sys_attr(iface, "operstate")?;
+    if !state.is_empty() {
         return Ok(Some(state));
     }
 
     Ok(None)
 }
 
+/// Retrieve the stations associated with the access point on a given
+/// interface.
+///
+/// # Arguments
+///
+/// * `iface` - A string slice holding the name of a wireless network interface
+///
+/// The `wpasupplicant` control interface is queried for each associated
+/// station (`STA-FIRST` followed by `STA-NEXT`) and the results are matched
+/// with the leases handed out by the systemd-networkd DHCP server. The kernel
+/// neighbour table is consulted for stations without a lease. If the query is
+/// successful, an `Ok` `Result` type is returned containing a `String` - a
+/// serialized vector of `Station` structs. In the event of an error, a
+/// `NetworkError` is returned in the `Result`. The `NetworkError` is then
+/// enumerated to a specific error type and an appropriate JSON RPC response is
+/// sent to the caller.
+///
+pub fn stations(iface: &str) -> Result<String, NetworkError> {
+    let wpa_path: String = format!("/var/run/wpa_supplicant/{}", iface);
+    let mut wpa = wpactrl::WpaCtrl::new()
+        .ctrl_path(wpa_path)
+        .open()
+        .context(WpaCtrlOpen)?;
+    let leases = networkd::dhcp_leases(iface);
+
+    let mut stations: Vec<Station> = Vec::new();
+    let mut reply = wpa.request("STA-FIRST").context(WpaCtrlRequest)?;
+    // each reply begins with the station address; `FAIL` or an empty reply
+    // marks the end of the list
+    while let Some(mac) = reply
+        .lines()
+        .next()
+        .map(|line| line.trim().to_lowercase())
+        .filter(|line| utils::is_mac(line))
+    {
+        let lease = leases.iter().find(|lease| lease.mac == mac);
+        let ip = match lease {
+            Some(lease) => Some(lease.ip.to_string()),
+            None => neighbour_ip(iface, &mac),
+        };
+        let next = format!("STA-NEXT {}", mac);
+        stations.push(Station {
+            mac,
+            ip,
+            hostname: lease.and_then(|lease| lease.hostname.clone()),
+            signal: sta_field(&reply, "signal"),
+            connected_time: sta_field(&reply, "connected_time"),
+            rx_bytes: sta_field(&reply, "rx_bytes"),
+            tx_bytes: sta_field(&reply, "tx_bytes"),
+        });
+        reply = wpa.request(&next).context(WpaCtrlRequest)?;
+    }
+
+    let list = serde_json::to_string(&stations).context(SerdeSerialize)?;
+
+    Ok(list)
+}
+
 /// Retrieve status for a given interface.
 ///
 /// # Arguments
@@ -514,6 +722,100 @@ pub fn traffic(iface: &str) -> Result<Option<String>, NetworkError> {
     Ok(None)
 }
 
+/// Retrieve the name of the interface currently providing uplink.
+///
+/// The kernel routing table (`/proc/net/route`) is parsed for default routes
+/// and the interface holding the route with the lowest metric is selected.
+///
+/// If a default route is found, an `Ok` `Result` type is returned containing
+/// `Some(String)` - where `String` is the name of the interface. If no default
+/// route exists, a `None` type is returned in the `Result`. In the event of an
+/// error, a `NetworkError` is returned in the `Result`. The `NetworkError` is
+/// then enumerated to a specific error type and an appropriate JSON RPC
+/// response is sent to the caller.
+///
+pub fn uplink() -> Result<Option<String>, NetworkError> {
+    let routes = fs::read_to_string("/proc/net/route").context(NoRoutes)?;
+    // columns: Iface Destination Gateway Flags RefCnt Use Metric Mask ...
+    let iface = routes
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() < 8 {
+                return None;
+            }
+            // RTF_UP flag must be set for the route to be usable
+            let flags = u16::from_str_radix(fields[3], 16).ok()?;
+            if fields[1] == "00000000" && fields[7] == "00000000" && flags & 0x1 == 0x1 {
+                let metric: u32 = fields[6].parse().ok()?;
+                Some((metric, fields[0].to_string()))
+            } else {
+                None
+            }
+        })
+        .min_by_key(|(metric, _)| *metric)
+        .map(|(_, iface)| iface);
+
+    Ok(iface)
+}
+
+/// Determine the kind of a network interface from its sysfs attributes.
+///
+/// Wireless interfaces are reported as `Ap` if the `wpasupplicant` instance
+/// controlling them is in access point mode, falling back to the `ap` name
+/// prefix used by PeachCloud when no control interface is available.
+fn iface_kind(iface: &str) -> IfaceKind {
+    let sys_path = format!("/sys/class/net/{}", iface);
+    let is_wireless = Path::new(&sys_path).join("wireless").exists()
+        || Path::new(&sys_path).join("phy80211").exists();
+    if is_wireless {
+        let wpa_path = format!("/var/run/wpa_supplicant/{}", iface);
+        let mode = wpactrl::WpaCtrl::new()
+            .ctrl_path(wpa_path)
+            .open()
+            .ok()
+            .and_then(|mut wpa| wpa.request("STATUS").ok())
+            .and_then(|status| utils::regex_finder(r"\nmode=(.*)\n", &status).ok())
+            .flatten();
+        return match mode {
+            Some(mode) if mode == "AP" => IfaceKind::Ap,
+            Some(_) => IfaceKind::Wifi,
+            None if iface.starts_with("ap") => IfaceKind::Ap,
+            None => IfaceKind::Wifi,
+        };
+    }
+    // interface types are defined as ARPHRD_* in linux/if_arp.h
+    match utils::sys_attr(iface, "type").as_deref() {
+        Ok("772") => IfaceKind::Loopback,
+        Ok("1") => IfaceKind::Ethernet,
+        _ => IfaceKind::Other,
+    }
+}
+
+/// Look up the IPv4 address of a station in the kernel neighbour table
+/// (`/proc/net/arp`).
+fn neighbour_ip(iface: &str, mac: &str) -> Option<String> {
+    let arp = fs::read_to_string("/proc/net/arp").ok()?;
+    // columns: IP address, HW type, Flags, HW address, Mask, Device
+    arp.lines().skip(1).find_map(|line| {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() >= 6 && fields[5] == iface && fields[3].eq_ignore_ascii_case(mac) {
+            Some(fields[0].to_string())
+        } else {
+            None
+        }
+    })
+}
+
+/// Parse a `key=value` field from a `STA` reply.
+fn sta_field<T: str::FromStr>(reply: &str, key: &str) -> Option<T> {
+    reply
+        .lines()
+        .find_map(|line| line.strip_prefix(key)?.strip_prefix('='))
+        .and_then(|value| value.trim().parse().ok())
+}
+
 /* SET - Methods for modifying state */
 
 /// Activate wireless access point.
@@ -611,17 +913,20 @@ pub fn add(wifi: &WiFi) -> Result<(), NetworkError> {
 /// connection.
 ///
 /// The status of the `wlan0` service and the state of the `wlan0` interface
-/// are checked. If the service is active but the interface is down (ie. not
-/// currently connected to an access point), then the access point is activated
-/// by calling the `activate_ap()` function.
+/// are checked. The service is considered active if its `wpasupplicant`
+/// control interface answers a `PING` request. If the service is active but
+/// the interface is down (ie. not currently connected to an access point),
+/// then the access point is activated by calling the `activate_ap()` function.
 ///
 pub fn check_iface() -> Result<(), NetworkError> {
-    // returns 0 if the service is currently active
-    let wlan0_status = Command::new("/usr/bin/systemctl")
-        .arg("is-active")
-        .arg("wpa_supplicant@wlan0.service")
-        .status()
-        .context(WlanState)?;
+    // the control socket only exists while the wlan0 service is running
+    let wlan0_active = wpactrl::WpaCtrl::new()
+        .ctrl_path("/var/run/wpa_supplicant/wlan0")
+        .open()
+        .ok()
+        .and_then(|mut wpa| wpa.request("PING").ok())
+        .map(|reply| reply.trim() == "PONG")
+        .unwrap_or(false);
 
     // returns the current state of the wlan0 interface
     let iface_state = state("wlan0")?;
@@ -633,7 +938,7 @@ pub fn check_iface() -> Result<(), NetworkError> {
     };
 
     // if wlan0 is active but not connected, start the ap0 service
-    if wlan0_status.success() && wlan0_state == "down" {
+    if wlan0_active && wlan0_state == "down" {
         activate_ap()?
     }
 
@@ -666,6 +971,41 @@ pub fn connect(id: &str, iface: &str) -> Result<(), NetworkError> {
     Ok(())
 }
 
+/// Deauthenticate a station from the access point on a given interface.
+///
+/// # Arguments
+///
+/// * `iface` - A string slice holding the name of a wireless network interface
+/// * `mac` - A string slice holding the MAC address of an associated station
+///
+/// If the MAC address is valid and `wpasupplicant` confirms that the station
+/// has been deauthenticated, an `Ok` `Result` type is returned. The station is
+/// free to reconnect. In the event of an error, a `NetworkError` is returned
+/// in the `Result`. The `NetworkError` is then enumerated to a specific error
+/// type and an appropriate JSON RPC response is sent to the caller.
+///
+pub fn deauthenticate(iface: &str, mac: &str) -> Result<(), NetworkError> {
+    if !utils::is_mac(mac) {
+        return Err(NetworkError::InvalidMac {
+            mac: mac.to_string(),
+        });
+    }
+    let wpa_path: String = format!("/var/run/wpa_supplicant/{}", iface);
+    let mut wpa = wpactrl::WpaCtrl::new()
+        .ctrl_path(wpa_path)
+        .open()
+        .context(WpaCtrlOpen)?;
+    let deauth = format!("DEAUTHENTICATE {}", mac);
+    let reply = wpa.request(&deauth).context(WpaCtrlRequest)?;
+    if reply.trim() != "OK" {
+        return Err(NetworkError::Deauthenticate {
+            iface: iface.to_string(),
+            mac: mac.to_string(),
+        });
+    }
+    Ok(())
+}
+
 /// Delete network credentials for a given network identifier and interface.
 ///
 /// # Arguments
@@ -823,6 +1163,39 @@ pub fn reconnect(iface: &str) -> Result<(), NetworkError> {
     Ok(())
 }
 
+/// Assign descending priorities to the networks in the given order, so that
+/// `wpa_supplicant` prefers the first network in the list, then the second
+/// and so on.
+///
+/// # Arguments
+///
+/// * `iface` - A string slice holding the name of a wireless network interface
+/// * `ids` - A slice of network identifiers, ordered from most to least
+///   preferred
+///
+/// If a priority is successfully set for every network in the list, an `Ok`
+/// `Result` type is returned. The updated priorities are held in memory by
+/// `wpa_supplicant` until `save()` is called. In the event of an error, a
+/// `NetworkError` is returned in the `Result`. The `NetworkError` is then
+/// enumerated to a specific error type and an appropriate JSON RPC response is
+/// sent to the caller.
+///
+pub fn reorder(iface: &str, ids: &[String]) -> Result<(), NetworkError> {
+    let wpa_path: String = format!("/var/run/wpa_supplicant/{}", iface);
+    let mut wpa = wpactrl::WpaCtrl::new()
+        .ctrl_path(wpa_path)
+        .open()
+        .context(WpaCtrlOpen)?;
+    // the first network receives the highest priority; the last receives 1
+    let len = ids.len();
+    for (position, id) in ids.iter().enumerate() {
+        let priority = len - position;
+        let set_priority = format!("SET_NETWORK {} priority {}", id, priority);
+        wpa.request(&set_priority).context(WpaCtrlRequest)?;
+    }
+    Ok(())
+}
+
 /// Save configuration updates to the `wpa_supplicant` configuration file.
 ///
 /// If wireless network configuration updates are successfully save to the
@@ -836,3 +1209,47 @@ pub fn save() -> Result<(), NetworkError> {
     wpa.request("SAVE_CONFIG").context(WpaCtrlRequest)?;
     Ok(())
 }
+
+/// Block or restore traffic between the device and the internet.
+///
+/// # Arguments
+///
+/// * `cut` - A boolean; `true` blocks traffic to every destination beyond the
+///   local networks, leaving the device reachable from the LAN and the access
+///   point, and `false` restores it
+///
+/// If the blocking routes are successfully added or removed, an `Ok` `Result`
+/// type is returned. In the event of an error, a `NetworkError` is returned in
+/// the `Result`. The `NetworkError` is then enumerated to a specific error type
+/// and an appropriate JSON RPC response is sent to the caller.
+///
+pub fn set_cutoff(cut: bool) -> Result<(), NetworkError> {
+    netlink::set_blocked(cut).context(SetCutoff)?;
+    Ok(())
+}
+
+/// Set the selection priority for a given network identifier and interface.
+///
+/// # Arguments
+///
+/// * `id` - A string slice holding the network identifier of an access point
+/// * `iface` - A string slice holding the name of a wireless network interface
+/// * `priority` - An integer priority; when several saved networks are in
+///   range, `wpa_supplicant` selects the one with the highest priority
+///
+/// If the priority is successfully set for the access point represented by
+/// the given network identifier, an `Ok` `Result` type is returned. In the
+/// event of an error, a `NetworkError` is returned in the `Result`. The
+/// `NetworkError` is then enumerated to a specific error type and an
+/// appropriate JSON RPC response is sent to the caller.
+///
+pub fn set_priority(id: &str, iface: &str, priority: i32) -> Result<(), NetworkError> {
+    let wpa_path: String = format!("/var/run/wpa_supplicant/{}", iface);
+    let mut wpa = wpactrl::WpaCtrl::new()
+        .ctrl_path(wpa_path)
+        .open()
+        .context(WpaCtrlOpen)?;
+    let set_priority = format!("SET_NETWORK {} priority {}", id, priority);
+    wpa.request(&set_priority).context(WpaCtrlRequest)?;
+    Ok(())
+}
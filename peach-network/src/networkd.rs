@@ -0,0 +1,784 @@
+//! Read and write systemd-networkd configuration for network interfaces.
+//!
+//! Each interface is matched by a `.network` file installed by `peach-config`
+//! (for example, `04-wired.network` for `eth0` and `08-wlan0.network` for
+//! `wlan0`). Rather than rewriting those files, the addressing mode, gateway
+//! and DNS servers are written to a drop-in file
+//! (`/etc/systemd/network/<network file>.d/50-peach.conf`) which
+//! systemd-networkd merges with the original configuration. Removing the
+//! drop-in restores the defaults.
+//!
+//! Changes are validated before being written. Once systemd-networkd has been
+//! restarted, connectivity is checked in the background until a timeout
+//! expires; if the interface does not come up with a routable address, or
+//! cannot reach any of the configured DNS servers (or, without any, the
+//! gateway), in that time the previous drop-in is restored.
+//!
+//! Leases handed out by the systemd-networkd DHCP server (enabled for `ap0`)
+//! are also read from its runtime state, allowing access point stations to be
+//! matched with their IP address and hostname.
+//!
+use std::{
+    fs, io,
+    net::{IpAddr, Ipv4Addr, SocketAddr, TcpStream, UdpSocket},
+    path::Path,
+    process::Command,
+    result::Result,
+    sync::atomic::{AtomicU64, Ordering},
+    thread,
+    time::{Duration, Instant},
+};
+
+use log::{debug, error, info, warn};
+use serde::{Deserialize, Serialize};
+use snafu::ResultExt;
+
+use crate::error::{NetworkError, ReadConfig, ReloadNetworkd, WriteConfig};
+use crate::{netlink, utils};
+
+/// File name of the drop-in written for each interface.
+pub const DROP_IN: &str = "50-peach.conf";
+
+/// Directory in which drop-in directories are created.
+pub const NETWORK_DIR: &str = "/etc/systemd/network";
+
+/// Default number of seconds to wait for connectivity before rolling back.
+pub const DEFAULT_TIMEOUT: u64 = 30;
+
+/// Directory in which the DHCP server persists its leases (one file per
+/// interface).
+pub const LEASE_DIR: &str = "/run/systemd/netif/dhcp-server-lease";
+
+// incremented each time a configuration is written, so that a pending
+// connectivity check does not roll back a newer configuration
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Lease handed out by the systemd-networkd DHCP server.
+#[derive(Debug)]
+pub struct Lease {
+    pub mac: String,
+    pub ip: String,
+    pub hostname: Option<String>,
+}
+
+/// Addressing mode, address, gateway and DNS servers for a network interface.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct NetConfig {
+    pub iface: String,
+    pub dhcp: bool,
+    pub address: Option<String>,
+    pub gateway: Option<String>,
+    pub dns: Vec<String>,
+}
+
+/// Network configuration update with an optional connectivity timeout
+/// (seconds).
+#[derive(Debug, Deserialize)]
+pub struct NetConfigUpdate {
+    pub iface: String,
+    pub dhcp: bool,
+    pub address: Option<String>,
+    pub gateway: Option<String>,
+    #[serde(default)]
+    pub dns: Vec<String>,
+    #[serde(default)]
+    pub timeout: Option<u64>,
+}
+
+impl NetConfigUpdate {
+    /// Split the update into the configuration to be written and the timeout.
+    pub fn into_parts(self) -> (NetConfig, u64) {
+        let config = NetConfig {
+            iface: self.iface,
+            dhcp: self.dhcp,
+            address: self.address,
+            gateway: self.gateway,
+            dns: self.dns,
+        };
+        let timeout = self.timeout.unwrap_or(DEFAULT_TIMEOUT);
+
+        (config, timeout)
+    }
+}
+
+/* GET - Methods for retrieving data */
+
+/// Retrieve the systemd-networkd configuration for a given interface.
+///
+/// # Arguments
+///
+/// * `iface` - A string slice holding the name of a network interface
+///
+/// The `.network` file matched to the interface by systemd-networkd is read,
+/// followed by the drop-in written by `set_net_config()` (if present). If
+/// both files are read successfully, an `Ok` `Result` type is returned
+/// containing a `NetConfig` with the effective addressing mode, address,
+/// gateway and DNS servers. In the event of an error, a `NetworkError` is
+/// returned in the `Result`. The `NetworkError` is then enumerated to a
+/// specific error type and an appropriate JSON RPC response is sent to the
+/// caller.
+///
+pub fn net_config(iface: &str) -> Result<NetConfig, NetworkError> {
+    let network_file = network_file(iface)?;
+    let mut contents = fs::read_to_string(&network_file).context(ReadConfig {
+        path: network_file.to_string(),
+    })?;
+    let drop_in = drop_in_path(&network_file);
+    match fs::read_to_string(&drop_in) {
+        Ok(drop_in_contents) => {
+            // the drop-in is applied after the original file
+            contents.push('\n');
+            contents.push_str(&drop_in_contents);
+        }
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => (),
+        Err(source) => {
+            return Err(NetworkError::ReadConfig {
+                path: drop_in,
+                source,
+            })
+        }
+    }
+
+    Ok(parse_config(iface, &contents))
+}
+
+/// Retrieve the leases handed out by the DHCP server on a given interface.
+///
+/// # Arguments
+///
+/// * `iface` - A string slice holding the name of a network interface
+///
+/// The JSON lease file written by systemd-networkd is parsed and a vector of
+/// `Lease` structs is returned. Leases are treated as supplementary data, so
+/// an empty vector is returned if the DHCP server is not running, the file
+/// cannot be parsed or the installed systemd version does not persist leases.
+///
+pub fn dhcp_leases(iface: &str) -> Vec<Lease> {
+    let path = format!("{}/{}", LEASE_DIR, iface);
+    let contents = match fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(e) => {
+            debug!("Failed to read DHCP leases from {}: {}", path, e);
+            return Vec::new();
+        }
+    };
+    let leases: serde_json::Value = match serde_json::from_str(&contents) {
+        Ok(leases) => leases,
+        Err(e) => {
+            warn!("Failed to parse DHCP leases from {}: {}", path, e);
+            return Vec::new();
+        }
+    };
+
+    leases["Leases"]
+        .as_array()
+        .map(|leases| leases.iter().filter_map(parse_lease).collect())
+        .unwrap_or_default()
+}
+
+/* SET - Methods for modifying state */
+
+/// Write the systemd-networkd configuration for a given interface, restart
+/// systemd-networkd and roll back if connectivity is lost.
+///
+/// # Arguments
+///
+/// * `config` - An instance of the `NetConfig` `struct` to be applied
+/// * `timeout` - Number of seconds to wait for connectivity to be confirmed
+///
+/// If the configuration is valid, the drop-in is written and systemd-networkd
+/// is restarted, an `Ok` `Result` type is returned without waiting for
+/// connectivity. Connectivity is then confirmed in a background thread; if it
+/// is not confirmed within the timeout, the previous drop-in is restored (or
+/// removed, if there was none), systemd-networkd is restarted again and a
+/// `ConfigRollback` error is logged. A configuration written before the
+/// timeout expires supersedes the pending check. In the event of any other
+/// error, a `NetworkError` is returned in the `Result`. The `NetworkError` is
+/// then enumerated to a specific error type and an appropriate JSON RPC
+/// response is sent to the caller.
+///
+pub fn set_net_config(config: &NetConfig, timeout: u64) -> Result<(), NetworkError> {
+    validate(config)?;
+
+    let network_file = network_file(&config.iface)?;
+    let drop_in = drop_in_path(&network_file);
+    let previous = match fs::read_to_string(&drop_in) {
+        Ok(contents) => Some(contents),
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => None,
+        Err(source) => {
+            return Err(NetworkError::ReadConfig {
+                path: drop_in,
+                source,
+            })
+        }
+    };
+
+    let generation = GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    write_drop_in(&drop_in, &render_drop_in(config))?;
+    restart_networkd()?;
+
+    let config = config.clone();
+    thread::spawn(move || {
+        if await_connectivity(&config, Duration::from_secs(timeout)) {
+            info!("Confirmed connectivity on {}.", config.iface);
+            return;
+        }
+        if GENERATION.load(Ordering::SeqCst) != generation {
+            debug!(
+                "Configuration of {} was superseded; not rolling back.",
+                config.iface
+            );
+            return;
+        }
+        warn!(
+            "Lost connectivity on {}; restoring previous configuration.",
+            config.iface
+        );
+        let restored = match previous {
+            Some(contents) => write_drop_in(&drop_in, &contents),
+            None => fs::remove_file(&drop_in).context(WriteConfig {
+                path: drop_in.to_string(),
+            }),
+        }
+        .and_then(|_| restart_networkd());
+        match restored {
+            Ok(()) => error!(
+                "{}",
+                NetworkError::ConfigRollback {
+                    iface: config.iface,
+                    timeout,
+                }
+            ),
+            Err(e) => error!("Failed to restore previous configuration: {}", e),
+        }
+    });
+
+    Ok(())
+}
+
+/* HELPERS */
+
+/// Return the path of the `.network` file which systemd-networkd has matched
+/// to the given interface, as recorded in its runtime link state.
+fn network_file(iface: &str) -> Result<String, NetworkError> {
+    let index = utils::iface_index(iface)?;
+    let link_path = format!("/run/systemd/netif/links/{}", index);
+    let link = fs::read_to_string(&link_path).context(ReadConfig { path: link_path })?;
+
+    link.lines()
+        .find_map(|line| line.strip_prefix("NETWORK_FILE="))
+        .map(|file| file.to_string())
+        .ok_or_else(|| NetworkError::NetworkFile {
+            iface: iface.to_string(),
+        })
+}
+
+/// Parse a single lease, where addresses are stored as arrays of bytes.
+fn parse_lease(lease: &serde_json::Value) -> Option<Lease> {
+    let bytes = |value: &serde_json::Value| -> Option<Vec<u8>> {
+        value
+            .as_array()?
+            .iter()
+            .map(|byte| byte.as_u64().map(|byte| byte as u8))
+            .collect()
+    };
+    let hw_addr = bytes(&lease["HardwareAddress"])?;
+    let addr = bytes(&lease["Address"])?;
+    if hw_addr.len() != 6 || addr.len() != 4 {
+        return None;
+    }
+    let mac = hw_addr
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect::<Vec<String>>()
+        .join(":");
+    let ip = Ipv4Addr::new(addr[0], addr[1], addr[2], addr[3]).to_string();
+    let hostname = lease["Hostname"].as_str().map(|name| name.to_string());
+
+    Some(Lease { mac, ip, hostname })
+}
+
+/// Return the path of the drop-in for the given `.network` file.
+fn drop_in_path(network_file: &str) -> String {
+    let file_name = Path::new(network_file)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    format!("{}/{}.d/{}", NETWORK_DIR, file_name, DROP_IN)
+}
+
+/// Parse the `[Network]` section of the given configuration, applying
+/// assignments in order so that later files override earlier ones.
+fn parse_config(iface: &str, contents: &str) -> NetConfig {
+    let mut config = NetConfig {
+        iface: iface.to_string(),
+        dhcp: false,
+        address: None,
+        gateway: None,
+        dns: Vec::new(),
+    };
+
+    let mut in_network = false;
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            in_network = line == "[Network]";
+            continue;
+        }
+        if !in_network || line.starts_with('#') {
+            continue;
+        }
+        if let Some((key, value)) = split_once(line, '=') {
+            let value = value.trim();
+            match key.trim() {
+                "DHCP" => config.dhcp = matches!(value, "yes" | "true" | "ipv4" | "ipv6"),
+                "Address" if !value.is_empty() => config.address = Some(value.to_string()),
+                "Gateway" if !value.is_empty() => config.gateway = Some(value.to_string()),
+                // an empty assignment resets the list of DNS servers
+                "DNS" if value.is_empty() => config.dns.clear(),
+                "DNS" => config
+                    .dns
+                    .extend(value.split_whitespace().map(|dns| dns.to_string())),
+                _ => (),
+            }
+        }
+    }
+
+    config
+}
+
+/// Render the drop-in contents for the given configuration.
+fn render_drop_in(config: &NetConfig) -> String {
+    let mut drop_in = String::from(
+        "# Written by peach-network. Remove this file to restore the default configuration.\n",
+    );
+    drop_in.push_str("[Network]\n");
+    if config.dhcp {
+        drop_in.push_str("DHCP=yes\n");
+    } else {
+        drop_in.push_str("DHCP=no\n");
+        if let Some(address) = &config.address {
+            drop_in.push_str(&format!("Address={}\n", address));
+        }
+        if let Some(gateway) = &config.gateway {
+            drop_in.push_str(&format!("Gateway={}\n", gateway));
+        }
+    }
+    if !config.dns.is_empty() {
+        // replace (rather than extend) any servers set in the original file
+        drop_in.push_str("DNS=\n");
+        drop_in.push_str(&format!("DNS={}\n", config.dns.join(" ")));
+        if config.dhcp {
+            // ignore servers offered by the DHCP server
+            drop_in.push_str("[DHCP]\nUseDNS=no\n");
+        }
+    }
+
+    drop_in
+}
+
+/// Ensure the configuration describes a usable static or DHCP setup.
+fn validate(config: &NetConfig) -> Result<(), NetworkError> {
+    let invalid = |err_msg: &str| NetworkError::InvalidConfig {
+        iface: config.iface.to_string(),
+        err_msg: err_msg.to_string(),
+    };
+
+    let address = match (&config.address, config.dhcp) {
+        (Some(_), true) => return Err(invalid("address cannot be set when DHCP is enabled")),
+        (None, false) => return Err(invalid("address is required for static configuration")),
+        (Some(address), false) => Some(parse_cidr(address).ok_or_else(|| {
+            invalid("address must be an IP address with prefix length (eg. 192.168.1.50/24)")
+        })?),
+        (None, true) => None,
+    };
+
+    if let Some(gateway) = &config.gateway {
+        if config.dhcp {
+            return Err(invalid("gateway cannot be set when DHCP is enabled"));
+        }
+        let gateway: IpAddr = gateway
+            .parse()
+            .map_err(|_| invalid("gateway must be an IP address"))?;
+        if let Some((address, prefix)) = address {
+            if !same_subnet(address, gateway, prefix) {
+                return Err(invalid("gateway is not in the same subnet as the address"));
+            }
+        }
+    }
+
+    if config.dns.iter().any(|dns| dns.parse::<IpAddr>().is_err()) {
+        return Err(invalid("DNS servers must be IP addresses"));
+    }
+
+    Ok(())
+}
+
+/// Parse an address in CIDR notation (eg. `192.168.1.50/24`).
+fn parse_cidr(cidr: &str) -> Option<(IpAddr, u8)> {
+    let (address, prefix) = split_once(cidr, '/')?;
+    let address: IpAddr = address.parse().ok()?;
+    let prefix: u8 = prefix.parse().ok()?;
+    let max_prefix = if address.is_ipv4() { 32 } else { 128 };
+
+    if prefix <= max_prefix {
+        Some((address, prefix))
+    } else {
+        None
+    }
+}
+
+/// Determine whether two addresses share the network defined by `prefix`.
+fn same_subnet(a: IpAddr, b: IpAddr, prefix: u8) -> bool {
+    match (a, b) {
+        (IpAddr::V4(a), IpAddr::V4(b)) => {
+            let mask = u32::MAX.checked_shl(32 - u32::from(prefix)).unwrap_or(0);
+            u32::from(a) & mask == u32::from(b) & mask
+        }
+        (IpAddr::V6(a), IpAddr::V6(b)) => {
+            let mask = u128::MAX.checked_shl(128 - u32::from(prefix)).unwrap_or(0);
+            u128::from(a) & mask == u128::from(b) & mask
+        }
+        _ => false,
+    }
+}
+
+/// Split a string at the first occurrence of the given delimiter.
+fn split_once(s: &str, delimiter: char) -> Option<(&str, &str)> {
+    let index = s.find(delimiter)?;
+
+    Some((&s[..index], &s[index + 1..]))
+}
+
+/// Write the drop-in, creating its parent directory if required.
+fn write_drop_in(path: &str, contents: &str) -> Result<(), NetworkError> {
+    if let Some(dir) = Path::new(path).parent() {
+        fs::create_dir_all(dir).context(WriteConfig {
+            path: dir.to_string_lossy().to_string(),
+        })?;
+    }
+    fs::write(path, contents).context(WriteConfig {
+        path: path.to_string(),
+    })?;
+
+    Ok(())
+}
+
+/// Restart systemd-networkd so that configuration changes take effect.
+fn restart_networkd() -> Result<(), NetworkError> {
+    debug!("Restarting systemd-networkd.");
+    let output = Command::new("sudo")
+        .arg("/usr/bin/systemctl")
+        .arg("restart")
+        .arg("systemd-networkd.service")
+        .output()
+        .context(ReloadNetworkd)?;
+    if !output.status.success() {
+        let msg = format!(
+            "systemctl exited with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+        return Err(NetworkError::ReloadNetworkd {
+            source: io::Error::other(msg),
+        });
+    }
+
+    Ok(())
+}
+
+/// Poll until the interface is up with a routable address and its uplink
+/// answers: one of the DNS servers (if any are configured) must accept a
+/// connection on port 53, or else the gateway (if one is configured) must
+/// respond. A DNS server beyond the gateway can only be reached through it,
+/// while the gateway itself need not serve DNS.
+fn await_connectivity(config: &NetConfig, timeout: Duration) -> bool {
+    let dns: Vec<IpAddr> = config
+        .dns
+        .iter()
+        .filter_map(|dns| dns.parse().ok())
+        .collect();
+    let gateway: Option<IpAddr> = config
+        .gateway
+        .as_ref()
+        .and_then(|gateway| gateway.parse().ok());
+
+    // fall back to polling if link notifications are unavailable
+    let mut monitor = netlink::LinkMonitor::new().ok();
+    let deadline = Instant::now() + timeout;
+    while Instant::now() < deadline {
+        // allow time for the interface to be reconfigured before checking,
+        // waking early if the kernel reports a link change for it
+        let wait = Duration::from_secs(2);
+        match monitor.as_mut() {
+            Some(monitor) => {
+                if let Err(e) = monitor.wait_for(&config.iface, wait) {
+                    debug!("Failed to receive link events: {}", e);
+                    thread::sleep(wait);
+                }
+            }
+            None => thread::sleep(wait),
+        }
+        if is_connected(&config.iface, &dns, gateway) {
+            return true;
+        }
+    }
+
+    false
+}
+
+/// Check the interface state and address, then the reachability of the DNS
+/// servers or, if there are none, of the gateway.
+fn is_connected(iface: &str, dns: &[IpAddr], gateway: Option<IpAddr>) -> bool {
+    let is_up = utils::sys_attr(iface, "operstate")
+        .map(|state| state == "up")
+        .unwrap_or(false);
+    let has_address = match (utils::iface_index(iface), netlink::addresses()) {
+        (Ok(index), Ok(addresses)) => addresses
+            .iter()
+            .any(|a| a.index == index && is_routable(a.ip)),
+        _ => false,
+    };
+    if !is_up || !has_address {
+        return false;
+    }
+
+    match (dns.is_empty(), gateway) {
+        (false, _) => dns.iter().any(|ip| {
+            TcpStream::connect_timeout(&SocketAddr::new(*ip, 53), Duration::from_secs(2)).is_ok()
+        }),
+        (true, Some(gateway)) => gateway_answers(iface, gateway),
+        (true, None) => true,
+    }
+}
+
+/// Whether an address can be used beyond the local link (an IPv4 link-local
+/// address is assigned when DHCP fails, and IPv6 link-local addresses are
+/// always present).
+fn is_routable(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => !ip.is_link_local() && !ip.is_loopback() && !ip.is_unspecified(),
+        IpAddr::V6(ip) => {
+            (ip.segments()[0] & 0xffc0) != 0xfe80 && !ip.is_loopback() && !ip.is_unspecified()
+        }
+    }
+}
+
+/// Check that the gateway responds. An IPv4 gateway must be resolved with
+/// ARP (prompted by sending it a datagram), while an IPv6 gateway must
+/// answer a connection attempt, even if only to refuse it.
+fn gateway_answers(iface: &str, gateway: IpAddr) -> bool {
+    let timeout = Duration::from_secs(2);
+    match gateway {
+        IpAddr::V4(ip) => {
+            if let Ok(socket) = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)) {
+                // the discard port; the datagram itself is not expected to
+                // be answered
+                let _ = socket.send_to(&[], (ip, 9));
+            }
+            let deadline = Instant::now() + timeout;
+            loop {
+                let resolved = fs::read_to_string("/proc/net/arp")
+                    .map(|arp| arp_resolved(&arp, iface, ip))
+                    .unwrap_or(false);
+                if resolved || Instant::now() >= deadline {
+                    return resolved;
+                }
+                thread::sleep(Duration::from_millis(100));
+            }
+        }
+        IpAddr::V6(_) => match TcpStream::connect_timeout(&SocketAddr::new(gateway, 53), timeout) {
+            Ok(_) => true,
+            Err(e) => e.kind() == io::ErrorKind::ConnectionRefused,
+        },
+    }
+}
+
+/// Whether the kernel ARP table (`/proc/net/arp`) holds a complete entry for
+/// the address on the interface.
+fn arp_resolved(arp: &str, iface: &str, ip: Ipv4Addr) -> bool {
+    // completed entries have the ATF_COM flag (0x2) set
+    const ATF_COM: u32 = 0x2;
+    arp.lines().skip(1).any(|line| {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        match fields.as_slice() {
+            [address, _, flags, _, _, device] => {
+                address.parse() == Ok(ip)
+                    && *device == iface
+                    && u32::from_str_radix(flags.trim_start_matches("0x"), 16)
+                        .map(|flags| flags & ATF_COM != 0)
+                        .unwrap_or(false)
+            }
+            _ => false,
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn static_config(address: &str, gateway: Option<&str>, dns: &[&str]) -> NetConfig {
+        NetConfig {
+            iface: "eth0".to_string(),
+            dhcp: false,
+            address: Some(address.to_string()),
+            gateway: gateway.map(|gateway| gateway.to_string()),
+            dns: dns.iter().map(|dns| dns.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn drop_in_overrides_network_file() {
+        let contents = "[Match]\nName=eth0\n\n[Network]\nDHCP=yes\nDNS=9.9.9.9\n\n\
+                        [DHCP]\nDNS=8.8.8.8\n\n\
+                        [Network]\nDHCP=no\n# Address=10.0.0.1/8\nAddress=192.168.1.50/24\n\
+                        Gateway=192.168.1.1\nDNS=\nDNS=1.1.1.1 1.0.0.1\n";
+        let config = parse_config("eth0", contents);
+        assert_eq!(config.iface, "eth0");
+        assert!(!config.dhcp);
+        assert_eq!(config.address.as_deref(), Some("192.168.1.50/24"));
+        assert_eq!(config.gateway.as_deref(), Some("192.168.1.1"));
+        assert_eq!(config.dns, vec!["1.1.1.1", "1.0.0.1"]);
+
+        let config = parse_config("eth0", "[Network]\nDHCP=ipv4\n");
+        assert!(config.dhcp);
+        assert_eq!(config.address, None);
+        assert!(config.dns.is_empty());
+    }
+
+    #[test]
+    fn static_drop_in_is_rendered() {
+        let config = static_config(
+            "192.168.1.50/24",
+            Some("192.168.1.1"),
+            &["1.1.1.1", "1.0.0.1"],
+        );
+        let drop_in = render_drop_in(&config);
+        assert!(drop_in.ends_with(
+            "[Network]\nDHCP=no\nAddress=192.168.1.50/24\nGateway=192.168.1.1\n\
+             DNS=\nDNS=1.1.1.1 1.0.0.1\n"
+        ));
+
+        // a rendered drop-in reads back as the same configuration
+        let parsed = parse_config("eth0", &drop_in);
+        assert_eq!(parsed.address, config.address);
+        assert_eq!(parsed.gateway, config.gateway);
+        assert_eq!(parsed.dns, config.dns);
+    }
+
+    #[test]
+    fn dhcp_drop_in_is_rendered() {
+        let mut config = NetConfig {
+            iface: "eth0".to_string(),
+            dhcp: true,
+            address: None,
+            gateway: None,
+            dns: Vec::new(),
+        };
+        assert!(render_drop_in(&config).ends_with("[Network]\nDHCP=yes\n"));
+
+        // static DNS servers replace those offered by the DHCP server
+        config.dns = vec!["1.1.1.1".to_string()];
+        assert!(render_drop_in(&config)
+            .ends_with("[Network]\nDHCP=yes\nDNS=\nDNS=1.1.1.1\n[DHCP]\nUseDNS=no\n"));
+    }
+
+    #[test]
+    fn configs_are_validated() {
+        assert!(validate(&static_config(
+            "192.168.1.50/24",
+            Some("192.168.1.1"),
+            &["1.1.1.1"]
+        ))
+        .is_ok());
+        assert!(validate(&static_config(
+            "fd00::50/64",
+            Some("fd00::1"),
+            &["fd00::53"]
+        ))
+        .is_ok());
+
+        for config in &[
+            static_config("192.168.1.50", None, &[]),
+            static_config("192.168.1.50/33", None, &[]),
+            static_config("192.168.1.50/24", Some("192.168.2.1"), &[]),
+            static_config("192.168.1.50/24", Some("gateway"), &[]),
+            static_config("192.168.1.50/24", None, &["dns.example.com"]),
+        ] {
+            match validate(config) {
+                Err(NetworkError::InvalidConfig { .. }) => (),
+                result => panic!("{:?} was not rejected: {:?}", config, result),
+            }
+        }
+
+        let mut dhcp = static_config("192.168.1.50/24", None, &[]);
+        dhcp.dhcp = true;
+        assert!(validate(&dhcp).is_err());
+        dhcp.address = None;
+        assert!(validate(&dhcp).is_ok());
+        dhcp.gateway = Some("192.168.1.1".to_string());
+        assert!(validate(&dhcp).is_err());
+        dhcp.gateway = None;
+        dhcp.address = None;
+        dhcp.dhcp = false;
+        assert!(validate(&dhcp).is_err());
+    }
+
+    #[test]
+    fn cidr_addresses_are_parsed() {
+        assert_eq!(
+            parse_cidr("192.168.1.50/24"),
+            Some((IpAddr::V4(Ipv4Addr::new(192, 168, 1, 50)), 24))
+        );
+        assert_eq!(
+            parse_cidr("fd00::1/128").map(|(_, prefix)| prefix),
+            Some(128)
+        );
+        assert_eq!(parse_cidr("fd00::1/129"), None);
+        assert_eq!(parse_cidr("10.0.0.1/33"), None);
+        assert_eq!(parse_cidr("10.0.0.1"), None);
+        assert_eq!(parse_cidr("10.0.0/8"), None);
+        assert_eq!(parse_cidr("10.0.0.1/x"), None);
+    }
+
+    #[test]
+    fn link_local_addresses_are_not_routable() {
+        for ip in &["169.254.10.1", "fe80::1", "127.0.0.1", "::1", "0.0.0.0"] {
+            assert!(!is_routable(ip.parse().unwrap()), "{}", ip);
+        }
+        for ip in &["192.168.1.50", "10.0.0.1", "2001:db8::1", "fd00::1"] {
+            assert!(is_routable(ip.parse().unwrap()), "{}", ip);
+        }
+    }
+
+    #[test]
+    fn arp_entries_are_matched() {
+        let arp =
+            "IP address       HW type     Flags       HW address            Mask     Device\n\
+                   192.168.1.1      0x1         0x2         aa:bb:cc:dd:ee:ff     *        eth0\n\
+                   192.168.1.2      0x1         0x0         00:00:00:00:00:00     *        eth0\n\
+                   10.0.0.1         0x1         0x2         aa:bb:cc:dd:ee:00     *        wlan0\n";
+        let ip = |ip: &str| -> Ipv4Addr { ip.parse().unwrap() };
+        assert!(arp_resolved(arp, "eth0", ip("192.168.1.1")));
+        // incomplete entries and entries of other interfaces don't count
+        assert!(!arp_resolved(arp, "eth0", ip("192.168.1.2")));
+        assert!(!arp_resolved(arp, "eth0", ip("10.0.0.1")));
+        assert!(!arp_resolved(arp, "eth0", ip("192.168.1.3")));
+    }
+
+    #[test]
+    fn subnets_are_compared() {
+        let ip = |ip: &str| ip.parse::<IpAddr>().unwrap();
+        assert!(same_subnet(ip("192.168.1.50"), ip("192.168.1.1"), 24));
+        assert!(!same_subnet(ip("192.168.1.50"), ip("192.168.2.1"), 24));
+        assert!(same_subnet(ip("192.168.1.50"), ip("192.168.2.1"), 16));
+        assert!(same_subnet(ip("10.0.0.1"), ip("192.168.2.1"), 0));
+        assert!(!same_subnet(ip("10.0.0.1"), ip("10.0.0.2"), 32));
+        assert!(same_subnet(ip("fd00::50"), ip("fd00::1"), 64));
+        assert!(!same_subnet(ip("fd00::50"), ip("fd01::1"), 64));
+        // addresses of different families never share a subnet
+        assert!(!same_subnet(ip("10.0.0.1"), ip("::1"), 0));
+    }
+}
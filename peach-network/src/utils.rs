@@ -1,3 +1,5 @@
+use std::{fs, io};
+
 use regex::Regex;
 use snafu::ResultExt;
 
@@ -17,3 +19,55 @@ pub fn regex_finder(pattern: &str, text: &str) -> Result<Option<String>, Network
 
     Ok(result)
 }
+
+/// Read an attribute of a network interface from sysfs, with surrounding
+/// whitespace removed
+///
+/// # Arguments
+///
+/// * `iface` - A string slice holding the name of a network interface
+/// * `attr` - A string slice holding the name of an attribute which is present
+///   for every interface (eg. `operstate` or `ifindex`)
+///
+/// A missing attribute is reported as a missing interface, while permission
+/// errors are reported separately from other I/O errors.
+///
+pub fn sys_attr(iface: &str, attr: &str) -> Result<String, NetworkError> {
+    let path = format!("/sys/class/net/{}/{}", iface, attr);
+    fs::read_to_string(&path)
+        .map(|value| value.trim().to_string())
+        .map_err(|source| match source.kind() {
+            io::ErrorKind::NotFound => NetworkError::NoSuchInterface {
+                iface: iface.to_string(),
+            },
+            io::ErrorKind::PermissionDenied => NetworkError::PermissionDenied { path },
+            _ => NetworkError::ReadSysfs { path, source },
+        })
+}
+
+/// Return the kernel index of a network interface
+///
+/// # Arguments
+///
+/// * `iface` - A string slice holding the name of a network interface
+///
+pub fn iface_index(iface: &str) -> Result<u32, NetworkError> {
+    let index = sys_attr(iface, "ifindex")?;
+    index.parse().map_err(|_| NetworkError::NoSuchInterface {
+        iface: iface.to_string(),
+    })
+}
+
+/// Check whether a string is a colon-separated MAC address
+///
+/// # Arguments
+///
+/// * `mac` - A string slice holding a MAC address (eg. `b8:27:eb:12:34:56`)
+///
+pub fn is_mac(mac: &str) -> bool {
+    let octets: Vec<&str> = mac.split(':').collect();
+    octets.len() == 6
+        && octets
+            .iter()
+            .all(|octet| octet.len() == 2 && octet.chars().all(|c| c.is_ascii_hexdigit()))
+}
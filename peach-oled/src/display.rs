@@ -0,0 +1,463 @@
+//! Display backends.
+//!
+//! The JSON-RPC methods draw to a `Display`, which is either the SSD1306 OLED
+//! connected over I2C (`Ssd1306`) or an in-memory `Framebuffer` which allows
+//! `peach-oled` (and `peach-menu`) to run on a development machine. The
+//! framebuffer can write each flushed frame to a PNG or PBM file, or render it
+//! in the terminal.
+//!
+//! Both backends keep a copy of the frame which is currently shown on the
+//! display, which can be read with `Display::frame` (and is returned by the
+//! `snapshot` method).
+
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+use embedded_graphics::drawable::Pixel;
+use embedded_graphics::pixelcolor::PixelColorU8;
+use embedded_graphics::unsignedcoord::UnsignedCoord;
+use embedded_graphics::Drawing;
+use linux_embedded_hal::i2cdev::core::I2CDevice;
+use linux_embedded_hal::i2cdev::linux::LinuxI2CDevice;
+use log::error;
+use ssd1306::interface::DisplayInterface;
+use ssd1306::prelude::*;
+
+use crate::error::OledError;
+
+/// Width of the display in pixels.
+pub const WIDTH: u32 = 128;
+/// Height of the display in pixels.
+pub const HEIGHT: u32 = 64;
+/// Contrast of the display after initialization.
+pub const DEFAULT_CONTRAST: u8 = 0x8f;
+
+/// A monochrome image the size of the display.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Frame {
+    pixels: Vec<bool>,
+}
+
+impl Default for Frame {
+    fn default() -> Frame {
+        Frame {
+            pixels: vec![false; (WIDTH * HEIGHT) as usize],
+        }
+    }
+}
+
+impl Frame {
+    pub fn new() -> Frame {
+        Frame::default()
+    }
+
+    /// Whether the pixel at the given coordinates is lit. Pixels outside the
+    /// display are never lit.
+    pub fn get(&self, x: u32, y: u32) -> bool {
+        x < WIDTH && y < HEIGHT && self.pixels[(y * WIDTH + x) as usize]
+    }
+
+    /// Light or darken a pixel. Pixels outside the display are ignored.
+    pub fn set(&mut self, x: u32, y: u32, on: bool) {
+        if x < WIDTH && y < HEIGHT {
+            self.pixels[(y * WIDTH + x) as usize] = on;
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.pixels.iter_mut().for_each(|pixel| *pixel = false);
+    }
+
+    /// Encode the frame as a plain (ASCII) PBM image, in which `1` is a lit
+    /// pixel. Plain PBM files can be compared line by line, which makes them
+    /// suitable for snapshot tests.
+    pub fn to_pbm(&self) -> String {
+        let mut pbm = format!("P1\n{} {}\n", WIDTH, HEIGHT);
+        for row in self.pixels.chunks(WIDTH as usize) {
+            let row: Vec<&str> = row.iter().map(|on| if *on { "1" } else { "0" }).collect();
+            pbm.push_str(&row.join(" "));
+            pbm.push('\n');
+        }
+        pbm
+    }
+
+    /// Decode a plain PBM image the size of the display.
+    pub fn from_pbm(pbm: &str) -> Option<Frame> {
+        // skip comments, then the magic number and dimensions
+        let mut tokens = pbm
+            .lines()
+            .map(|line| line.split('#').next().unwrap_or(""))
+            .flat_map(|line| line.split_whitespace());
+        if tokens.next()? != "P1"
+            || tokens.next()?.parse::<u32>().ok()? != WIDTH
+            || tokens.next()?.parse::<u32>().ok()? != HEIGHT
+        {
+            return None;
+        }
+        // pixels may be separated by whitespace or not
+        let pixels: Vec<bool> = tokens
+            .flat_map(|token| token.chars())
+            .map(|c| c == '1')
+            .collect();
+        if pixels.len() != (WIDTH * HEIGHT) as usize {
+            return None;
+        }
+        Some(Frame { pixels })
+    }
+
+    /// Pack the frame into a 1-bpp bitmap: 8 pixels per byte, most
+    /// significant bit first, row by row. This is the same format accepted by
+    /// the `draw` method.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.pixels
+            .chunks(8)
+            .map(|bits| {
+                bits.iter()
+                    .enumerate()
+                    .fold(0u8, |byte, (i, on)| byte | ((*on as u8) << (7 - i)))
+            })
+            .collect()
+    }
+
+    /// Encode the frame as a PNG image (1-bit greyscale, lit pixels white).
+    pub fn to_png(&self) -> Result<Vec<u8>, png::EncodingError> {
+        let data = self.to_bytes();
+
+        let mut png = Vec::new();
+        {
+            let mut encoder = png::Encoder::new(&mut png, WIDTH, HEIGHT);
+            encoder.set_color(png::ColorType::Grayscale);
+            encoder.set_depth(png::BitDepth::One);
+            let mut writer = encoder.write_header()?;
+            writer.write_image_data(&data)?;
+        }
+        Ok(png)
+    }
+
+    /// Render the frame as text for the terminal, using half-block characters
+    /// so that each line of text shows two rows of pixels.
+    pub fn to_terminal(&self) -> String {
+        let mut text = String::new();
+        for y in (0..HEIGHT).step_by(2) {
+            for x in 0..WIDTH {
+                text.push(match (self.get(x, y), self.get(x, y + 1)) {
+                    (true, true) => '█',
+                    (true, false) => '▀',
+                    (false, true) => '▄',
+                    (false, false) => ' ',
+                });
+            }
+            text.push('\n');
+        }
+        text
+    }
+}
+
+/// Anything which can be drawn to, pixel by pixel: a display buffer or an
+/// off-screen layer.
+pub trait Canvas {
+    /// Light or darken a pixel.
+    fn set_pixel(&mut self, x: u32, y: u32, on: bool);
+}
+
+/// A display which the JSON-RPC methods draw to.
+///
+/// Drawing happens in a buffer: the display only changes when the buffer is
+/// flushed.
+pub trait Display: Canvas + Send {
+    /// Initialize the display.
+    fn init(&mut self) -> Result<(), OledError>;
+
+    /// Clear the buffer.
+    fn clear(&mut self);
+
+    /// Show the contents of the buffer on the display.
+    fn flush(&mut self) -> Result<(), OledError>;
+
+    /// Turn the display on or off. The contents are kept while it is off.
+    fn power(&mut self, on: bool) -> Result<(), OledError>;
+
+    /// Whether the display is turned on.
+    fn is_on(&self) -> bool;
+
+    /// Set the contrast (brightness) of the display, from 0 to 255.
+    fn set_contrast(&mut self, level: u8) -> Result<(), OledError>;
+
+    /// The frame which is currently shown on the display (the buffer as of
+    /// the last flush).
+    fn frame(&self) -> &Frame;
+}
+
+// allows fonts and images from `embedded-graphics` to be drawn to any display
+impl Drawing<PixelColorU8> for dyn Display + '_ {
+    fn draw<T>(&mut self, item_pixels: T)
+    where
+        T: Iterator<Item = Pixel<PixelColorU8>>,
+    {
+        for Pixel(UnsignedCoord(x, y), color) in item_pixels {
+            self.set_pixel(x, y, color.into_inner() != 0);
+        }
+    }
+}
+
+/// I2C address of the SSD1306 display.
+pub const SSD1306_ADDRESS: u16 = 0x3c;
+
+/// The SSD1306 OLED display.
+///
+/// The `ssd1306` driver does not expose the contrast command, so it is sent
+/// over a second handle (`control`) to the display's I2C device.
+pub struct Ssd1306<DI: DisplayInterface> {
+    disp: GraphicsMode<DI>,
+    control: LinuxI2CDevice,
+    buffer: Frame,
+    visible: Frame,
+    on: bool,
+}
+
+impl<DI: DisplayInterface> Ssd1306<DI> {
+    pub fn new(disp: GraphicsMode<DI>, control: LinuxI2CDevice) -> Ssd1306<DI> {
+        Ssd1306 {
+            disp,
+            control,
+            buffer: Frame::new(),
+            visible: Frame::new(),
+            on: false,
+        }
+    }
+}
+
+impl<DI: DisplayInterface> Canvas for Ssd1306<DI> {
+    fn set_pixel(&mut self, x: u32, y: u32, on: bool) {
+        self.disp.set_pixel(x, y, on as u8);
+        self.buffer.set(x, y, on);
+    }
+}
+
+impl<DI: DisplayInterface + Send> Display for Ssd1306<DI> {
+    fn init(&mut self) -> Result<(), OledError> {
+        self.disp.init().map_err(|_| OledError::DisplayError {
+            msg: "Problem initializing the OLED display".to_string(),
+        })?;
+        self.on = true;
+        Ok(())
+    }
+
+    fn clear(&mut self) {
+        self.disp.clear();
+        self.buffer.clear();
+    }
+
+    fn flush(&mut self) -> Result<(), OledError> {
+        self.disp.flush().map_err(|_| OledError::DisplayError {
+            msg: "Problem flushing the OLED display".to_string(),
+        })?;
+        self.visible = self.buffer.clone();
+        Ok(())
+    }
+
+    fn power(&mut self, on: bool) -> Result<(), OledError> {
+        self.disp
+            .display_on(on)
+            .map_err(|_| OledError::DisplayError {
+                msg: "Problem turning the OLED display on or off".to_string(),
+            })?;
+        self.on = on;
+        Ok(())
+    }
+
+    fn is_on(&self) -> bool {
+        self.on
+    }
+
+    fn set_contrast(&mut self, level: u8) -> Result<(), OledError> {
+        // control byte (0x00: command stream), then the contrast command
+        self.control
+            .write(&[0x00, 0x81, level])
+            .map_err(|err| OledError::DisplayError {
+                msg: format!("Problem setting the contrast of the OLED display: {}", err),
+            })
+    }
+
+    fn frame(&self) -> &Frame {
+        &self.visible
+    }
+}
+
+/// Where the framebuffer writes each frame.
+#[derive(Debug, PartialEq)]
+pub enum Output {
+    Pbm(PathBuf),
+    Png(PathBuf),
+    Terminal,
+}
+
+impl Output {
+    /// Parse an output: `terminal`, or the path of a file whose extension
+    /// selects the format (`.png` or `.pbm`).
+    pub fn parse(value: &str) -> Output {
+        if value == "terminal" {
+            return Output::Terminal;
+        }
+        let path = PathBuf::from(value);
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("png") => Output::Png(path),
+            _ => Output::Pbm(path),
+        }
+    }
+
+    fn write(&self, frame: &Frame) -> io::Result<()> {
+        match self {
+            Output::Pbm(path) => fs::write(path, frame.to_pbm()),
+            Output::Png(path) => {
+                let png = frame.to_png().map_err(io::Error::other)?;
+                fs::write(path, png)
+            }
+            Output::Terminal => {
+                // redraw in place: clear the screen and move the cursor home
+                let mut stdout = io::stdout();
+                write!(stdout, "\x1b[2J\x1b[H{}", frame.to_terminal())?;
+                stdout.flush()
+            }
+        }
+    }
+}
+
+/// An in-memory display, for development and tests.
+#[derive(Default)]
+pub struct Framebuffer {
+    buffer: Frame,
+    visible: Frame,
+    on: bool,
+    contrast: u8,
+    output: Option<Output>,
+}
+
+impl Framebuffer {
+    pub fn new(output: Option<Output>) -> Framebuffer {
+        Framebuffer {
+            contrast: DEFAULT_CONTRAST,
+            output,
+            ..Framebuffer::default()
+        }
+    }
+
+    /// The contrast of the display (it is not shown in the output).
+    pub fn contrast(&self) -> u8 {
+        self.contrast
+    }
+
+    // write the frame as it appears (blank while the display is off); this is
+    // a development aid, so failures are logged rather than returned
+    fn write_output(&self) {
+        if let Some(output) = &self.output {
+            let blank = Frame::new();
+            let frame = if self.on { &self.visible } else { &blank };
+            if let Err(err) = output.write(frame) {
+                error!("Problem writing the framebuffer to {:?}: {}", output, err);
+            }
+        }
+    }
+}
+
+impl Canvas for Framebuffer {
+    fn set_pixel(&mut self, x: u32, y: u32, on: bool) {
+        self.buffer.set(x, y, on);
+    }
+}
+
+impl Display for Framebuffer {
+    fn init(&mut self) -> Result<(), OledError> {
+        self.on = true;
+        self.write_output();
+        Ok(())
+    }
+
+    fn clear(&mut self) {
+        self.buffer.clear();
+    }
+
+    fn flush(&mut self) -> Result<(), OledError> {
+        self.visible = self.buffer.clone();
+        self.write_output();
+        Ok(())
+    }
+
+    fn power(&mut self, on: bool) -> Result<(), OledError> {
+        self.on = on;
+        self.write_output();
+        Ok(())
+    }
+
+    fn is_on(&self) -> bool {
+        self.on
+    }
+
+    fn set_contrast(&mut self, level: u8) -> Result<(), OledError> {
+        self.contrast = level;
+        Ok(())
+    }
+
+    fn frame(&self) -> &Frame {
+        &self.visible
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use embedded_graphics::coord::Coord;
+    use embedded_graphics::fonts::Font6x8;
+    use embedded_graphics::prelude::*;
+
+    // test that drawing only changes the frame once flushed
+    #[test]
+    fn framebuffer_flush() {
+        let mut display: Box<dyn Display> = Box::new(Framebuffer::new(None));
+        display.init().unwrap();
+        display.draw(
+            Font6x8::render_str("PeachCloud")
+                .translate(Coord::new(0, 0))
+                .into_iter(),
+        );
+        assert_eq!(display.frame(), &Frame::new());
+
+        display.flush().unwrap();
+        assert_ne!(display.frame(), &Frame::new());
+
+        display.clear();
+        display.flush().unwrap();
+        assert_eq!(display.frame(), &Frame::new());
+    }
+
+    // test that frames survive a round trip through the PBM format
+    #[test]
+    fn pbm_round_trip() {
+        let mut frame = Frame::new();
+        frame.set(0, 0, true);
+        frame.set(127, 63, true);
+        frame.set(128, 0, true);
+
+        let decoded = Frame::from_pbm(&frame.to_pbm()).unwrap();
+        assert_eq!(decoded, frame);
+        assert!(decoded.get(127, 63));
+        assert!(!decoded.get(128, 0));
+    }
+
+    // test that the packed bitmap is row-major with the msb first
+    #[test]
+    fn frame_to_bytes() {
+        let mut frame = Frame::new();
+        frame.set(0, 0, true);
+        frame.set(9, 0, true);
+        frame.set(127, 63, true);
+
+        let bytes = frame.to_bytes();
+        assert_eq!(bytes.len(), (WIDTH * HEIGHT / 8) as usize);
+        assert_eq!(bytes[0], 0b1000_0000);
+        assert_eq!(bytes[1], 0b0100_0000);
+        assert_eq!(bytes[bytes.len() - 1], 0b0000_0001);
+    }
+}
@@ -9,6 +9,9 @@ pub type BoxError = Box<dyn error::Error>;
 #[derive(Debug, Snafu)]
 #[snafu(visibility(pub(crate)))]
 pub enum OledError {
+    #[snafu(display("{}", msg))]
+    DisplayError { msg: String },
+
     #[snafu(display("Failed to create interface for I2C device: {}", source))]
     I2CError {
         source: hal::i2cdev::linux::LinuxI2CError,
@@ -21,7 +24,6 @@ pub enum OledError {
         value: i32,
     },
 
-    // TODO: implement for validate() in src/lib.rs
     #[snafu(display("Font size invalid: {}", font))]
     InvalidFontSize { font: String },
 
@@ -38,6 +40,11 @@ pub enum OledError {
 impl From<OledError> for Error {
     fn from(err: OledError) -> Self {
         match &err {
+            OledError::DisplayError { msg } => Error {
+                code: ErrorCode::ServerError(-32004),
+                message: msg.to_string(),
+                data: None,
+            },
             OledError::I2CError { source } => Error {
                 code: ErrorCode::ServerError(-32000),
                 message: format!("Failed to create interface for I2C device: {}", source),
@@ -1,15 +1,24 @@
-mod error;
+pub mod display;
+pub mod error;
+pub mod scene;
+pub mod shapes;
+pub mod text;
 
 use std::{
     env, process,
     result::Result,
     sync::{Arc, Mutex},
+    thread,
 };
 
 use embedded_graphics::coord::Coord;
+use embedded_graphics::drawable::Pixel;
 use embedded_graphics::fonts::{Font12x16, Font6x12, Font6x8, Font8x16};
 use embedded_graphics::image::Image1BPP;
+use embedded_graphics::pixelcolor::PixelColorU8;
 use embedded_graphics::prelude::*;
+use embedded_graphics::Drawing;
+use hal::i2cdev::linux::LinuxI2CDevice;
 use hal::I2cdev;
 use jsonrpc_core::{types::error::Error, IoHandler, Params, Value};
 use jsonrpc_http_server::{AccessControlAllowOrigin, DomainsValidation, ServerBuilder};
@@ -17,12 +26,19 @@ use jsonrpc_http_server::{AccessControlAllowOrigin, DomainsValidation, ServerBui
 use jsonrpc_test as test;
 use linux_embedded_hal as hal;
 use log::{debug, error, info};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use snafu::{ensure, ResultExt};
 use ssd1306::prelude::*;
 use ssd1306::Builder;
 
-use crate::error::{BoxError, I2CError, InvalidCoordinate, InvalidString, OledError};
+use crate::display::{
+    Canvas, Display, Framebuffer, Output, Ssd1306, HEIGHT, SSD1306_ADDRESS, WIDTH,
+};
+use crate::error::{
+    BoxError, I2CError, InvalidCoordinate, InvalidFontSize, InvalidString, OledError,
+};
+use crate::scene::{Layers, Scene};
+use crate::text::TextBox;
 
 //define the Graphic struct for receiving draw commands
 #[derive(Debug, Deserialize)]
@@ -49,11 +65,120 @@ pub struct On {
     on: bool,
 }
 
+//define the Contrast struct for receiving contrast commands
+#[derive(Debug, Deserialize)]
+pub struct Contrast {
+    level: u8,
+}
+
+//define the Line struct for receiving line commands
+#[derive(Debug, Deserialize)]
+pub struct Line {
+    x0: i32,
+    y0: i32,
+    x1: i32,
+    y1: i32,
+}
+
+//define the Rectangle struct for receiving rectangle commands
+#[derive(Debug, Deserialize)]
+pub struct Rectangle {
+    x_coord: i32,
+    y_coord: i32,
+    width: u32,
+    height: u32,
+    #[serde(default)]
+    filled: bool,
+}
+
+//define the Circle struct for receiving circle commands
+#[derive(Debug, Deserialize)]
+pub struct Circle {
+    x_coord: i32,
+    y_coord: i32,
+    radius: u32,
+    #[serde(default)]
+    filled: bool,
+}
+
+//define the Progress struct for receiving progress bar commands
+#[derive(Debug, Deserialize)]
+pub struct Progress {
+    x_coord: i32,
+    y_coord: i32,
+    width: u32,
+    height: u32,
+    percent: u8,
+}
+
+//define the Chart struct for receiving chart commands
+#[derive(Debug, Deserialize)]
+pub struct Chart {
+    x_coord: i32,
+    y_coord: i32,
+    width: u32,
+    height: u32,
+    values: Vec<f32>,
+    style: ChartStyle,
+    #[serde(default)]
+    max: Option<f32>,
+}
+
+/// Style of the chart drawn by the `chart` method.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChartStyle {
+    /// A bar for each value.
+    Bar,
+    /// A line joining the values.
+    Sparkline,
+}
+
+//define the Snap struct for receiving snapshot commands
+#[derive(Debug, Default, Deserialize)]
+pub struct Snap {
+    #[serde(default)]
+    format: SnapFormat,
+}
+
+/// Format of the frame returned by the `snapshot` method.
+#[derive(Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SnapFormat {
+    /// A packed 1-bpp bitmap (see `Frame::to_bytes`).
+    #[default]
+    Bitmap,
+    /// A PNG image.
+    Png,
+}
+
+/// The frame currently shown on the display, as returned by the `snapshot`
+/// method.
+#[derive(Debug, Serialize)]
+pub struct Snapshot {
+    format: SnapFormat,
+    width: u32,
+    height: u32,
+    on: bool,
+    bytes: Vec<u8>,
+}
+
+/// A display shared between the JSON-RPC methods.
+pub type SharedDisplay = Arc<Mutex<Box<dyn Display>>>;
+
+/// The layers drawn by the `render` method, shared with the thread which
+/// animates them.
+pub type SharedLayers = Arc<Mutex<Layers>>;
+
 fn validate(m: &Msg) -> Result<(), OledError> {
+    // measure the string in characters (as drawn), not bytes
+    let len = m.string.chars().count();
+    ensure!(len <= 21, InvalidString { len });
+
     ensure!(
-        m.string.len() <= 21,
-        InvalidString {
-            len: m.string.len()
+        text::font_size(&m.font_size).is_some(),
+        InvalidFontSize {
+            font: m.font_size.to_string(),
         }
     );
 
@@ -96,13 +221,108 @@ fn validate(m: &Msg) -> Result<(), OledError> {
     Ok(())
 }
 
+// check a coordinate of a shape, which may lie off the display by up to the
+// display's own size in each direction
+fn check_coord(coord: &str, value: i32, size: u32) -> Result<(), OledError> {
+    let size = size as i32;
+    ensure!(
+        value >= -size && value < 2 * size,
+        InvalidCoordinate {
+            coord: coord.to_string(),
+            range: format!("{}-{}", -size, 2 * size - 1),
+            value,
+        }
+    );
+    Ok(())
+}
+
+// check the width, height or radius of a shape, which may be up to twice the
+// width of the display
+fn check_size(coord: &str, value: u32) -> Result<(), OledError> {
+    ensure!(
+        value <= 2 * WIDTH,
+        InvalidCoordinate {
+            coord: coord.to_string(),
+            range: format!("0-{}", 2 * WIDTH),
+            value: value.min(i32::MAX as u32) as i32,
+        }
+    );
+    Ok(())
+}
+
+impl Line {
+    fn validate(&self) -> Result<(), OledError> {
+        check_coord("x0", self.x0, WIDTH)?;
+        check_coord("y0", self.y0, HEIGHT)?;
+        check_coord("x1", self.x1, WIDTH)?;
+        check_coord("y1", self.y1, HEIGHT)
+    }
+}
+
+impl Rectangle {
+    fn validate(&self) -> Result<(), OledError> {
+        check_coord("x", self.x_coord, WIDTH)?;
+        check_coord("y", self.y_coord, HEIGHT)?;
+        check_size("width", self.width)?;
+        check_size("height", self.height)
+    }
+}
+
+impl Circle {
+    fn validate(&self) -> Result<(), OledError> {
+        check_coord("x", self.x_coord, WIDTH)?;
+        check_coord("y", self.y_coord, HEIGHT)?;
+        check_size("radius", self.radius)
+    }
+}
+
+impl Progress {
+    fn validate(&self) -> Result<(), OledError> {
+        check_coord("x", self.x_coord, WIDTH)?;
+        check_coord("y", self.y_coord, HEIGHT)?;
+        check_size("width", self.width)?;
+        check_size("height", self.height)
+    }
+}
+
+impl Chart {
+    fn validate(&self) -> Result<(), OledError> {
+        check_coord("x", self.x_coord, WIDTH)?;
+        check_coord("y", self.y_coord, HEIGHT)?;
+        check_size("width", self.width)?;
+        check_size("height", self.height)
+    }
+}
+
+/// Open the display selected by the `PEACH_OLED_DISPLAY` environment variable:
+/// `ssd1306` (the default) for the OLED connected to `/dev/i2c-1`, or
+/// `framebuffer` for an in-memory display. The framebuffer writes each frame
+/// to the output given by `PEACH_OLED_OUTPUT` (`terminal`, or a `.png` or
+/// `.pbm` file), if set.
+fn open_display() -> Result<Box<dyn Display>, BoxError> {
+    let backend = env::var("PEACH_OLED_DISPLAY").unwrap_or_else(|_| "ssd1306".to_string());
+    match backend.as_str() {
+        "framebuffer" => {
+            info!("Using the framebuffer display.");
+            let output = env::var("PEACH_OLED_OUTPUT")
+                .ok()
+                .map(|output| Output::parse(&output));
+            Ok(Box::new(Framebuffer::new(output)))
+        }
+        _ => {
+            debug!("Creating interface for I2C device.");
+            let i2c = I2cdev::new("/dev/i2c-1").context(I2CError)?;
+            let control = LinuxI2CDevice::new("/dev/i2c-1", SSD1306_ADDRESS).context(I2CError)?;
+            let disp: GraphicsMode<_> = Builder::new().connect_i2c(i2c).into();
+            Ok(Box::new(Ssd1306::new(disp, control)))
+        }
+    }
+}
+
 pub fn run() -> Result<(), BoxError> {
     info!("Starting up.");
 
-    debug!("Creating interface for I2C device.");
-    let i2c = I2cdev::new("/dev/i2c-1").context(I2CError)?;
-
-    let mut disp: GraphicsMode<_> = Builder::new().connect_i2c(i2c).into();
+    let mut disp = open_display()?;
 
     info!("Initializing the display.");
     disp.init().unwrap_or_else(|_| {
@@ -117,11 +337,62 @@ pub fn run() -> Result<(), BoxError> {
     });
 
     let oled = Arc::new(Mutex::new(disp));
-    let oled_clone = Arc::clone(&oled);
+    let layers = SharedLayers::default();
+    animate(Arc::clone(&oled), Arc::clone(&layers));
+    let io = handler(oled, layers);
+
+    let http_server =
+        env::var("PEACH_OLED_SERVER").unwrap_or_else(|_| "127.0.0.1:5112".to_string());
+
+    info!("Starting JSON-RPC server on {}.", http_server);
+    let server = ServerBuilder::new(io)
+        .cors(DomainsValidation::AllowOnly(vec![
+            AccessControlAllowOrigin::Null,
+        ]))
+        .start_http(
+            &http_server
+                .parse()
+                .expect("Invalid HTTP address and port combination"),
+        )
+        .expect("Unable to start RPC server");
+
+    info!("Listening for requests.");
+    server.wait();
+
+    Ok(())
+}
+
+/// Redraw the layers with scrolling text on each tick, in a thread of their
+/// own. Nothing is redrawn while the display is turned off.
+pub fn animate(oled: SharedDisplay, layers: SharedLayers) {
+    thread::spawn(move || loop {
+        thread::sleep(scene::TICK);
+        let mut oled = oled.lock().unwrap();
+        if !oled.is_on() {
+            continue;
+        }
+        let mut layers = layers.lock().unwrap();
+        match layers.tick() {
+            Ok(false) => (),
+            Ok(true) => {
+                layers.composite(&mut **oled);
+                if let Err(e) = oled.flush() {
+                    error!("Failed to flush animated layers: {}", e);
+                }
+            }
+            Err(e) => error!("Failed to redraw animated layers: {}", e),
+        }
+    });
+}
 
+/// Create the JSON-RPC I/O handler, with methods which draw to the given
+/// display and layers.
+pub fn handler(oled: SharedDisplay, layers: SharedLayers) -> IoHandler {
     info!("Creating JSON-RPC I/O handler.");
     let mut io = IoHandler::default();
 
+    let oled_clone = Arc::clone(&oled);
+
     io.add_method("clear", move |_| {
         let mut oled = oled_clone.lock().unwrap();
         info!("Clearing the display.");
@@ -136,6 +407,17 @@ pub fn run() -> Result<(), BoxError> {
 
     let oled_clone = Arc::clone(&oled);
 
+    io.add_method("contrast", move |params: Params| {
+        let c: Result<Contrast, Error> = params.parse();
+        let c: Contrast = c?;
+        let mut oled = oled_clone.lock().unwrap();
+        info!("Setting the display contrast to {}.", c.level);
+        oled.set_contrast(c.level)?;
+        Ok(Value::String("success".into()))
+    });
+
+    let oled_clone = Arc::clone(&oled);
+
     io.add_method("draw", move |params: Params| {
         let g: Result<Graphic, Error> = params.parse();
         let g: Graphic = g?;
@@ -173,7 +455,7 @@ pub fn run() -> Result<(), BoxError> {
         } else {
             info!("Turnin the display off.");
         }
-        oled.display_on(o.on).unwrap_or_else(|_| {
+        oled.power(o.on).unwrap_or_else(|_| {
             error!("Problem turning the display on.");
             process::exit(1);
         });
@@ -182,6 +464,154 @@ pub fn run() -> Result<(), BoxError> {
 
     let oled_clone = Arc::clone(&oled);
 
+    io.add_method("line", move |params: Params| {
+        let l: Result<Line, Error> = params.parse();
+        let l: Line = l?;
+        l.validate()?;
+        let mut oled = oled_clone.lock().unwrap();
+        info!("Drawing a line to the display.");
+        shapes::line(&mut **oled, l.x0, l.y0, l.x1, l.y1, true);
+        Ok(Value::String("success".into()))
+    });
+
+    let oled_clone = Arc::clone(&oled);
+
+    io.add_method("rectangle", move |params: Params| {
+        let r: Result<Rectangle, Error> = params.parse();
+        let r: Rectangle = r?;
+        r.validate()?;
+        let mut oled = oled_clone.lock().unwrap();
+        info!("Drawing a rectangle to the display.");
+        shapes::rectangle(
+            &mut **oled,
+            r.x_coord,
+            r.y_coord,
+            r.width,
+            r.height,
+            r.filled,
+            true,
+        );
+        Ok(Value::String("success".into()))
+    });
+
+    let oled_clone = Arc::clone(&oled);
+
+    io.add_method("circle", move |params: Params| {
+        let c: Result<Circle, Error> = params.parse();
+        let c: Circle = c?;
+        c.validate()?;
+        let mut oled = oled_clone.lock().unwrap();
+        info!("Drawing a circle to the display.");
+        shapes::circle(&mut **oled, c.x_coord, c.y_coord, c.radius, c.filled, true);
+        Ok(Value::String("success".into()))
+    });
+
+    let oled_clone = Arc::clone(&oled);
+
+    io.add_method("progress", move |params: Params| {
+        let p: Result<Progress, Error> = params.parse();
+        let p: Progress = p?;
+        p.validate()?;
+        let mut oled = oled_clone.lock().unwrap();
+        info!("Drawing a progress bar to the display.");
+        shapes::progress_bar(
+            &mut **oled,
+            p.x_coord,
+            p.y_coord,
+            p.width,
+            p.height,
+            p.percent,
+        );
+        Ok(Value::String("success".into()))
+    });
+
+    let oled_clone = Arc::clone(&oled);
+
+    io.add_method("chart", move |params: Params| {
+        let c: Result<Chart, Error> = params.parse();
+        let c: Chart = c?;
+        c.validate()?;
+        let mut oled = oled_clone.lock().unwrap();
+        info!("Drawing a chart to the display.");
+        match c.style {
+            ChartStyle::Bar => shapes::bar_chart(
+                &mut **oled,
+                c.x_coord,
+                c.y_coord,
+                c.width,
+                c.height,
+                &c.values,
+                c.max,
+            ),
+            ChartStyle::Sparkline => shapes::sparkline(
+                &mut **oled,
+                c.x_coord,
+                c.y_coord,
+                c.width,
+                c.height,
+                &c.values,
+            ),
+        }
+        Ok(Value::String("success".into()))
+    });
+
+    let oled_clone = Arc::clone(&oled);
+
+    io.add_method("render", move |params: Params| {
+        let s: Result<Scene, Error> = params.parse();
+        let s: Scene = s?;
+        // hold the display for the whole render so that other requests
+        // cannot interleave with it
+        let mut oled = oled_clone.lock().unwrap();
+        let mut layers = layers.lock().unwrap();
+        info!("Rendering the '{}' layer.", s.layer);
+        layers.render(s)?;
+        layers.composite(&mut **oled);
+        debug!("Flushing the display.");
+        oled.flush()?;
+        Ok(Value::String("success".into()))
+    });
+
+    let oled_clone = Arc::clone(&oled);
+
+    io.add_method("snapshot", move |params: Params| {
+        // the format is optional and defaults to a packed bitmap
+        let s: Snap = match params {
+            Params::None => Snap::default(),
+            params => params.parse()?,
+        };
+        let oled = oled_clone.lock().unwrap();
+        info!("Taking a snapshot of the display.");
+        let frame = oled.frame();
+        let bytes = match s.format {
+            SnapFormat::Bitmap => frame.to_bytes(),
+            SnapFormat::Png => frame.to_png().map_err(|err| OledError::DisplayError {
+                msg: format!("Failed to encode the display as PNG: {}", err),
+            })?,
+        };
+        let snapshot = Snapshot {
+            format: s.format,
+            width: WIDTH,
+            height: HEIGHT,
+            on: oled.is_on(),
+            bytes,
+        };
+        Ok(serde_json::to_value(snapshot).unwrap())
+    });
+
+    let oled_clone = Arc::clone(&oled);
+
+    io.add_method("text", move |params: Params| {
+        let t: Result<TextBox, Error> = params.parse();
+        let t: TextBox = t?;
+        let mut oled = oled_clone.lock().unwrap();
+        info!("Writing a text box to the display.");
+        t.draw(&mut **oled, 0)?;
+        Ok(Value::String("success".into()))
+    });
+
+    let oled_clone = Arc::clone(&oled);
+
     io.add_method("write", move |params: Params| {
         info!("Received a 'write' request.");
         let m: Result<Msg, Error> = params.parse();
@@ -191,54 +621,82 @@ pub fn run() -> Result<(), BoxError> {
         let mut oled = oled_clone.lock().unwrap();
 
         info!("Writing to the display.");
-        if m.font_size == "6x8" {
-            oled.draw(
-                Font6x8::render_str(&m.string)
-                    .translate(Coord::new(m.x_coord, m.y_coord))
-                    .into_iter(),
-            );
-        } else if m.font_size == "6x12" {
-            oled.draw(
-                Font6x12::render_str(&m.string)
-                    .translate(Coord::new(m.x_coord, m.y_coord))
-                    .into_iter(),
-            );
-        } else if m.font_size == "8x16" {
-            oled.draw(
-                Font8x16::render_str(&m.string)
-                    .translate(Coord::new(m.x_coord, m.y_coord))
-                    .into_iter(),
-            );
-        } else if m.font_size == "12x16" {
-            oled.draw(
-                Font12x16::render_str(&m.string)
-                    .translate(Coord::new(m.x_coord, m.y_coord))
-                    .into_iter(),
-            );
-        }
+        write_text(&mut **oled, &m, false);
 
         Ok(Value::String("success".into()))
     });
 
-    let http_server =
-        env::var("PEACH_OLED_SERVER").unwrap_or_else(|_| "127.0.0.1:5112".to_string());
+    let oled_clone = Arc::clone(&oled);
 
-    info!("Starting JSON-RPC server on {}.", http_server);
-    let server = ServerBuilder::new(io)
-        .cors(DomainsValidation::AllowOnly(vec![
-            AccessControlAllowOrigin::Null,
-        ]))
-        .start_http(
-            &http_server
-                .parse()
-                .expect("Invalid HTTP address and port combination"),
-        )
-        .expect("Unable to start RPC server");
+    io.add_method("write_inverted", move |params: Params| {
+        info!("Received a 'write_inverted' request.");
+        let m: Result<Msg, Error> = params.parse();
+        let m: Msg = m?;
+        validate(&m)?;
 
-    info!("Listening for requests.");
-    server.wait();
+        let mut oled = oled_clone.lock().unwrap();
 
-    Ok(())
+        info!("Writing inverted text to the display.");
+        write_text(&mut **oled, &m, true);
+
+        Ok(Value::String("success".into()))
+    });
+
+    io
+}
+
+// render a message with the requested font; inverted text is drawn as dark
+// glyphs on a lit background
+fn write_text<C>(oled: &mut C, m: &Msg, inverted: bool)
+where
+    C: Canvas + Drawing<PixelColorU8> + ?Sized,
+{
+    let string = text::substitute(&m.string);
+    if inverted {
+        let (width, height) = match text::font_size(&m.font_size) {
+            Some(size) => size,
+            None => return,
+        };
+        let len = string.chars().count() as u32;
+        shapes::rectangle(oled, m.x_coord, m.y_coord, width * len, height, true, true);
+    }
+    let style = |Pixel(coord, color): Pixel<PixelColorU8>| {
+        if inverted {
+            Pixel(coord, PixelColorU8(u8::from(color.into_inner() == 0)))
+        } else {
+            Pixel(coord, color)
+        }
+    };
+
+    if m.font_size == "6x8" {
+        oled.draw(
+            Font6x8::render_str(&string)
+                .translate(Coord::new(m.x_coord, m.y_coord))
+                .into_iter()
+                .map(style),
+        );
+    } else if m.font_size == "6x12" {
+        oled.draw(
+            Font6x12::render_str(&string)
+                .translate(Coord::new(m.x_coord, m.y_coord))
+                .into_iter()
+                .map(style),
+        );
+    } else if m.font_size == "8x16" {
+        oled.draw(
+            Font8x16::render_str(&string)
+                .translate(Coord::new(m.x_coord, m.y_coord))
+                .into_iter()
+                .map(style),
+        );
+    } else if m.font_size == "12x16" {
+        oled.draw(
+            Font12x16::render_str(&string)
+                .translate(Coord::new(m.x_coord, m.y_coord))
+                .into_iter()
+                .map(style),
+        );
+    }
 }
 
 #[cfg(test)]
@@ -327,6 +785,28 @@ mod tests {
         );
     }
 
+    // test to ensure correct DisplayError error response
+    #[test]
+    fn rpc_display_error() {
+        let rpc = {
+            let mut io = IoHandler::new();
+            io.add_method("rpc_display_error", |_| {
+                Err(Error::from(OledError::DisplayError {
+                    msg: "Failed to encode PNG".to_string(),
+                }))
+            });
+            test::Rpc::from(io)
+        };
+
+        assert_eq!(
+            rpc.request("rpc_display_error", &()),
+            r#"{
+  "code": -32004,
+  "message": "Failed to encode PNG"
+}"#
+        );
+    }
+
     // test to ensure correct InvalidCoordinate error response
     #[test]
     fn rpc_invalid_coord() {
@@ -421,6 +901,95 @@ mod tests {
         );
     }
 
+    // test that a snapshot returns the frame shown on the display
+    #[test]
+    fn rpc_snapshot() {
+        let mut disp: Box<dyn Display> = Box::new(Framebuffer::new(None));
+        disp.init().unwrap();
+        disp.set_pixel(0, 0, true);
+        disp.flush().unwrap();
+        let rpc = test::Rpc::from(handler(Arc::new(Mutex::new(disp)), SharedLayers::default()));
+
+        let snapshot: Value = serde_json::from_str(&rpc.request("snapshot", &())).unwrap();
+        assert_eq!(snapshot["format"], "bitmap");
+        assert_eq!(snapshot["width"], 128);
+        assert_eq!(snapshot["height"], 64);
+        assert_eq!(snapshot["on"], true);
+        assert_eq!(snapshot["bytes"].as_array().unwrap().len(), 1024);
+        assert_eq!(snapshot["bytes"][0], 0b1000_0000);
+
+        let snapshot: Value = serde_json::from_str(&rpc.request("snapshot", &["png"])).unwrap();
+        assert_eq!(snapshot["format"], "png");
+        // png signature
+        assert_eq!(snapshot["bytes"][1], b'P');
+    }
+
+    // test that a scene is drawn and flushed in a single request
+    #[test]
+    fn rpc_render() {
+        let oled: SharedDisplay = Arc::new(Mutex::new(Box::new(Framebuffer::new(None))));
+        let rpc = test::Rpc::from(handler(Arc::clone(&oled), SharedLayers::default()));
+
+        let scene = serde_json::json!({
+            "layer": "menu",
+            "ops": [
+                { "op": "write", "x_coord": 0, "y_coord": 0, "string": "PeachCloud", "font_size": "6x8" },
+                { "op": "rectangle", "x_coord": 0, "y_coord": 60, "width": 128, "height": 4, "filled": true }
+            ]
+        });
+        assert_eq!(rpc.request("render", &scene), r#""success""#);
+
+        let oled = oled.lock().unwrap();
+        // the text is drawn to the first 8 rows (96 bytes)
+        assert!(oled.frame().to_bytes()[..96].iter().any(|byte| *byte != 0));
+        assert!(oled.frame().get(127, 63));
+    }
+
+    // test that inverted text is drawn on a lit background
+    #[test]
+    fn rpc_write_inverted() {
+        let oled: SharedDisplay = Arc::new(Mutex::new(Box::new(Framebuffer::new(None))));
+        let rpc = test::Rpc::from(handler(Arc::clone(&oled), SharedLayers::default()));
+
+        let msg = serde_json::json!({
+            "x_coord": 0,
+            "y_coord": 0,
+            "string": "PeachCloud",
+            "font_size": "6x8"
+        });
+        assert_eq!(rpc.request("write_inverted", &msg), r#""success""#);
+        assert_eq!(rpc.request("flush", &()), r#""success""#);
+
+        let oled = oled.lock().unwrap();
+        // the spacing between glyphs is lit, the area beyond the text is not
+        assert!(oled.frame().get(5, 0));
+        assert!(oled.frame().get(59, 7));
+        assert!(!oled.frame().get(60, 0));
+    }
+
+    // test that a text box wraps long text and measures it in characters
+    #[test]
+    fn rpc_text() {
+        let oled: SharedDisplay = Arc::new(Mutex::new(Box::new(Framebuffer::new(None))));
+        let rpc = test::Rpc::from(handler(Arc::clone(&oled), SharedLayers::default()));
+
+        let text = serde_json::json!({
+            "x_coord": 0,
+            "y_coord": 0,
+            "width": 60,
+            "height": 16,
+            "string": "Connected to Café Wi-Fi",
+            "font_size": "6x8"
+        });
+        assert_eq!(rpc.request("text", &text), r#""success""#);
+        assert_eq!(rpc.request("flush", &()), r#""success""#);
+
+        let oled = oled.lock().unwrap();
+        // the text wraps onto a second line, but not beyond the box
+        assert!((0..60).any(|x| oled.frame().get(x, 10)));
+        assert!(!(0..128).any(|x| oled.frame().get(x, 17)));
+    }
+
     // test to ensure correct parse error response
     #[test]
     fn rpc_parse_error() {
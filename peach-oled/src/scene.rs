@@ -0,0 +1,352 @@
+//! Scenes and layers.
+//!
+//! A scene is a list of draw operations which replaces the contents of a
+//! named layer. Layers are kept off-screen and composited onto the display
+//! buffer in order of their `z` index (lowest first) each time a scene is
+//! rendered, followed by a single flush. This allows a client to redraw a
+//! whole screen in one request, without flicker, while other clients draw to
+//! layers of their own (for example, a notification overlay above the menu).
+//!
+//! Layers keep the operations of their scene, so that layers containing
+//! scrolling text can be redrawn on each tick of the display.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use embedded_graphics::coord::Coord;
+use embedded_graphics::drawable::Pixel;
+use embedded_graphics::image::Image1BPP;
+use embedded_graphics::pixelcolor::PixelColorU8;
+use embedded_graphics::prelude::*;
+use embedded_graphics::unsignedcoord::UnsignedCoord;
+use embedded_graphics::Drawing;
+use serde::Deserialize;
+
+use crate::display::{Canvas, Display, Frame, HEIGHT, WIDTH};
+use crate::error::OledError;
+use crate::text::TextBox;
+use crate::{shapes, validate, write_text};
+use crate::{Chart, ChartStyle, Circle, Graphic, Line, Msg, Progress, Rectangle};
+
+/// A draw operation, tagged by the name of the equivalent JSON-RPC method.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum Op {
+    Chart(Chart),
+    Circle(Circle),
+    Draw(Graphic),
+    Line(Line),
+    Progress(Progress),
+    Rectangle(Rectangle),
+    Text(TextBox),
+    Write(Msg),
+    WriteInverted(Msg),
+}
+
+impl Op {
+    /// Whether the operation draws differently on each tick.
+    pub fn animated(&self) -> bool {
+        match self {
+            Op::Text(t) => t.scrolls(),
+            _ => false,
+        }
+    }
+
+    /// Apply the operation to a canvas, as drawn on the given tick.
+    pub fn apply<C>(&self, canvas: &mut C, tick: usize) -> Result<(), OledError>
+    where
+        C: Canvas + Drawing<PixelColorU8> + ?Sized,
+    {
+        match self {
+            Op::Chart(c) => {
+                c.validate()?;
+                match c.style {
+                    ChartStyle::Bar => shapes::bar_chart(
+                        canvas, c.x_coord, c.y_coord, c.width, c.height, &c.values, c.max,
+                    ),
+                    ChartStyle::Sparkline => shapes::sparkline(
+                        canvas, c.x_coord, c.y_coord, c.width, c.height, &c.values,
+                    ),
+                }
+            }
+            Op::Circle(c) => {
+                c.validate()?;
+                shapes::circle(canvas, c.x_coord, c.y_coord, c.radius, c.filled, true)
+            }
+            Op::Draw(g) => canvas.draw(
+                Image1BPP::new(&g.bytes, g.width, g.height)
+                    .translate(Coord::new(g.x_coord, g.y_coord))
+                    .into_iter(),
+            ),
+            Op::Line(l) => {
+                l.validate()?;
+                shapes::line(canvas, l.x0, l.y0, l.x1, l.y1, true)
+            }
+            Op::Progress(p) => {
+                p.validate()?;
+                shapes::progress_bar(canvas, p.x_coord, p.y_coord, p.width, p.height, p.percent)
+            }
+            Op::Rectangle(r) => {
+                r.validate()?;
+                shapes::rectangle(
+                    canvas, r.x_coord, r.y_coord, r.width, r.height, r.filled, true,
+                )
+            }
+            Op::Text(t) => t.draw(canvas, tick)?,
+            Op::Write(m) => {
+                validate(m)?;
+                write_text(canvas, m, false);
+            }
+            Op::WriteInverted(m) => {
+                validate(m)?;
+                write_text(canvas, m, true);
+            }
+        }
+        Ok(())
+    }
+}
+
+fn default_layer() -> String {
+    "base".to_string()
+}
+
+//define the Scene struct for receiving render commands
+#[derive(Debug, Deserialize)]
+pub struct Scene {
+    #[serde(default = "default_layer")]
+    pub layer: String,
+    #[serde(default)]
+    pub z: i32,
+    pub ops: Vec<Op>,
+}
+
+/// Interval between ticks, on which animated layers are redrawn.
+pub const TICK: Duration = Duration::from_millis(250);
+
+/// The pixels drawn to a layer. Only the pixels drawn by its scene (lit or
+/// dark) are composited; the rest of the layer is transparent.
+#[derive(Debug, Default)]
+struct Sheet {
+    pixels: Frame,
+    mask: Frame,
+}
+
+impl Canvas for Sheet {
+    fn set_pixel(&mut self, x: u32, y: u32, on: bool) {
+        self.pixels.set(x, y, on);
+        self.mask.set(x, y, true);
+    }
+}
+
+impl Drawing<PixelColorU8> for Sheet {
+    fn draw<T>(&mut self, item_pixels: T)
+    where
+        T: Iterator<Item = Pixel<PixelColorU8>>,
+    {
+        for Pixel(UnsignedCoord(x, y), color) in item_pixels {
+            self.set_pixel(x, y, color.into_inner() != 0);
+        }
+    }
+}
+
+/// An off-screen layer, holding the operations of its scene.
+#[derive(Debug)]
+struct Layer {
+    z: i32,
+    ops: Vec<Op>,
+    tick: usize,
+    sheet: Sheet,
+}
+
+impl Layer {
+    // redraw the layer as it appears on its current tick
+    fn draw(&mut self) -> Result<(), OledError> {
+        let mut sheet = Sheet::default();
+        for op in &self.ops {
+            op.apply(&mut sheet, self.tick)?;
+        }
+        self.sheet = sheet;
+        Ok(())
+    }
+
+    fn animated(&self) -> bool {
+        self.ops.iter().any(Op::animated)
+    }
+}
+
+/// The layers drawn by scenes, by name.
+#[derive(Debug, Default)]
+pub struct Layers {
+    layers: HashMap<String, Layer>,
+}
+
+impl Layers {
+    /// Replace the contents of the scene's layer. A scene without operations
+    /// removes the layer. If any operation is invalid the layer is left as it
+    /// was.
+    pub fn render(&mut self, scene: Scene) -> Result<(), OledError> {
+        if scene.ops.is_empty() {
+            self.layers.remove(&scene.layer);
+            return Ok(());
+        }
+        let mut layer = Layer {
+            z: scene.z,
+            ops: scene.ops,
+            tick: 0,
+            sheet: Sheet::default(),
+        };
+        layer.draw()?;
+        self.layers.insert(scene.layer, layer);
+        Ok(())
+    }
+
+    /// Advance animated layers by a tick and redraw them. Returns `true` if
+    /// any layer was redrawn, in which case the layers should be composited
+    /// again.
+    pub fn tick(&mut self) -> Result<bool, OledError> {
+        let mut changed = false;
+        for layer in self.layers.values_mut().filter(|layer| layer.animated()) {
+            layer.tick = layer.tick.wrapping_add(1);
+            layer.draw()?;
+            changed = true;
+        }
+        Ok(changed)
+    }
+
+    /// Clear the display buffer and draw the layers onto it, lowest `z` index
+    /// first (layers with the same index are drawn in order of name).
+    pub fn composite(&self, disp: &mut dyn Display) {
+        let mut layers: Vec<(&String, &Layer)> = self.layers.iter().collect();
+        layers.sort_by_key(|(name, layer)| (layer.z, *name));
+
+        disp.clear();
+        for (_, layer) in layers {
+            for y in 0..HEIGHT {
+                for x in 0..WIDTH {
+                    if layer.sheet.mask.get(x, y) {
+                        disp.set_pixel(x, y, layer.sheet.pixels.get(x, y));
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::display::Framebuffer;
+
+    // parse a scene from json, as received by the `render` method
+    fn scene(json: &str) -> Scene {
+        serde_json::from_str(json).unwrap()
+    }
+
+    // render the scenes and return the flushed frame
+    fn render(scenes: &[&str]) -> Frame {
+        let mut layers = Layers::default();
+        for json in scenes {
+            layers.render(scene(json)).unwrap();
+        }
+        let mut disp = Framebuffer::new(None);
+        layers.composite(&mut disp);
+        disp.flush().unwrap();
+        disp.frame().clone()
+    }
+
+    #[test]
+    fn scene_replaces_layer() {
+        let frame = render(&[
+            r#"{"layer": "menu", "ops": [{"op": "line", "x0": 0, "y0": 0, "x1": 9, "y1": 0}]}"#,
+            r#"{"layer": "menu", "ops": [{"op": "line", "x0": 0, "y0": 5, "x1": 9, "y1": 5}]}"#,
+        ]);
+        assert!(!frame.get(0, 0));
+        assert!(frame.get(0, 5));
+    }
+
+    #[test]
+    fn layers_are_composited_in_order() {
+        let menu = r#"{"layer": "menu", "ops": [
+            {"op": "rectangle", "x_coord": 0, "y_coord": 0, "width": 10, "height": 10, "filled": true}
+        ]}"#;
+        // an overlay which darkens part of the menu
+        let overlay = r#"{"layer": "overlay", "z": 1, "ops": [
+            {"op": "progress", "x_coord": 0, "y_coord": 0, "width": 20, "height": 6, "percent": 0}
+        ]}"#;
+
+        let frame = render(&[overlay, menu]);
+        // inside the progress bar is dark, the menu shows below it
+        assert!(!frame.get(2, 2));
+        assert!(frame.get(2, 8));
+
+        // removing the overlay reveals the menu
+        let removed = r#"{"layer": "overlay", "ops": []}"#;
+        let frame = render(&[overlay, menu, removed]);
+        assert!(frame.get(2, 2));
+    }
+
+    #[test]
+    fn invalid_scene_keeps_layer() {
+        let mut layers = Layers::default();
+        layers
+            .render(scene(
+                r#"{"ops": [{"op": "circle", "x_coord": 5, "y_coord": 5, "radius": 2}]}"#,
+            ))
+            .unwrap();
+        let invalid = scene(
+            r#"{"ops": [{"op": "write", "x_coord": -1, "y_coord": 0, "string": "x", "font_size": "6x8"}]}"#,
+        );
+        assert!(layers.render(invalid).is_err());
+        assert!(layers.layers["base"].sheet.mask.get(7, 5));
+    }
+
+    #[test]
+    fn out_of_range_shapes_are_rejected() {
+        let mut layers = Layers::default();
+        for op in &[
+            r#"{"op": "line", "x0": 0, "y0": 0, "x1": 2000000000, "y1": 0}"#,
+            r#"{"op": "circle", "x_coord": 64, "y_coord": 32, "radius": 1000000000}"#,
+            r#"{"op": "rectangle", "x_coord": 0, "y_coord": -65, "width": 10, "height": 10}"#,
+            r#"{"op": "progress", "x_coord": 0, "y_coord": 0, "width": 4294967295, "height": 8, "percent": 50}"#,
+        ] {
+            let invalid = scene(&format!(r#"{{"ops": [{}]}}"#, op));
+            match layers.render(invalid) {
+                Err(OledError::InvalidCoordinate { .. }) => (),
+                result => panic!("{} was not rejected: {:?}", op, result),
+            }
+        }
+        assert!(layers.layers.is_empty());
+    }
+
+    #[test]
+    fn scrolling_text_is_animated() {
+        let mut layers = Layers::default();
+        layers
+            .render(scene(
+                r#"{"ops": [{"op": "text", "x_coord": 0, "y_coord": 0, "width": 12, "height": 8,
+                    "string": "Il", "font_size": "6x8"}]}"#,
+            ))
+            .unwrap();
+        assert!(!layers.tick().unwrap());
+
+        // scrolling text which fits in its box does not move
+        layers
+            .render(scene(
+                r#"{"ops": [{"op": "text", "x_coord": 0, "y_coord": 0, "width": 12, "height": 8,
+                    "string": "Il", "font_size": "6x8", "overflow": "scroll"}]}"#,
+            ))
+            .unwrap();
+        assert!(!layers.tick().unwrap());
+
+        layers
+            .render(scene(
+                r#"{"ops": [{"op": "text", "x_coord": 0, "y_coord": 0, "width": 6, "height": 8,
+                    "string": "Il", "font_size": "6x8", "overflow": "scroll"}]}"#,
+            ))
+            .unwrap();
+        let before = layers.layers["base"].sheet.pixels.clone();
+        assert!(layers.tick().unwrap());
+        assert_ne!(layers.layers["base"].sheet.pixels, before);
+    }
+}
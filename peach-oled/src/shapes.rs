@@ -0,0 +1,392 @@
+//! Drawing primitives: lines, rectangles, circles, progress bars and charts.
+//!
+//! Shapes are drawn to a canvas (the display buffer or a layer) pixel by
+//! pixel. Coordinates may lie outside the display, in which case the shape is
+//! clipped.
+
+use crate::display::{Canvas, HEIGHT, WIDTH};
+
+// shapes are drawn with 64-bit coordinates, so that no coordinate or size
+// received as an `i32` or `u32` can overflow
+
+// light a pixel, ignoring coordinates outside the display
+fn plot<C: Canvas + ?Sized>(disp: &mut C, x: i64, y: i64, on: bool) {
+    if x >= 0 && y >= 0 && x < i64::from(WIDTH) && y < i64::from(HEIGHT) {
+        disp.set_pixel(x as u32, y as u32, on);
+    }
+}
+
+// interpolate the value at `t` of a line from (t0, s0) to (t1, s1), rounding
+// to the nearest pixel
+fn interpolate(t0: i64, s0: i64, t1: i64, s1: i64, t: i64) -> i64 {
+    if t0 == t1 {
+        return s0;
+    }
+    let num = i128::from(t - t0) * i128::from(s1 - s0);
+    let den = i128::from(t1 - t0);
+    let (num, den) = if den < 0 { (-num, -den) } else { (num, den) };
+    s0 + (2 * num + den).div_euclid(2 * den) as i64
+}
+
+// draw a line a pixel at a time along its major axis, only over the part of
+// that axis which lies on the display
+fn draw_line<C: Canvas + ?Sized>(disp: &mut C, x0: i64, y0: i64, x1: i64, y1: i64, on: bool) {
+    if (x1 - x0).abs() >= (y1 - y0).abs() {
+        let from = x0.min(x1).max(0);
+        let to = x0.max(x1).min(i64::from(WIDTH) - 1);
+        for x in from..=to {
+            plot(disp, x, interpolate(x0, y0, x1, y1, x), on);
+        }
+    } else {
+        let from = y0.min(y1).max(0);
+        let to = y0.max(y1).min(i64::from(HEIGHT) - 1);
+        for y in from..=to {
+            plot(disp, interpolate(y0, x0, y1, x1, y), y, on);
+        }
+    }
+}
+
+/// Draw a one pixel wide line between two points.
+pub fn line<C: Canvas + ?Sized>(disp: &mut C, x0: i32, y0: i32, x1: i32, y1: i32, on: bool) {
+    draw_line(
+        disp,
+        i64::from(x0),
+        i64::from(y0),
+        i64::from(x1),
+        i64::from(y1),
+        on,
+    );
+}
+
+fn draw_rectangle<C: Canvas + ?Sized>(
+    disp: &mut C,
+    x: i64,
+    y: i64,
+    width: i64,
+    height: i64,
+    filled: bool,
+    on: bool,
+) {
+    if width <= 0 || height <= 0 {
+        return;
+    }
+    let (x1, y1) = (x + width - 1, y + height - 1);
+    if filled {
+        for row in y.max(0)..=y1.min(i64::from(HEIGHT) - 1) {
+            draw_line(disp, x, row, x1, row, on);
+        }
+    } else {
+        draw_line(disp, x, y, x1, y, on);
+        draw_line(disp, x, y1, x1, y1, on);
+        draw_line(disp, x, y, x, y1, on);
+        draw_line(disp, x1, y, x1, y1, on);
+    }
+}
+
+/// Draw a rectangle with its top-left corner at the given coordinates, either
+/// filled or as a one pixel outline.
+pub fn rectangle<C: Canvas + ?Sized>(
+    disp: &mut C,
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+    filled: bool,
+    on: bool,
+) {
+    draw_rectangle(
+        disp,
+        i64::from(x),
+        i64::from(y),
+        i64::from(width),
+        i64::from(height),
+        filled,
+        on,
+    );
+}
+
+// the largest integer whose square is at most `n`
+fn isqrt(n: i128) -> i64 {
+    let mut root = (n as f64).sqrt() as i128;
+    while root * root > n {
+        root -= 1;
+    }
+    while (root + 1) * (root + 1) <= n {
+        root += 1;
+    }
+    root as i64
+}
+
+/// Draw a circle centred on the given coordinates, either filled or as a one
+/// pixel outline.
+///
+/// The circle covers the pixels within half a pixel of its radius. It is
+/// drawn a row at a time, only over the rows which lie on the display.
+pub fn circle<C: Canvas + ?Sized>(
+    disp: &mut C,
+    cx: i32,
+    cy: i32,
+    radius: u32,
+    filled: bool,
+    on: bool,
+) {
+    let (cx, cy, r) = (i64::from(cx), i64::from(cy), i64::from(radius));
+    // the half-width of the circle on the row `dy` from its centre (-1 if it
+    // does not reach the row)
+    let half_width = |dy: i64| {
+        let n = i128::from(r) * i128::from(r + 1) - i128::from(dy) * i128::from(dy);
+        if n < 0 {
+            -1
+        } else {
+            isqrt(n)
+        }
+    };
+    for y in (cy - r).max(0)..=(cy + r).min(i64::from(HEIGHT) - 1) {
+        let dy = y - cy;
+        let outer = half_width(dy);
+        if filled {
+            draw_line(disp, cx - outer, y, cx + outer, y, on);
+        } else {
+            // the outline is the pixels of the circle which have a neighbour
+            // outside of it
+            let inner = (half_width(dy - 1).min(half_width(dy + 1)) + 1).min(outer);
+            draw_line(disp, cx - outer, y, cx - inner, y, on);
+            draw_line(disp, cx + inner, y, cx + outer, y, on);
+        }
+    }
+}
+
+/// Draw a progress bar: an outlined rectangle, filled from the left in
+/// proportion to `percent` (clamped to 0-100).
+pub fn progress_bar<C: Canvas + ?Sized>(
+    disp: &mut C,
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+    percent: u8,
+) {
+    let (x, y, width, height) = (
+        i64::from(x),
+        i64::from(y),
+        i64::from(width),
+        i64::from(height),
+    );
+    // clear the bar first so that it can be redrawn in place
+    draw_rectangle(disp, x, y, width, height, true, false);
+    draw_rectangle(disp, x, y, width, height, false, true);
+    if width > 4 && height > 4 {
+        let inner = width - 4;
+        let filled = inner * i64::from(percent.min(100)) / 100;
+        draw_rectangle(disp, x + 2, y + 2, filled, height - 4, true, true);
+    }
+}
+
+/// Draw a bar chart of the given values within a box, scaled so that the
+/// largest value (or `max`, if given) fills the height of the box. Bars are
+/// separated by a one pixel gap.
+pub fn bar_chart<C: Canvas + ?Sized>(
+    disp: &mut C,
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+    values: &[f32],
+    max: Option<f32>,
+) {
+    if values.is_empty() {
+        return;
+    }
+    let max = max.unwrap_or_else(|| values.iter().cloned().fold(0.0, f32::max));
+    let bar_width = (i64::from(width) / values.len() as i64).max(1);
+    for (i, value) in values.iter().enumerate() {
+        let bar_x = i64::from(x) + i as i64 * bar_width;
+        let bar_height = i64::from(scale(*value, 0.0, max, height));
+        // leave a gap between bars, unless they are only a pixel wide
+        let gap = if bar_width > 1 { 1 } else { 0 };
+        draw_rectangle(
+            disp,
+            bar_x,
+            i64::from(y) + i64::from(height) - bar_height,
+            bar_width - gap,
+            bar_height,
+            true,
+            true,
+        );
+    }
+}
+
+/// Draw a sparkline of the given values within a box: a line joining the
+/// values, scaled so that the smallest is at the bottom of the box and the
+/// largest at the top.
+pub fn sparkline<C: Canvas + ?Sized>(
+    disp: &mut C,
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+    values: &[f32],
+) {
+    if values.is_empty() || width == 0 || height == 0 {
+        return;
+    }
+    let min = values.iter().cloned().fold(f32::INFINITY, f32::min);
+    let max = values.iter().cloned().fold(f32::NEG_INFINITY, f32::max);
+    let (x, y) = (i64::from(x), i64::from(y));
+    let points: Vec<(i64, i64)> = values
+        .iter()
+        .enumerate()
+        .map(|(i, value)| {
+            let px = if values.len() > 1 {
+                x + i as i64 * (i64::from(width) - 1) / (values.len() as i64 - 1)
+            } else {
+                x
+            };
+            // a value at the top of the scale fills the box height
+            let py = y + i64::from(height) - 1 - i64::from(scale(*value, min, max, height - 1));
+            (px, py)
+        })
+        .collect();
+    if points.len() == 1 {
+        plot(disp, points[0].0, points[0].1, true);
+    }
+    for pair in points.windows(2) {
+        draw_line(disp, pair[0].0, pair[0].1, pair[1].0, pair[1].1, true);
+    }
+}
+
+// scale a value in the range min-max to the range 0-size
+fn scale(value: f32, min: f32, max: f32, size: u32) -> u32 {
+    if max <= min {
+        return 0;
+    }
+    let ratio = ((value - min) / (max - min)).clamp(0.0, 1.0);
+    (ratio * size as f32).round() as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::display::{Display, Frame, Framebuffer};
+
+    // draw with the given function and return the flushed frame
+    fn render<F: FnOnce(&mut dyn Display)>(draw: F) -> Frame {
+        let mut disp = Framebuffer::new(None);
+        draw(&mut disp);
+        disp.flush().unwrap();
+        disp.frame().clone()
+    }
+
+    // count the lit pixels of a frame
+    fn lit(frame: &Frame) -> usize {
+        (0..64)
+            .flat_map(|y| (0..128).map(move |x| (x, y)))
+            .filter(|(x, y)| frame.get(*x, *y))
+            .count()
+    }
+
+    #[test]
+    fn line_endpoints() {
+        let frame = render(|disp| line(disp, 0, 0, 9, 3, true));
+        assert!(frame.get(0, 0));
+        assert!(frame.get(9, 3));
+        assert_eq!(lit(&frame), 10);
+    }
+
+    #[test]
+    fn rectangle_outline_and_fill() {
+        let outline = render(|disp| rectangle(disp, 1, 1, 10, 5, false, true));
+        assert!(outline.get(1, 1));
+        assert!(outline.get(10, 5));
+        assert!(!outline.get(5, 3));
+        assert_eq!(lit(&outline), 2 * 10 + 2 * 3);
+
+        let filled = render(|disp| rectangle(disp, 1, 1, 10, 5, true, true));
+        assert_eq!(lit(&filled), 50);
+    }
+
+    #[test]
+    fn shapes_are_clipped() {
+        let frame = render(|disp| {
+            rectangle(disp, -5, -5, 10, 10, true, true);
+            circle(disp, 127, 63, 4, true, true);
+        });
+        assert!(frame.get(0, 0));
+        assert!(frame.get(4, 4));
+        assert!(!frame.get(5, 5));
+        assert!(frame.get(127, 63));
+    }
+
+    #[test]
+    fn extreme_coordinates_are_clipped() {
+        let frame = render(|disp| {
+            line(disp, 0, 10, 2_000_000_000, 10, true);
+            line(disp, i32::MIN, i32::MIN, i32::MAX, i32::MAX, true);
+            rectangle(disp, 0, 20, u32::MAX, u32::MAX, false, true);
+            circle(disp, 64, 1_000_000_040, 1_000_000_000, false, true);
+            progress_bar(disp, i32::MAX, i32::MAX, u32::MAX, u32::MAX, 100);
+        });
+        // the lines run off the display
+        assert!(frame.get(127, 10));
+        assert!(frame.get(0, 0));
+        assert!(frame.get(63, 63));
+        // the top and left edges of the rectangle
+        assert!(frame.get(127, 20));
+        assert!(frame.get(0, 63));
+        assert!(!frame.get(1, 21));
+        // the top of the circle
+        assert!(frame.get(64, 40));
+        assert!(!frame.get(64, 39));
+        assert!(!frame.get(64, 41));
+
+        let outside = render(|disp| {
+            rectangle(disp, i32::MIN, i32::MIN, 1 << 31, 1 << 31, true, true);
+            circle(disp, i32::MAX, i32::MAX, 1_000_000_000, true, true);
+        });
+        assert_eq!(lit(&outside), 0);
+
+        let covered =
+            render(|disp| rectangle(disp, i32::MIN, i32::MIN, u32::MAX, u32::MAX, true, true));
+        assert_eq!(lit(&covered), 128 * 64);
+        let covered = render(|disp| circle(disp, i32::MAX, i32::MAX, u32::MAX, true, true));
+        assert_eq!(lit(&covered), 128 * 64);
+        let covered = render(|disp| circle(disp, 64, 32, 1_000_000_000, true, true));
+        assert_eq!(lit(&covered), 128 * 64);
+    }
+
+    #[test]
+    fn circle_outline() {
+        let frame = render(|disp| circle(disp, 20, 20, 5, false, true));
+        assert!(frame.get(25, 20));
+        assert!(frame.get(15, 20));
+        assert!(frame.get(20, 15));
+        assert!(frame.get(20, 25));
+        assert!(!frame.get(20, 20));
+    }
+
+    #[test]
+    fn progress_bar_fill() {
+        let frame = render(|disp| progress_bar(disp, 0, 0, 104, 8, 50));
+        // 100 inner pixels wide, half of which are filled
+        assert!(frame.get(2, 2));
+        assert!(frame.get(51, 2));
+        assert!(!frame.get(52, 2));
+    }
+
+    #[test]
+    fn bar_chart_scaling() {
+        let frame = render(|disp| bar_chart(disp, 0, 0, 20, 10, &[5.0, 10.0], None));
+        // the largest value fills the height of the box
+        assert!(frame.get(10, 0));
+        assert!(!frame.get(0, 4));
+        assert!(frame.get(0, 5));
+    }
+
+    #[test]
+    fn sparkline_points() {
+        let frame = render(|disp| sparkline(disp, 0, 0, 11, 10, &[0.0, 1.0]));
+        assert!(frame.get(0, 9));
+        assert!(frame.get(10, 0));
+    }
+}
@@ -0,0 +1,225 @@
+//! Text layout: measuring, wrapping and scrolling text in a box.
+//!
+//! Text is measured in characters rather than bytes, since every glyph of a
+//! font has the same width. The fonts only include printable ASCII
+//! characters, so other characters are substituted before drawing (accented
+//! latin letters by their base letter, anything else by `?`).
+
+use embedded_graphics::pixelcolor::PixelColorU8;
+use embedded_graphics::Drawing;
+use serde::Deserialize;
+
+use crate::display::Canvas;
+use crate::error::OledError;
+use crate::{shapes, write_text, Msg};
+
+/// Number of spaces separating the end of scrolling text from its start.
+const SCROLL_GAP: usize = 3;
+
+/// Width and height in pixels of a glyph of the given font, if it exists.
+pub fn font_size(font: &str) -> Option<(u32, u32)> {
+    match font {
+        "6x8" => Some((6, 8)),
+        "6x12" => Some((6, 12)),
+        "8x16" => Some((8, 16)),
+        "12x16" => Some((12, 16)),
+        _ => None,
+    }
+}
+
+/// Replace characters which are missing from the fonts.
+pub fn substitute(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            ' '..='~' => c,
+            'À'..='Å' => 'A',
+            'à'..='å' => 'a',
+            'Ç' => 'C',
+            'ç' => 'c',
+            'È'..='Ë' => 'E',
+            'è'..='ë' => 'e',
+            'Ì'..='Ï' => 'I',
+            'ì'..='ï' => 'i',
+            'Ñ' => 'N',
+            'ñ' => 'n',
+            'Ò'..='Ö' | 'Ø' => 'O',
+            'ò'..='ö' | 'ø' => 'o',
+            'Ù'..='Ü' => 'U',
+            'ù'..='ü' => 'u',
+            'Ý' => 'Y',
+            'ý' | 'ÿ' => 'y',
+            c if c.is_whitespace() => ' ',
+            _ => '?',
+        })
+        .collect()
+}
+
+/// Split text into lines of at most `columns` characters, breaking between
+/// words where possible.
+pub fn wrap(text: &str, columns: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    if columns == 0 {
+        return lines;
+    }
+    for paragraph in text.split('\n') {
+        let mut line = String::new();
+        let mut len = 0;
+        for word in paragraph.split_whitespace() {
+            let mut word: Vec<char> = word.chars().collect();
+            // start a new line if the word does not fit on this one
+            if len > 0 && len + 1 + word.len() > columns {
+                lines.push(line);
+                line = String::new();
+                len = 0;
+            }
+            // break words which are longer than a line (which is now empty)
+            while word.len() > columns {
+                let rest = word.split_off(columns);
+                lines.push(word.into_iter().collect());
+                word = rest;
+            }
+            if len > 0 {
+                line.push(' ');
+                len += 1;
+            }
+            len += word.len();
+            line.extend(word);
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+/// The `columns` characters of text visible after scrolling it `offset`
+/// characters to the left. Text which fits is not scrolled; otherwise it
+/// wraps around, with a gap between its end and start.
+pub fn marquee(text: &str, columns: usize, offset: usize) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    if chars.len() <= columns {
+        return text.to_string();
+    }
+    let len = chars.len() + SCROLL_GAP;
+    (0..columns)
+        .map(|i| *chars.get((offset + i) % len).unwrap_or(&' '))
+        .collect()
+}
+
+/// How text which does not fit on one line of a text box is shown.
+#[derive(Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Overflow {
+    /// Wrap the text onto as many lines as fit in the box.
+    #[default]
+    Wrap,
+    /// Show a single line, scrolling the text from right to left.
+    Scroll,
+}
+
+//define the TextBox struct for receiving text commands
+#[derive(Debug, Deserialize)]
+pub struct TextBox {
+    x_coord: i32,
+    y_coord: i32,
+    width: u32,
+    height: u32,
+    string: String,
+    font_size: String,
+    #[serde(default)]
+    overflow: Overflow,
+    #[serde(default)]
+    offset: usize,
+}
+
+impl TextBox {
+    /// Whether the text scrolls (and so must be redrawn to animate it): only
+    /// scrolling text which is too long for the box moves.
+    pub fn scrolls(&self) -> bool {
+        self.overflow == Overflow::Scroll
+            && font_size(&self.font_size).is_some_and(|(char_width, _)| {
+                substitute(&self.string).chars().count() > (self.width / char_width) as usize
+            })
+    }
+
+    /// Draw the text box, clearing it first. `tick` is added to the scroll
+    /// offset of scrolling text.
+    pub fn draw<C>(&self, canvas: &mut C, tick: usize) -> Result<(), OledError>
+    where
+        C: Canvas + Drawing<PixelColorU8> + ?Sized,
+    {
+        let (char_width, char_height) =
+            font_size(&self.font_size).ok_or_else(|| OledError::InvalidFontSize {
+                font: self.font_size.to_string(),
+            })?;
+        let columns = (self.width / char_width) as usize;
+        let rows = (self.height / char_height) as usize;
+
+        let text = substitute(&self.string);
+        let lines = match self.overflow {
+            Overflow::Wrap => {
+                let mut lines = wrap(&text, columns);
+                // mark text which does not fit in the box
+                if lines.len() > rows && rows > 0 {
+                    lines.truncate(rows);
+                    let last: String = lines[rows - 1].chars().take(columns.max(3) - 3).collect();
+                    lines[rows - 1] = format!("{}...", last);
+                }
+                lines
+            }
+            Overflow::Scroll => vec![marquee(&text, columns, self.offset + tick)],
+        };
+
+        shapes::rectangle(
+            canvas,
+            self.x_coord,
+            self.y_coord,
+            self.width,
+            self.height,
+            true,
+            false,
+        );
+        for (row, line) in lines.into_iter().take(rows).enumerate() {
+            let msg = Msg {
+                x_coord: self.x_coord,
+                y_coord: self.y_coord + (row as u32 * char_height) as i32,
+                string: line,
+                font_size: self.font_size.to_string(),
+            };
+            write_text(canvas, &msg, false);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn substitute_unsupported_characters() {
+        assert_eq!(substitute("Café Ñandú"), "Cafe Nandu");
+        assert_eq!(substitute("wifi\t📶"), "wifi ?");
+    }
+
+    #[test]
+    fn wrap_words() {
+        assert_eq!(
+            wrap("Failed to connect to network", 10),
+            vec!["Failed to", "connect to", "network"]
+        );
+        // long words are broken
+        assert_eq!(
+            wrap("fe80::1ff:fe23:4567:890a", 10),
+            vec!["fe80::1ff:", "fe23:4567:", "890a"]
+        );
+        assert_eq!(wrap("a\nb", 10), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn marquee_scrolls_and_wraps_around() {
+        assert_eq!(marquee("peach", 10, 3), "peach");
+        assert_eq!(marquee("peachcloud", 5, 0), "peach");
+        assert_eq!(marquee("peachcloud", 5, 5), "cloud");
+        assert_eq!(marquee("peachcloud", 5, 8), "ud   ");
+        assert_eq!(marquee("peachcloud", 5, 13), "peach");
+    }
+}
@@ -10,28 +10,59 @@ pub type BoxError = Box<dyn error::Error>;
 #[derive(Debug, Snafu)]
 #[snafu(visibility(pub(crate)))]
 pub enum StatError {
+    #[snafu(display("Invalid time range: {} is after {}", from, to))]
+    InvalidRange { from: u64, to: u64 },
+
+    #[snafu(display("Failed to retrieve CPU frequency: {}", source))]
+    ReadCpuFreq { source: io::Error },
+
     #[snafu(display("Failed to retrieve CPU statistics: {}", source))]
     ReadCpuStat { source: ProbeError },
 
     #[snafu(display("Failed to retrieve disk usage statistics: {}", source))]
     ReadDiskUsage { source: ProbeError },
 
+    #[snafu(display("Failed to retrieve statistics history: {}", source))]
+    ReadHistory { source: io::Error },
+
     #[snafu(display("Failed to retrieve load average statistics: {}", source))]
     ReadLoadAvg { source: ProbeError },
 
     #[snafu(display("Failed to retrieve memory statistics: {}", source))]
     ReadMemStat { source: ProbeError },
 
+    #[snafu(display("Failed to retrieve service statistics: {}", source))]
+    ReadServiceStat { source: io::Error },
+
+    #[snafu(display("Failed to retrieve temperature: {}", source))]
+    ReadTemperature { source: io::Error },
+
+    #[snafu(display("Failed to retrieve throttled state: {}", source))]
+    ReadThrottled { source: io::Error },
+
     #[snafu(display("Failed to retrieve system uptime: {}", source))]
     ReadUptime { source: io::Error },
 
     #[snafu(display("JSON serialization failed: {}", source))]
     SerdeSerialize { source: SerdeError },
+
+    #[snafu(display("Unknown metric: {}", metric))]
+    UnknownMetric { metric: String },
 }
 
 impl From<StatError> for Error {
     fn from(err: StatError) -> Self {
         match &err {
+            StatError::InvalidRange { from, to } => Error {
+                code: ErrorCode::ServerError(-32003),
+                message: format!("Invalid time range: {} is after {}", from, to),
+                data: None,
+            },
+            StatError::ReadCpuFreq { source } => Error {
+                code: ErrorCode::ServerError(-32001),
+                message: format!("Failed to retrieve CPU frequency: {}", source),
+                data: None,
+            },
             StatError::ReadCpuStat { source } => Error {
                 code: ErrorCode::ServerError(-32001),
                 message: format!("Failed to retrieve CPU statistics: {}", source),
@@ -42,6 +73,11 @@ impl From<StatError> for Error {
                 message: format!("Failed to retrieve disk usage statistics: {}", source),
                 data: None,
             },
+            StatError::ReadHistory { source } => Error {
+                code: ErrorCode::ServerError(-32001),
+                message: format!("Failed to retrieve statistics history: {}", source),
+                data: None,
+            },
             StatError::ReadLoadAvg { source } => Error {
                 code: ErrorCode::ServerError(-32001),
                 message: format!("Failed to retrieve load average statistics: {}", source),
@@ -52,6 +88,21 @@ impl From<StatError> for Error {
                 message: format!("Failed to retrieve memory statistics: {}", source),
                 data: None,
             },
+            StatError::ReadServiceStat { source } => Error {
+                code: ErrorCode::ServerError(-32001),
+                message: format!("Failed to retrieve service statistics: {}", source),
+                data: None,
+            },
+            StatError::ReadTemperature { source } => Error {
+                code: ErrorCode::ServerError(-32001),
+                message: format!("Failed to retrieve temperature: {}", source),
+                data: None,
+            },
+            StatError::ReadThrottled { source } => Error {
+                code: ErrorCode::ServerError(-32001),
+                message: format!("Failed to retrieve throttled state: {}", source),
+                data: None,
+            },
             StatError::ReadUptime { source } => Error {
                 code: ErrorCode::ServerError(-32001),
                 message: format!("Failed to retrieve system uptime: {}", source),
@@ -62,6 +113,11 @@ impl From<StatError> for Error {
                 message: format!("JSON serialization failed: {}", source),
                 data: None,
             },
+            StatError::UnknownMetric { metric } => Error {
+                code: ErrorCode::ServerError(-32004),
+                message: format!("Unknown metric: {}", metric),
+                data: None,
+            },
         }
     }
 }
@@ -0,0 +1,105 @@
+//! Hardware telemetry read from sysfs.
+//!
+//! Temperatures are read from the thermal zones (`/sys/class/thermal`), CPU
+//! frequencies from cpufreq (`/sys/devices/system/cpu/cpu*/cpufreq`) and the
+//! undervoltage and throttling flags from the Raspberry Pi firmware
+//! (`/sys/devices/platform/soc/soc:firmware/get_throttled`).
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::structs::{CpuFreq, Temperature, Throttled};
+
+const THERMAL_PATH: &str = "/sys/class/thermal";
+const CPU_PATH: &str = "/sys/devices/system/cpu";
+const THROTTLED_PATH: &str = "/sys/devices/platform/soc/soc:firmware/get_throttled";
+
+/// Read a sysfs attribute and parse it as an integer.
+fn read_int(path: &Path) -> io::Result<i64> {
+    let value = fs::read_to_string(path)?;
+    value.trim().parse().map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("invalid value in {}", path.display()),
+        )
+    })
+}
+
+/// List the entries of a sysfs directory with the given name prefix, sorted
+/// by name.
+fn entries(dir: &str, prefix: &str) -> io::Result<Vec<String>> {
+    let mut names: Vec<String> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .filter(|name| name.starts_with(prefix))
+        .collect();
+    names.sort();
+    Ok(names)
+}
+
+/// Read the temperature of every thermal zone. The Raspberry Pi has a single
+/// zone for the SoC (`cpu-thermal`).
+pub fn temperatures() -> io::Result<Vec<Temperature>> {
+    let mut temps = Vec::new();
+    for zone in entries(THERMAL_PATH, "thermal_zone")? {
+        let path = Path::new(THERMAL_PATH).join(&zone);
+        let kind = fs::read_to_string(path.join("type"))
+            .map(|kind| kind.trim().to_string())
+            .unwrap_or_default();
+        // temperatures are given in millidegrees Celsius
+        let millidegrees = read_int(&path.join("temp"))?;
+        temps.push(Temperature {
+            zone,
+            kind,
+            celsius: millidegrees as f32 / 1000.0,
+        });
+    }
+    Ok(temps)
+}
+
+/// Read the current, minimum and maximum frequency of each CPU core.
+pub fn cpu_freqs() -> io::Result<Vec<CpuFreq>> {
+    let mut freqs = Vec::new();
+    for cpu in entries(CPU_PATH, "cpu")? {
+        let path = Path::new(CPU_PATH).join(&cpu).join("cpufreq");
+        // skips `cpufreq`, `cpuidle` and cores without frequency scaling
+        if !cpu[3..].chars().all(|c| c.is_ascii_digit()) || !path.exists() {
+            continue;
+        }
+        // frequencies are given in kHz
+        let mhz = |attr: &str| read_int(&path.join(attr)).map(|khz| (khz / 1000) as u32);
+        freqs.push(CpuFreq {
+            cur_mhz: mhz("scaling_cur_freq")?,
+            min_mhz: mhz("cpuinfo_min_freq")?,
+            max_mhz: mhz("cpuinfo_max_freq")?,
+            cpu,
+        });
+    }
+    Ok(freqs)
+}
+
+/// Read the undervoltage and throttling flags reported by the firmware.
+pub fn throttled() -> io::Result<Throttled> {
+    let value = fs::read_to_string(THROTTLED_PATH)?;
+    let value = value.trim();
+    // the value is a hexadecimal bitmask, with or without a `0x` prefix
+    let bits = u32::from_str_radix(value.trim_start_matches("0x"), 16).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("invalid throttled value: {}", value),
+        )
+    })?;
+    let bit = |n: u32| bits & (1 << n) != 0;
+    Ok(Throttled {
+        under_voltage: bit(0),
+        freq_capped: bit(1),
+        throttled: bit(2),
+        soft_temp_limit: bit(3),
+        under_voltage_occurred: bit(16),
+        freq_capped_occurred: bit(17),
+        throttled_occurred: bit(18),
+        soft_temp_limit_occurred: bit(19),
+        bits,
+    })
+}
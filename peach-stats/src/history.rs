@@ -0,0 +1,266 @@
+//! Time-series history of system statistics.
+//!
+//! A sampler thread records CPU usage, memory usage, load average, network
+//! throughput and CPU temperature at a configurable interval. Each metric is
+//! stored on disk in one fixed-size ring buffer per tier: samples are
+//! averaged into 1-minute slots (kept for 24 hours) and 1-hour slots (kept
+//! for 30 days). Slots are overwritten as the ring wraps around, so the files
+//! never grow and each sample is a small in-place write.
+
+use std::fs::{self, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use std::{thread, thread::JoinHandle};
+
+use log::{error, warn};
+use probes::{cpu, load, memory, network};
+
+use crate::hardware;
+use crate::structs::{Point, Series};
+
+/// Size of a slot on disk: period start (`u64`), mean (`f32`) and sample
+/// count (`u32`), little-endian.
+const SLOT_LEN: u64 = 16;
+
+/// Names of the recorded metrics.
+pub const METRICS: [&str; 6] = ["cpu", "mem", "load", "net_rx", "net_tx", "temp"];
+
+/// A resolution at which metrics are stored.
+#[derive(Debug)]
+pub struct Tier {
+    pub name: &'static str, // used as the file extension
+    pub resolution: u64,    // length of a slot (seconds)
+    pub slots: u64,         // number of slots in the ring
+}
+
+/// Storage tiers, finest resolution first.
+pub const TIERS: [Tier; 2] = [
+    Tier {
+        name: "minute",
+        resolution: 60,
+        slots: 24 * 60,
+    },
+    Tier {
+        name: "hour",
+        resolution: 60 * 60,
+        slots: 30 * 24,
+    },
+];
+
+/// Current time in seconds since the Unix epoch.
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.as_secs())
+        .unwrap_or_default()
+}
+
+/// Ring buffer files of all metrics, stored in a single directory.
+#[derive(Debug)]
+pub struct History {
+    dir: PathBuf,
+}
+
+impl History {
+    pub fn new(dir: PathBuf) -> History {
+        History { dir }
+    }
+
+    fn path(&self, metric: &str, tier: &Tier) -> PathBuf {
+        self.dir.join(format!("{}.{}", metric, tier.name))
+    }
+
+    /// Add a sample to the slot covering `time` in every tier.
+    pub fn record(&self, metric: &str, time: u64, value: f32) -> io::Result<()> {
+        for tier in &TIERS {
+            let start = time - time % tier.resolution;
+            let offset = (start / tier.resolution % tier.slots) * SLOT_LEN;
+            let mut file = OpenOptions::new()
+                .read(true)
+                .write(true)
+                .create(true)
+                .truncate(false)
+                .open(self.path(metric, tier))?;
+
+            let mut slot = [0; SLOT_LEN as usize];
+            file.seek(SeekFrom::Start(offset))?;
+            // slots beyond the end of the file have not been written yet
+            let (mean, count) = match file.read_exact(&mut slot) {
+                Ok(_) => match decode(&slot) {
+                    (slot_start, mean, count) if slot_start == start && count > 0 => (
+                        (mean * count as f32 + value) / (count + 1) as f32,
+                        count + 1,
+                    ),
+                    _ => (value, 1),
+                },
+                Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => (value, 1),
+                Err(e) => return Err(e),
+            };
+
+            file.seek(SeekFrom::Start(offset))?;
+            file.write_all(&encode(start, mean, count))?;
+        }
+
+        Ok(())
+    }
+
+    /// Retrieve the points of a metric between `from` and `to` (inclusive),
+    /// oldest first. The finest tier which still holds `from` is used.
+    pub fn query(&self, metric: &str, from: u64, to: u64) -> io::Result<Series> {
+        let now = now();
+        let tier = TIERS
+            .iter()
+            .find(|tier| now.saturating_sub(from) < tier.resolution * tier.slots)
+            .unwrap_or(&TIERS[TIERS.len() - 1]);
+        // slots older than this have been (or are about to be) overwritten
+        let oldest = now.saturating_sub(tier.resolution * tier.slots);
+
+        let mut bytes = Vec::new();
+        match fs::File::open(self.path(metric, tier)) {
+            Ok(mut file) => {
+                file.read_to_end(&mut bytes)?;
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => (),
+            Err(e) => return Err(e),
+        }
+
+        let mut points: Vec<Point> = bytes
+            .chunks_exact(SLOT_LEN as usize)
+            .map(decode)
+            .filter(|&(start, _, count)| {
+                count > 0 && start > oldest && start + tier.resolution > from && start <= to
+            })
+            .map(|(time, value, _)| Point { time, value })
+            .collect();
+        points.sort_by_key(|point| point.time);
+
+        Ok(Series {
+            metric: metric.to_string(),
+            resolution: tier.resolution,
+            points,
+        })
+    }
+}
+
+fn encode(start: u64, mean: f32, count: u32) -> [u8; SLOT_LEN as usize] {
+    let mut slot = [0; SLOT_LEN as usize];
+    slot[..8].copy_from_slice(&start.to_le_bytes());
+    slot[8..12].copy_from_slice(&mean.to_le_bytes());
+    slot[12..].copy_from_slice(&count.to_le_bytes());
+    slot
+}
+
+fn decode(slot: &[u8]) -> (u64, f32, u32) {
+    let mut start = [0; 8];
+    let mut mean = [0; 4];
+    let mut count = [0; 4];
+    start.copy_from_slice(&slot[..8]);
+    mean.copy_from_slice(&slot[8..12]);
+    count.copy_from_slice(&slot[12..16]);
+    (
+        u64::from_le_bytes(start),
+        f32::from_le_bytes(mean),
+        u32::from_le_bytes(count),
+    )
+}
+
+/// Reads the system statistics. CPU usage and network throughput are
+/// calculated from the change in the kernel counters since the previous
+/// sample, so they are first recorded on the second sample.
+#[derive(Debug, Default)]
+struct Sampler {
+    cpu: Option<(u64, u64)>,          // busy and total jiffies
+    net: Option<(Instant, u64, u64)>, // time of sample, received and transmitted bytes
+}
+
+impl Sampler {
+    fn sample(&mut self) -> Vec<(&'static str, f32)> {
+        let mut values = Vec::new();
+
+        if let Ok(stats) = cpu::proc::read() {
+            let s = stats.stat;
+            let busy = s.user + s.system + s.nice;
+            let total = busy + s.idle;
+            if let Some((last_busy, last_total)) = self.cpu {
+                if total > last_total && busy >= last_busy {
+                    let percent = (busy - last_busy) as f32 / (total - last_total) as f32;
+                    values.push(("cpu", percent * 100.0));
+                }
+            }
+            self.cpu = Some((busy, total));
+        }
+
+        if let Ok(mem) = memory::read() {
+            if mem.total() > 0 {
+                values.push(("mem", mem.used() as f32 / mem.total() as f32 * 100.0));
+            }
+        }
+
+        if let Ok(load) = load::read() {
+            values.push(("load", load.one));
+        }
+
+        if let Ok(net) = network::read() {
+            let (rx, tx) = net
+                .interfaces
+                .iter()
+                .filter(|(iface, _)| iface.as_str() != "lo")
+                .fold((0, 0), |(rx, tx), (_, data)| {
+                    (rx + data.received, tx + data.transmitted)
+                });
+            let time = Instant::now();
+            if let Some((last_time, last_rx, last_tx)) = self.net {
+                let secs = time.duration_since(last_time).as_secs_f32();
+                // counters are reset when an interface is removed
+                if secs > 0.0 && rx >= last_rx && tx >= last_tx {
+                    values.push(("net_rx", (rx - last_rx) as f32 / secs));
+                    values.push(("net_tx", (tx - last_tx) as f32 / secs));
+                }
+            }
+            self.net = Some((time, rx, tx));
+        }
+
+        // the highest temperature of all thermal zones
+        if let Ok(temps) = hardware::temperatures() {
+            if let Some(temp) = temps.iter().map(|t| t.celsius).reduce(f32::max) {
+                values.push(("temp", temp));
+            }
+        }
+
+        values
+    }
+}
+
+/// Spawn a thread which samples the system statistics every `interval` and
+/// records them in the history. No samples are recorded if the history
+/// directory cannot be created.
+pub fn spawn_sampler(history: Arc<Mutex<History>>, interval: Duration) -> JoinHandle<()> {
+    thread::spawn(move || {
+        let dir = history.lock().unwrap().dir.clone();
+        if let Err(e) = fs::create_dir_all(&dir) {
+            error!(
+                "Failed to create history directory {}: {}",
+                dir.display(),
+                e
+            );
+            return;
+        }
+
+        let mut sampler = Sampler::default();
+        loop {
+            let values = sampler.sample();
+            let time = now();
+            let history = history.lock().unwrap();
+            for (metric, value) in values {
+                if let Err(e) = history.record(metric, time, value) {
+                    warn!("Failed to record {} history: {}", metric, e);
+                }
+            }
+            drop(history);
+
+            thread::sleep(interval);
+        }
+    })
+}
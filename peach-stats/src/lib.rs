@@ -1,23 +1,51 @@
 mod error;
+mod hardware;
+mod history;
+mod services;
 mod stats;
 mod structs;
 
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use std::{env, result::Result};
 
-use jsonrpc_core::{IoHandler, Value};
+use jsonrpc_core::{types::error::Error, IoHandler, Params, Value};
 use jsonrpc_http_server::{AccessControlAllowOrigin, DomainsValidation, ServerBuilder};
 #[allow(unused_imports)]
 use jsonrpc_test as test;
 use log::info;
 
 use crate::error::BoxError;
+use crate::history::History;
+use crate::structs::HistoryQuery;
 
 pub fn run() -> Result<(), BoxError> {
     info!("Starting up.");
 
+    let history_dir =
+        env::var("PEACH_STATS_DIR").unwrap_or_else(|_| "/var/lib/peach-stats".to_string());
+    let interval = env::var("PEACH_STATS_INTERVAL")
+        .ok()
+        .and_then(|secs| secs.parse::<u64>().ok())
+        .unwrap_or(60)
+        // an interval of 0 would make the sampler busy-loop
+        .max(1);
+    let history = Arc::new(Mutex::new(History::new(PathBuf::from(history_dir))));
+
+    info!("Sampling statistics every {} seconds.", interval);
+    history::spawn_sampler(Arc::clone(&history), Duration::from_secs(interval));
+
     info!("Creating JSON-RPC I/O handler.");
     let mut io = IoHandler::default();
 
+    io.add_method("cpu_freq", move |_| {
+        info!("Fetching CPU frequencies.");
+        let freqs = stats::cpu_freq()?;
+
+        Ok(Value::String(freqs))
+    });
+
     io.add_method("cpu_stats", move |_| {
         info!("Fetching CPU statistics.");
         let stats = stats::cpu_stats()?;
@@ -39,6 +67,16 @@ pub fn run() -> Result<(), BoxError> {
         Ok(Value::String(disks))
     });
 
+    io.add_method("history", move |params: Params| {
+        let q: Result<HistoryQuery, Error> = params.parse();
+        let q: HistoryQuery = q?;
+        info!("Fetching {} history.", q.metric);
+        let history = history.lock().unwrap();
+        let series = stats::history(&history, &q)?;
+
+        Ok(Value::String(series))
+    });
+
     io.add_method("load_average", move |_| {
         info!("Fetching system load average statistics.");
         let avg = stats::load_average()?;
@@ -55,6 +93,27 @@ pub fn run() -> Result<(), BoxError> {
 
     io.add_method("ping", |_| Ok(Value::String("success".to_string())));
 
+    io.add_method("service_stats", move |_| {
+        info!("Fetching service resource usage.");
+        let services = stats::service_stats()?;
+
+        Ok(Value::String(services))
+    });
+
+    io.add_method("temperature", move |_| {
+        info!("Fetching temperatures.");
+        let temps = stats::temperature()?;
+
+        Ok(Value::String(temps))
+    });
+
+    io.add_method("throttled", move |_| {
+        info!("Fetching undervoltage and throttling flags.");
+        let throttled = stats::throttled()?;
+
+        Ok(Value::String(throttled))
+    });
+
     io.add_method("uptime", move |_| {
         info!("Fetching system uptime.");
         let uptime = stats::uptime()?;
@@ -85,6 +144,7 @@ pub fn run() -> Result<(), BoxError> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::error::StatError;
 
     // test to ensure correct success response
     #[test]
@@ -99,4 +159,57 @@ mod tests {
 
         assert_eq!(rpc.request("rpc_success_response", &()), r#""success""#);
     }
+
+    // test to ensure correct UnknownMetric error response
+    #[test]
+    fn rpc_unknownmetric_error() {
+        let rpc = {
+            let mut io = IoHandler::new();
+            io.add_method("rpc_unknownmetric_error", |_| {
+                Err(Error::from(StatError::UnknownMetric {
+                    metric: "fan".to_string(),
+                }))
+            });
+            test::Rpc::from(io)
+        };
+
+        assert_eq!(
+            rpc.request("rpc_unknownmetric_error", &()),
+            r#"{
+  "code": -32004,
+  "message": "Unknown metric: fan"
+}"#
+        );
+    }
+
+    // test to ensure samples are averaged into one point per period
+    #[test]
+    fn history_downsampling() {
+        let dir = env::temp_dir().join(format!("peach-stats-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let history = History::new(dir.clone());
+
+        // three samples in the same minute, one in the next
+        let start = history::now() - history::now() % 3600 - 3600;
+        for (offset, value) in [(0, 10.0), (20, 20.0), (40, 30.0), (60, 50.0)] {
+            history.record("cpu", start + offset, value).unwrap();
+        }
+
+        let minutes = history.query("cpu", start, start + 3599).unwrap();
+        assert_eq!(minutes.resolution, 60);
+        assert_eq!(minutes.points.len(), 2);
+        assert_eq!(minutes.points[0].time, start);
+        assert!((minutes.points[0].value - 20.0).abs() < f32::EPSILON);
+        assert!((minutes.points[1].value - 50.0).abs() < f32::EPSILON);
+
+        // a range beyond 24 hours is served from the hourly tier
+        let hours = history
+            .query("cpu", start - 2 * 86400, start + 3599)
+            .unwrap();
+        assert_eq!(hours.resolution, 3600);
+        assert_eq!(hours.points.len(), 1);
+        assert!((hours.points[0].value - 27.5).abs() < f32::EPSILON);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
 }
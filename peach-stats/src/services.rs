@@ -0,0 +1,191 @@
+//! Resource usage of the PeachCloud services.
+//!
+//! The state, main PID, restart count and control group of each service are
+//! queried from systemd (`systemctl show`). The processes in the control group
+//! are then measured from `/proc/<pid>`: CPU time (`stat`), resident memory
+//! (`status`) and open file descriptors (`fd`).
+//!
+//! CPU usage is calculated from the CPU time consumed by the processes
+//! between two samples taken `SAMPLE_PERIOD` apart, and is given as a
+//! percentage of one core (so a busy service may exceed 100% on a multi-core
+//! device).
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::process::Command;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use peach_config::constants::SERVICES;
+
+use crate::structs::ServiceStat;
+
+/// Time between the two CPU time samples.
+const SAMPLE_PERIOD: Duration = Duration::from_millis(250);
+
+/// Clock ticks per second used by `/proc/<pid>/stat` (`USER_HZ`, which is 100
+/// on all architectures supported by PeachCloud).
+const CLK_TCK: f32 = 100.0;
+
+/// Control group hierarchies, in order of preference: the unified (v2)
+/// hierarchy and the systemd (v1) hierarchy.
+const CGROUP_PATHS: [&str; 3] = [
+    "/sys/fs/cgroup",
+    "/sys/fs/cgroup/unified",
+    "/sys/fs/cgroup/systemd",
+];
+
+/// Properties of a systemd unit.
+struct Unit {
+    name: String,
+    state: String,
+    main_pid: u32,
+    restarts: Option<u32>,
+    control_group: String,
+}
+
+/// Query the properties of a unit, returning `None` for services which are
+/// not installed (and entries which are not units, such as `peach-config`).
+fn unit(service: &str) -> io::Result<Option<Unit>> {
+    let output = Command::new("systemctl")
+        .arg("show")
+        .arg("--property=LoadState,ActiveState,MainPID,NRestarts,ControlGroup")
+        .arg(service)
+        .output()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let props: HashMap<&str, &str> = stdout
+        .lines()
+        .filter_map(|line| {
+            let mut kv = line.splitn(2, '=');
+            Some((kv.next()?, kv.next()?))
+        })
+        .collect();
+    if props.get("LoadState") != Some(&"loaded") {
+        return Ok(None);
+    }
+    Ok(Some(Unit {
+        name: service.to_string(),
+        state: props.get("ActiveState").unwrap_or(&"unknown").to_string(),
+        main_pid: props
+            .get("MainPID")
+            .and_then(|pid| pid.parse().ok())
+            .unwrap_or(0),
+        // `NRestarts` is only reported by systemd 235 and later
+        restarts: props.get("NRestarts").and_then(|n| n.parse().ok()),
+        control_group: props.get("ControlGroup").unwrap_or(&"").to_string(),
+    }))
+}
+
+/// List the processes of a unit from its control group, falling back to the
+/// main process if the control group cannot be read.
+fn pids(unit: &Unit) -> Vec<u32> {
+    if !unit.control_group.is_empty() {
+        for root in CGROUP_PATHS.iter() {
+            let path = format!("{}{}/cgroup.procs", root, unit.control_group);
+            if let Ok(procs) = fs::read_to_string(path) {
+                return procs.lines().filter_map(|pid| pid.parse().ok()).collect();
+            }
+        }
+    }
+    match unit.main_pid {
+        0 => Vec::new(),
+        pid => vec![pid],
+    }
+}
+
+/// Read the CPU time (user and system) consumed by a process, in clock ticks.
+fn cpu_ticks(pid: u32) -> Option<u64> {
+    let stat = fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    // the command name may contain spaces, so fields are counted from the
+    // closing parenthesis which follows it (`utime` and `stime` are fields 14
+    // and 15 of the line)
+    let fields: Vec<&str> = stat[stat.rfind(')')? + 1..].split_whitespace().collect();
+    let utime: u64 = fields.get(11)?.parse().ok()?;
+    let stime: u64 = fields.get(12)?.parse().ok()?;
+    Some(utime + stime)
+}
+
+/// Read the resident memory of a process, in kilobytes.
+fn rss(pid: u32) -> Option<u64> {
+    let status = fs::read_to_string(format!("/proc/{}/status", pid)).ok()?;
+    status
+        .lines()
+        .find(|line| line.starts_with("VmRSS:"))
+        .and_then(|line| line.split_whitespace().nth(1))
+        .and_then(|kb| kb.parse().ok())
+}
+
+/// Count the open file descriptors of a process. This requires permission to
+/// inspect the process, so `None` is returned for processes of other users.
+fn fds(pid: u32) -> Option<u32> {
+    fs::read_dir(format!("/proc/{}/fd", pid))
+        .ok()
+        .map(|entries| entries.count() as u32)
+}
+
+/// Sum a per-process measurement over the processes of a service, skipping
+/// processes for which it cannot be read (such as processes which exit while
+/// being measured). Returns `None` if it could not be read for any process.
+fn sum<T: std::iter::Sum<T>>(pids: &[u32], measure: impl Fn(u32) -> Option<T>) -> Option<T> {
+    let measured: Vec<T> = pids.iter().filter_map(|pid| measure(*pid)).collect();
+    if measured.is_empty() {
+        None
+    } else {
+        Some(measured.into_iter().sum())
+    }
+}
+
+/// Measure the resource usage of each installed PeachCloud service.
+pub fn service_stats() -> io::Result<Vec<ServiceStat>> {
+    let mut units = Vec::new();
+    for service in SERVICES.iter() {
+        if let Some(unit) = unit(service)? {
+            let pids = pids(&unit);
+            units.push((unit, pids));
+        }
+    }
+
+    let before: Vec<u64> = units
+        .iter()
+        .map(|(_, pids)| pids.iter().filter_map(|pid| cpu_ticks(*pid)).sum())
+        .collect();
+    let start = Instant::now();
+    thread::sleep(SAMPLE_PERIOD);
+    let elapsed = start.elapsed().as_secs_f32();
+
+    let stats = units
+        .into_iter()
+        .zip(before)
+        .map(|((unit, pids), before)| {
+            let after: u64 = pids.iter().filter_map(|pid| cpu_ticks(*pid)).sum();
+            // processes which exit between the samples may reduce the total
+            let ticks = after.saturating_sub(before) as f32;
+            ServiceStat {
+                cpu_percent: ticks / CLK_TCK / elapsed * 100.0,
+                rss: sum(&pids, rss).unwrap_or(0),
+                fds: sum(&pids, fds),
+                processes: pids.len() as u32,
+                restarts: unit.restarts,
+                name: unit.name,
+                state: unit.state,
+            }
+        })
+        .collect();
+
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // test to ensure processes which can't be measured are skipped
+    #[test]
+    fn sum_skips_unreadable_processes() {
+        let measure = |pid| if pid == 2 { None } else { Some(u64::from(pid)) };
+        assert_eq!(sum(&[1, 2, 3], measure), Some(4));
+        assert_eq!(sum(&[2], measure), None);
+        assert_eq!(sum(&[], measure), None);
+    }
+}
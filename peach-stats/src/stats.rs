@@ -5,7 +5,10 @@ use snafu::ResultExt;
 use systemstat::{Platform, System};
 
 use crate::error::*;
-use crate::structs::{CpuStat, CpuStatPercentages, DiskUsage, LoadAverage, MemStat};
+use crate::hardware;
+use crate::history::{History, METRICS};
+use crate::services;
+use crate::structs::{CpuStat, CpuStatPercentages, DiskUsage, HistoryQuery, LoadAverage, MemStat};
 
 pub fn cpu_stats() -> Result<String, StatError> {
     let cpu_stats = cpu::proc::read().context(ReadCpuStat)?;
@@ -35,6 +38,13 @@ pub fn cpu_stats_percent() -> Result<String, StatError> {
     Ok(json_cpu)
 }
 
+pub fn cpu_freq() -> Result<String, StatError> {
+    let freqs = hardware::cpu_freqs().context(ReadCpuFreq)?;
+    let json_freqs = serde_json::to_string(&freqs).context(SerdeSerialize)?;
+
+    Ok(json_freqs)
+}
+
 pub fn disk_usage() -> Result<String, StatError> {
     let disks = disk_usage::read().context(ReadDiskUsage)?;
     let mut disk_usages = Vec::new();
@@ -54,6 +64,26 @@ pub fn disk_usage() -> Result<String, StatError> {
     Ok(json_disks)
 }
 
+pub fn history(history: &History, query: &HistoryQuery) -> Result<String, StatError> {
+    if !METRICS.contains(&query.metric.as_str()) {
+        return Err(StatError::UnknownMetric {
+            metric: query.metric.to_string(),
+        });
+    }
+    if query.from > query.to {
+        return Err(StatError::InvalidRange {
+            from: query.from,
+            to: query.to,
+        });
+    }
+    let series = history
+        .query(&query.metric, query.from, query.to)
+        .context(ReadHistory)?;
+    let json_series = serde_json::to_string(&series).context(SerdeSerialize)?;
+
+    Ok(json_series)
+}
+
 pub fn load_average() -> Result<String, StatError> {
     let l = load::read().context(ReadLoadAvg)?;
     let load_avg = LoadAverage {
@@ -78,6 +108,27 @@ pub fn mem_stats() -> Result<String, StatError> {
     Ok(json_mem)
 }
 
+pub fn service_stats() -> Result<String, StatError> {
+    let services = services::service_stats().context(ReadServiceStat)?;
+    let json_services = serde_json::to_string(&services).context(SerdeSerialize)?;
+
+    Ok(json_services)
+}
+
+pub fn temperature() -> Result<String, StatError> {
+    let temps = hardware::temperatures().context(ReadTemperature)?;
+    let json_temps = serde_json::to_string(&temps).context(SerdeSerialize)?;
+
+    Ok(json_temps)
+}
+
+pub fn throttled() -> Result<String, StatError> {
+    let throttled = hardware::throttled().context(ReadThrottled)?;
+    let json_throttled = serde_json::to_string(&throttled).context(SerdeSerialize)?;
+
+    Ok(json_throttled)
+}
+
 pub fn uptime() -> Result<String, StatError> {
     let sys = System::new();
     let uptime = sys.uptime().context(ReadUptime)?;
@@ -1,4 +1,4 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize)]
 pub struct CpuStat {
@@ -16,6 +16,14 @@ pub struct CpuStatPercentages {
     pub nice: f32,
 }
 
+#[derive(Debug, Serialize)]
+pub struct CpuFreq {
+    pub cpu: String,
+    pub cur_mhz: u32,
+    pub min_mhz: u32,
+    pub max_mhz: u32,
+}
+
 #[derive(Debug, Serialize)]
 pub struct DiskUsage {
     pub filesystem: Option<String>,
@@ -26,6 +34,13 @@ pub struct DiskUsage {
     pub mountpoint: String,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct HistoryQuery {
+    pub metric: String,
+    pub from: u64, // start of the time range (seconds since the Unix epoch)
+    pub to: u64,   // end of the time range (seconds since the Unix epoch)
+}
+
 #[derive(Debug, Serialize)]
 pub struct LoadAverage {
     pub one: f32,
@@ -39,3 +54,47 @@ pub struct MemStat {
     pub free: u64,
     pub used: u64,
 }
+
+#[derive(Debug, Serialize)]
+pub struct Point {
+    pub time: u64, // start of the period (seconds since the Unix epoch)
+    pub value: f32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Series {
+    pub metric: String,
+    pub resolution: u64, // length of the period of each point (seconds)
+    pub points: Vec<Point>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ServiceStat {
+    pub name: String,
+    pub state: String,         // systemd `ActiveState`, eg. `active` or `failed`
+    pub processes: u32,        // number of processes in the control group
+    pub cpu_percent: f32,      // percentage of one core
+    pub rss: u64,              // resident memory (kilobytes)
+    pub fds: Option<u32>,      // open file descriptors (if permitted to read)
+    pub restarts: Option<u32>, // automatic restarts by systemd
+}
+
+#[derive(Debug, Serialize)]
+pub struct Temperature {
+    pub zone: String, // eg. `thermal_zone0`
+    pub kind: String, // eg. `cpu-thermal`
+    pub celsius: f32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Throttled {
+    pub under_voltage: bool,            // under-voltage detected
+    pub freq_capped: bool,              // arm frequency capped
+    pub throttled: bool,                // currently throttled
+    pub soft_temp_limit: bool,          // soft temperature limit active
+    pub under_voltage_occurred: bool,   // under-voltage has occurred since boot
+    pub freq_capped_occurred: bool,     // arm frequency capping has occurred since boot
+    pub throttled_occurred: bool,       // throttling has occurred since boot
+    pub soft_temp_limit_occurred: bool, // soft temperature limit has occurred since boot
+    pub bits: u32,                      // raw bitmask reported by the firmware
+}
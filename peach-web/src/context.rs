@@ -29,20 +29,24 @@ use serde::Serialize;
 use peach_lib::config_manager::load_peach_config;
 use peach_lib::dyndns_client;
 use peach_lib::dyndns_client::{get_dyndns_subdomain, is_dns_updater_online};
+use peach_lib::monitor::{Alert, Threshold};
 use peach_lib::network_client;
-use peach_lib::network_client::{AccessPoint, Networks, Scan};
+use peach_lib::network_client::{AccessPoint, Networks, Scan, Station};
 use peach_lib::oled_client;
 use peach_lib::sbot_client;
 use peach_lib::stats_client;
-use peach_lib::stats_client::{CpuStatPercentages, DiskUsage, LoadAverage, MemStat, Traffic};
+use peach_lib::stats_client::{
+    CpuFreq, CpuStatPercentages, DiskUsage, LoadAverage, MemStat, ServiceStat, Throttled, Traffic,
+};
 
 use crate::monitor;
-use crate::monitor::{Alert, Data, Threshold};
+use crate::monitor::Data;
 
 // used in /device for system statistics
 #[derive(Debug, Serialize)]
 pub struct DeviceContext {
     pub back: Option<String>,
+    pub cpu_freq: Vec<CpuFreq>,
     pub cpu_stat_percent: Option<CpuStatPercentages>,
     pub disk_stats: Vec<DiskUsage>,
     pub flash_name: Option<String>,
@@ -56,6 +60,9 @@ pub struct DeviceContext {
     pub dyndns_is_online: bool,
     pub config_is_valid: bool,
     pub sbot_is_online: bool,
+    pub services: Vec<ServiceStat>,
+    pub soc_temp: Option<f32>,
+    pub throttled: Option<Throttled>,
     pub title: Option<String>,
     pub uptime: Option<i32>,
 }
@@ -66,6 +73,15 @@ impl DeviceContext {
         let cpu_stat_percent = stats_client::cpu_stats_percent().ok();
         let load_average = stats_client::load_average().ok();
         let mem_stats = stats_client::mem_stats().ok();
+        // hardware telemetry (only available on the raspberry pi)
+        let cpu_freq = stats_client::cpu_freq().unwrap_or_default();
+        // highest temperature of all thermal zones
+        let soc_temp = stats_client::temperature()
+            .ok()
+            .and_then(|temps| temps.iter().map(|t| t.celsius).reduce(f32::max));
+        let throttled = stats_client::throttled().ok();
+        // resource usage of each installed peachcloud service
+        let services = stats_client::service_stats().unwrap_or_default();
         let network_ping = match network_client::ping() {
             Ok(_) => "ONLINE".to_string(),
             Err(_) => "OFFLINE".to_string(),
@@ -83,15 +99,7 @@ impl DeviceContext {
             Err(_) => "Unavailable".to_string(),
         };
 
-        // serialize disk usage data into Vec<DiskUsage>
-        let disk_usage_stats = match stats_client::disk_usage() {
-            Ok(disks) => {
-                let partitions: Vec<DiskUsage> = serde_json::from_str(disks.as_str())
-                    .expect("Failed to deserialize disk_usage response");
-                partitions
-            }
-            Err(_) => Vec::new(),
-        };
+        let disk_usage_stats = stats_client::disk_usage().unwrap_or_default();
 
         let mut disk_stats = Vec::new();
         // select only the partition we're interested in: /dev/mmcblk0p2 ("/")
@@ -148,6 +156,7 @@ impl DeviceContext {
 
         DeviceContext {
             back: None,
+            cpu_freq,
             cpu_stat_percent,
             disk_stats,
             flash_name: None,
@@ -161,6 +170,9 @@ impl DeviceContext {
             dyndns_is_online,
             config_is_valid,
             sbot_is_online,
+            services,
+            soc_temp,
+            throttled,
             title: None,
             uptime: uptime_parsed,
         }
@@ -397,12 +409,62 @@ impl SendPasswordResetContext {
     }
 }
 
+// used in /network/ap to list the stations associated with the access point
+#[derive(Debug, Serialize)]
+pub struct NetworkApContext {
+    pub ap_ip: String,
+    pub ap_ssid: String,
+    pub ap_state: String,
+    pub back: Option<String>,
+    pub flash_name: Option<String>,
+    pub flash_msg: Option<String>,
+    pub stations: Option<Vec<Station>>,
+    pub title: Option<String>,
+}
+
+impl NetworkApContext {
+    pub fn build() -> NetworkApContext {
+        let ap_ip = match network_client::ip("ap0") {
+            Ok(ip) => ip,
+            Err(_) => "x.x.x.x".to_string(),
+        };
+        let ap_ssid = match network_client::ssid("ap0") {
+            Ok(ssid) => ssid,
+            Err(_) => "Not currently activated".to_string(),
+        };
+        let ap_state = match network_client::state("ap0") {
+            Ok(state) => state,
+            Err(_) => "Interface unavailable".to_string(),
+        };
+        let stations = match network_client::stations("ap0") {
+            Ok(stations) => Some(stations),
+            Err(_) => None,
+        };
+
+        NetworkApContext {
+            ap_ip,
+            ap_ssid,
+            ap_state,
+            back: None,
+            flash_name: None,
+            flash_msg: None,
+            stations,
+            title: None,
+        }
+    }
+}
+
 #[derive(Debug, Serialize)]
 pub struct NetworkContext {
+    pub ap_clients: Option<usize>,
     pub ap_ip: String,
     pub ap_ssid: String,
     pub ap_state: String,
     pub ap_traffic: Option<Traffic>,
+    // name and kind of the interface holding the default route
+    pub uplink_iface: String,
+    pub uplink_kind: String,
+    // ip, state and traffic are reported for the uplink interface
     pub wlan_ip: String,
     pub wlan_rssi: Option<String>,
     pub wlan_scan: Option<Vec<Scan>>,
@@ -423,6 +485,10 @@ pub struct NetworkContext {
 
 impl NetworkContext {
     pub fn build() -> NetworkContext {
+        let ap_clients = match network_client::stations("ap0") {
+            Ok(stations) => Some(stations.len()),
+            Err(_) => None,
+        };
         let ap_ip = match network_client::ip("ap0") {
             Ok(ip) => ip,
             Err(_) => "x.x.x.x".to_string(),
@@ -435,47 +501,29 @@ impl NetworkContext {
             Ok(state) => state,
             Err(_) => "Interface unavailable".to_string(),
         };
-        let ap_traffic = match network_client::traffic("ap0") {
-            Ok(traffic) => {
-                let mut t = traffic;
-                // modify traffic values & assign measurement unit
-                // based on received and transmitted values
-                // if received > 999 MB, convert it to GB
-                if t.received > 1_047_527_424 {
-                    t.received /= 1_073_741_824;
-                    t.rx_unit = Some("GB".to_string());
-                } else if t.received > 0 {
-                    // otherwise, convert it to MB
-                    t.received = (t.received / 1024) / 1024;
-                    t.rx_unit = Some("MB".to_string());
-                } else {
-                    t.received = 0;
-                    t.rx_unit = Some("MB".to_string());
-                }
-
-                if t.transmitted > 1_047_527_424 {
-                    t.transmitted /= 1_073_741_824;
-                    t.tx_unit = Some("GB".to_string());
-                } else if t.transmitted > 0 {
-                    t.transmitted = (t.transmitted / 1024) / 1024;
-                    t.tx_unit = Some("MB".to_string());
-                } else {
-                    t.transmitted = 0;
-                    t.tx_unit = Some("MB".to_string());
-                }
-                Some(t)
-            }
-            Err(_) => None,
+        let ap_traffic = network_client::traffic("ap0").ok();
+        // fall back to the wireless client if no interface is providing uplink
+        let (uplink_iface, uplink_kind) = match network_client::list_interfaces() {
+            Ok(interfaces) => match interfaces.into_iter().find(|iface| iface.uplink) {
+                Some(iface) => (iface.name, iface.kind),
+                None => ("wlan0".to_string(), "wifi".to_string()),
+            },
+            Err(_) => ("wlan0".to_string(), "wifi".to_string()),
         };
-        let wlan_ip = match network_client::ip("wlan0") {
+        // wifi-specific queries target the uplink only if it is wireless
+        let wifi_iface = match uplink_kind.as_str() {
+            "wifi" => uplink_iface.as_str(),
+            _ => "wlan0",
+        };
+        let wlan_ip = match network_client::ip(&uplink_iface) {
             Ok(ip) => ip,
             Err(_) => "x.x.x.x".to_string(),
         };
-        let wlan_rssi = match network_client::rssi_percent("wlan0") {
+        let wlan_rssi = match network_client::rssi_percent(wifi_iface) {
             Ok(rssi) => Some(rssi),
             Err(_) => None,
         };
-        let wlan_scan = match network_client::available_networks("wlan0") {
+        let wlan_scan = match network_client::available_networks(wifi_iface) {
             Ok(networks) => {
                 let scan: Vec<Scan> = serde_json::from_str(networks.as_str())
                     .expect("Failed to deserialize scan_networks response");
@@ -483,56 +531,28 @@ impl NetworkContext {
             }
             Err(_) => None,
         };
-        let wlan_ssid = match network_client::ssid("wlan0") {
+        let wlan_ssid = match network_client::ssid(wifi_iface) {
             Ok(ssid) => ssid,
             Err(_) => "Not connected".to_string(),
         };
-        let wlan_state = match network_client::state("wlan0") {
+        let wlan_state = match network_client::state(&uplink_iface) {
             Ok(state) => state,
             Err(_) => "Interface unavailable".to_string(),
         };
-        let wlan_status = match network_client::status("wlan0") {
+        let wlan_status = match network_client::status(wifi_iface) {
             Ok(status) => status,
             Err(_) => "Interface unavailable".to_string(),
         };
-        let wlan_traffic = match network_client::traffic("wlan0") {
-            Ok(traffic) => {
-                let mut t = traffic;
-                // modify traffic values & assign measurement unit
-                // based on received and transmitted values
-                // if received > 999 MB, convert it to GB
-                if t.received > 1_047_527_424 {
-                    t.received /= 1_073_741_824;
-                    t.rx_unit = Some("GB".to_string());
-                } else if t.received > 0 {
-                    // otherwise, convert it to MB
-                    t.received = (t.received / 1024) / 1024;
-                    t.rx_unit = Some("MB".to_string());
-                } else {
-                    t.received = 0;
-                    t.rx_unit = Some("MB".to_string());
-                }
-
-                if t.transmitted > 1_047_527_424 {
-                    t.transmitted /= 1_073_741_824;
-                    t.tx_unit = Some("GB".to_string());
-                } else if t.transmitted > 0 {
-                    t.transmitted = (t.transmitted / 1024) / 1024;
-                    t.tx_unit = Some("MB".to_string());
-                } else {
-                    t.transmitted = 0;
-                    t.tx_unit = Some("MB".to_string());
-                }
-                Some(t)
-            }
-            Err(_) => None,
-        };
+        let wlan_traffic = network_client::traffic(&uplink_iface).ok();
 
         NetworkContext {
+            ap_clients,
             ap_ip,
             ap_ssid,
             ap_state,
             ap_traffic,
+            uplink_iface,
+            uplink_kind,
             wlan_ip,
             wlan_rssi,
             wlan_scan,
@@ -576,7 +596,7 @@ impl NetworkAddContext {
 pub struct NetworkAlertContext {
     pub alert: Alert,
     pub back: Option<String>,
-    pub data_total: Data, // combined stored and current wifi traffic in bytes
+    pub data_total: Data, // usage total in bytes (kept up to date by peach-monitor)
     pub flash_name: Option<String>,
     pub flash_msg: Option<String>,
     pub threshold: Threshold,
@@ -586,24 +606,13 @@ pub struct NetworkAlertContext {
 
 impl NetworkAlertContext {
     pub fn build() -> NetworkAlertContext {
-        let alert = monitor::get_alerts().unwrap();
-        // stored wifi data values as bytes
-        let stored_traffic = monitor::get_data().unwrap();
-        let threshold = monitor::get_thresholds().unwrap();
-        // current wifi traffic values as bytes
-        let traffic = match network_client::traffic("wlan0") {
-            Ok(t) => t,
-            Err(_) => Traffic {
-                received: 0,
-                transmitted: 0,
-                rx_unit: None,
-                tx_unit: None,
-            },
-        };
-
-        let current_traffic = traffic.received + traffic.transmitted;
-        let total = stored_traffic.total + current_traffic;
-        let data_total = Data { total };
+        let alert = monitor::get_alerts().unwrap_or_default();
+        // stored usage total as bytes; peach-monitor samples the interface
+        // counters, so the current traffic is already included
+        let data_total = monitor::get_data().unwrap_or_default();
+        let threshold = monitor::get_thresholds().unwrap_or_default();
+        // current uplink traffic values as bytes
+        let traffic = network_client::traffic(&network_client::uplink_iface()).unwrap_or_default();
 
         NetworkAlertContext {
             alert,
@@ -686,38 +695,7 @@ impl NetworkDetailContext {
             Ok(status) => status,
             Err(_) => "Interface unavailable".to_string(),
         };
-        let wlan_traffic = match network_client::traffic("wlan0") {
-            Ok(traffic) => {
-                let mut t = traffic;
-                // modify traffic values & assign measurement unit
-                // based on received and transmitted values
-                // if received > 999 MB, convert it to GB
-                if t.received > 1_047_527_424 {
-                    t.received /= 1_073_741_824;
-                    t.rx_unit = Some("GB".to_string());
-                } else if t.received > 0 {
-                    // otherwise, convert it to MB
-                    t.received = (t.received / 1024) / 1024;
-                    t.rx_unit = Some("MB".to_string());
-                } else {
-                    t.received = 0;
-                    t.rx_unit = Some("MB".to_string());
-                }
-
-                if t.transmitted > 1_047_527_424 {
-                    t.transmitted /= 1_073_741_824;
-                    t.tx_unit = Some("GB".to_string());
-                } else if t.transmitted > 0 {
-                    t.transmitted = (t.transmitted / 1024) / 1024;
-                    t.tx_unit = Some("MB".to_string());
-                } else {
-                    t.transmitted = 0;
-                    t.tx_unit = Some("MB".to_string());
-                }
-                Some(t)
-            }
-            Err(_) => None,
-        };
+        let wlan_traffic = network_client::traffic("wlan0").ok();
         // create a hashmap to combine wlan_list & wlan_scan without repetition
         let mut wlan_networks = HashMap::new();
         for ap in wlan_scan {
@@ -765,6 +743,16 @@ impl NetworkDetailContext {
     }
 }
 
+// used in /network/wifi to display saved networks in order of preference,
+// followed by any unsaved networks which are in range
+#[derive(Debug, Serialize)]
+pub struct NetworkListItem {
+    pub priority: Option<i32>,
+    pub saved: bool,
+    pub ssid: String,
+    pub state: String,
+}
+
 #[derive(Debug, Serialize)]
 pub struct NetworkListContext {
     pub ap_state: String,
@@ -772,13 +760,14 @@ pub struct NetworkListContext {
     pub flash_name: Option<String>,
     pub flash_msg: Option<String>,
     pub title: Option<String>,
-    pub wlan_networks: HashMap<String, String>,
+    pub wlan_networks: Vec<NetworkListItem>,
     pub wlan_ssid: String,
 }
 
 impl NetworkListContext {
     pub fn build() -> NetworkListContext {
         // list of networks saved in the wpa_supplicant.conf
+        // (sorted from highest to lowest priority by peach-network)
         let wlan_list = match network_client::saved_networks() {
             Ok(ssids) => {
                 let networks: Vec<Networks> = serde_json::from_str(ssids.as_str())
@@ -803,16 +792,32 @@ impl NetworkListContext {
             Err(_) => "Not connected".to_string(),
         };
 
-        // create a hashmap to combine wlan_list & wlan_scan without repetition
-        let mut wlan_networks = HashMap::new();
-        for ap in wlan_scan {
-            wlan_networks.insert(ap.ssid, "Available".to_string());
-        }
+        // combine wlan_list & wlan_scan without repetition, retaining the
+        // priority order of the saved networks
+        let mut wlan_networks: Vec<NetworkListItem> = Vec::new();
         for network in wlan_list {
-            // insert ssid (with state) only if it doesn't already exist
-            wlan_networks
-                .entry(network.ssid)
-                .or_insert_with(|| "Not in range".to_string());
+            let state = if wlan_scan.iter().any(|ap| ap.ssid == network.ssid) {
+                "Available".to_string()
+            } else {
+                "Not in range".to_string()
+            };
+            wlan_networks.push(NetworkListItem {
+                priority: network.priority,
+                saved: true,
+                ssid: network.ssid,
+                state,
+            });
+        }
+        for ap in wlan_scan {
+            // insert ssid only if it doesn't already exist
+            if !wlan_networks.iter().any(|network| network.ssid == ap.ssid) {
+                wlan_networks.push(NetworkListItem {
+                    priority: None,
+                    saved: false,
+                    ssid: ap.ssid,
+                    state: "Available".to_string(),
+                });
+            }
         }
 
         let ap_state = match network_client::state("ap0") {
@@ -0,0 +1,21 @@
+//! Custom Tera filters for the HTML templates.
+
+use std::collections::HashMap;
+
+use peach_lib::byte_size::ByteSize;
+use rocket_contrib::templates::tera::{Result, Value};
+
+/// Format a number of bytes with binary units (eg. `{{ traffic.received |
+/// bytes }}` renders `1.5 GiB`).
+pub fn bytes(value: Value, _: HashMap<String, Value>) -> Result<Value> {
+    let bytes = match value.as_u64() {
+        Some(bytes) => bytes,
+        // sizes calculated in templates may be floats
+        None => match value.as_f64() {
+            Some(bytes) if bytes >= 0.0 => bytes.round() as u64,
+            _ => return Err(format!("Filter `bytes` received a non-size value: {}", value).into()),
+        },
+    };
+
+    Ok(Value::String(ByteSize(bytes).to_string()))
+}
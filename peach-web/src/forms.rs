@@ -1,8 +1,11 @@
 //! Provides data structures which are used to parse forms from post requests.
 //!
-use rocket::request::FromForm;
+use peach_lib::byte_size::{ByteSize, MIB};
+use peach_lib::monitor::Threshold;
+use rocket::http::RawStr;
+use rocket::request::{FromForm, FromFormValue};
 use rocket::UriDisplayQuery;
-use serde::Deserialize;
+use serde::{Deserialize, Deserializer};
 
 #[derive(Debug, Deserialize, FromForm)]
 pub struct DnsForm {
@@ -11,6 +14,11 @@ pub struct DnsForm {
     pub dynamic_domain: String,
 }
 
+#[derive(Debug, Deserialize, FromForm)]
+pub struct Mac {
+    pub mac: String,
+}
+
 #[derive(Debug, Deserialize, FromForm)]
 pub struct PasswordForm {
     pub old_password: String,
@@ -30,6 +38,67 @@ pub struct Ssid {
     pub ssid: String,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct SsidOrder {
+    pub ssids: Vec<String>,
+}
+
+/// A traffic threshold, given with a unit (eg. `2.5GB`) or as a number of
+/// megabytes.
+#[derive(Debug)]
+pub struct ThresholdSize(pub ByteSize);
+
+impl<'v> FromFormValue<'v> for ThresholdSize {
+    type Error = &'v RawStr;
+
+    fn from_form_value(value: &'v RawStr) -> Result<ThresholdSize, &'v RawStr> {
+        let decoded = value.url_decode().map_err(|_| value)?;
+        ByteSize::parse_with_unit(&decoded, MIB)
+            .map(ThresholdSize)
+            .map_err(|_| value)
+    }
+}
+
+impl<'de> Deserialize<'de> for ThresholdSize {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<ThresholdSize, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Megabytes(f64),
+            Text(String),
+        }
+
+        let size = match Repr::deserialize(deserializer)? {
+            Repr::Megabytes(mb) => ByteSize::parse_with_unit(&mb.to_string(), MIB),
+            Repr::Text(text) => ByteSize::parse_with_unit(&text, MIB),
+        }
+        .map_err(serde::de::Error::custom)?;
+
+        Ok(ThresholdSize(size))
+    }
+}
+
+#[derive(Debug, Deserialize, FromForm)]
+pub struct ThresholdForm {
+    pub warn: ThresholdSize,
+    pub cut: ThresholdSize,
+    pub warn_flag: bool,
+    pub cut_flag: bool,
+    pub reset_day: u64,
+}
+
+impl From<ThresholdForm> for Threshold {
+    fn from(form: ThresholdForm) -> Threshold {
+        Threshold {
+            warn: form.warn.0,
+            cut: form.cut.0,
+            warn_flag: form.warn_flag,
+            cut_flag: form.cut_flag,
+            reset_day: form.reset_day,
+        }
+    }
+}
+
 #[derive(Debug, Deserialize, FromForm)]
 pub struct WiFi {
     pub ssid: String,
@@ -10,7 +10,10 @@
 //! | POST   | /api/v1/device/reboot            | Reboot device                 |
 //! | POST   | /api/v1/device/shutdown          | Shutdown device               |
 //! | POST   | /api/v1/network/activate_ap      |                               |
+//! | POST   | /api/v1/network/ap/deauthenticate | Disconnect station from AP   |
+//! | GET    | /api/v1/network/ap/stations      | List stations connected to AP |
 //! | POST   | /api/v1/network/activate_client  |                               |
+//! | GET    | /api/v1/network/interfaces       | List network interfaces       |
 //! | GET    | /api/v1/network/ip               |                               |
 //! | GET    | /api/v1/network/rssi             |                               |
 //! | GET    | /api/v1/network/ssid             |                               |
@@ -22,8 +25,11 @@
 //! | POST   | /api/v1/network/wifi/disconnect  | Disconnect WiFi access point  |
 //! | POST   | /api/v1/network/wifi/forget      | Forget / remove network       |
 //! | POST   | /api/v1/network/wifi/modify      | Modify network password       |
+//! | POST   | /api/v1/network/wifi/order       | Set preferred network order   |
 //! | POST   | /api/v1/network/wifi/usage       | Update alert thresholds       |
+//! | POST   | /api/v1/network/wifi/usage/override | Override data usage cutoff |
 //! | POST   | /api/v1/network/wifi/usage/reset | Reset stored data usage total |
+//! | GET    | /api/v1/network/wifi/usage/history | Daily, monthly & cycle usage |
 //! | GET    | /api/v1/ping                     |                               |
 //! | GET    | /api/v1/ping/network             | Ping `peach-network`          |
 //! | GET    | /api/v1/ping/oled                | Ping `peach-oled`             |
@@ -42,13 +48,13 @@ use peach_lib::dyndns_client::is_dns_updater_online;
 use peach_lib::network_client;
 use peach_lib::oled_client;
 use peach_lib::stats_client;
-use peach_lib::stats_client::Traffic;
 
 use crate::common::{save_dns_configuration, save_password_form, save_reset_password_form};
 use crate::device;
-use crate::forms::{DnsForm, PasswordForm, ResetPasswordForm, Ssid, WiFi};
+use crate::forms::{
+    DnsForm, Mac, PasswordForm, ResetPasswordForm, Ssid, SsidOrder, ThresholdForm, WiFi,
+};
 use crate::monitor;
-use crate::monitor::Threshold;
 
 #[derive(Serialize)]
 pub struct JsonResponse {
@@ -131,10 +137,64 @@ pub fn activate_client() -> Json<JsonResponse> {
     }
 }
 
+#[post("/api/v1/network/ap/deauthenticate", data = "<station>")]
+pub fn deauthenticate_station_json(station: Json<Mac>) -> Json<JsonResponse> {
+    // disconnect the station from the access point
+    match network_client::deauthenticate("ap0", &station.mac) {
+        Ok(_) => {
+            debug!("Deauthenticated station {}.", station.mac);
+            let status = "success".to_string();
+            let msg = "Device disconnected.".to_string();
+            Json(build_json_response(status, None, Some(msg)))
+        }
+        Err(_) => {
+            warn!("Failed to deauthenticate station {}.", station.mac);
+            let status = "error".to_string();
+            let msg = "Failed to disconnect device.".to_string();
+            Json(build_json_response(status, None, Some(msg)))
+        }
+    }
+}
+
+#[get("/api/v1/network/ap/stations")]
+pub fn return_stations() -> Json<JsonResponse> {
+    // retrieve the stations associated with the access point
+    match network_client::stations("ap0") {
+        Ok(stations) => {
+            let status = "success".to_string();
+            let data = json!(stations);
+            Json(build_json_response(status, Some(data), None))
+        }
+        Err(_) => {
+            let status = "error".to_string();
+            let msg = "Access point is not currently active.".to_string();
+            Json(build_json_response(status, None, Some(msg)))
+        }
+    }
+}
+
+#[get("/api/v1/network/interfaces")]
+pub fn return_interfaces() -> Json<JsonResponse> {
+    // retrieve kind, state and addresses for all network interfaces
+    match network_client::list_interfaces() {
+        Ok(interfaces) => {
+            let status = "success".to_string();
+            let data = json!(interfaces);
+            Json(build_json_response(status, Some(data), None))
+        }
+        Err(_) => {
+            let status = "error".to_string();
+            let msg = "Failed to retrieve network interfaces.".to_string();
+            Json(build_json_response(status, None, Some(msg)))
+        }
+    }
+}
+
 #[get("/api/v1/network/ip")]
 pub fn return_ip() -> Json<JsonResponse> {
-    // retrieve ip for wlan0 or set to x.x.x.x if not found
-    let wlan_ip = match network_client::ip("wlan0") {
+    let uplink = network_client::uplink_iface();
+    // retrieve ip for the uplink interface or set to x.x.x.x if not found
+    let uplink_ip = match network_client::ip(&uplink) {
         Ok(ip) => ip,
         Err(_) => "x.x.x.x".to_string(),
     };
@@ -143,9 +203,17 @@ pub fn return_ip() -> Json<JsonResponse> {
         Ok(ip) => ip,
         Err(_) => "x.x.x.x".to_string(),
     };
+    // wlan0 is always included, for clients which predate the uplink field
+    let wlan_ip = if uplink == "wlan0" {
+        uplink_ip.clone()
+    } else {
+        network_client::ip("wlan0").unwrap_or_else(|_| "x.x.x.x".to_string())
+    };
     let data = json!({
         "wlan0": wlan_ip,
-        "ap0": ap_ip
+        uplink.as_str(): uplink_ip,
+        "ap0": ap_ip,
+        "uplink": uplink
     });
     let status = "success".to_string();
     Json(build_json_response(status, Some(data), None))
@@ -187,19 +255,28 @@ pub fn return_ssid() -> Json<JsonResponse> {
 
 #[get("/api/v1/network/state")]
 pub fn return_state() -> Json<JsonResponse> {
-    // retrieve state of wlan0 or set to x.x.x.x if not found
-    let wlan_state = match network_client::state("wlan0") {
+    let uplink = network_client::uplink_iface();
+    // retrieve state of the uplink interface or set to unavailable if not found
+    let uplink_state = match network_client::state(&uplink) {
         Ok(state) => state,
         Err(_) => "unavailable".to_string(),
     };
-    // retrieve state for ap0 or set to x.x.x.x if not found
+    // retrieve state for ap0 or set to unavailable if not found
     let ap_state = match network_client::state("ap0") {
         Ok(state) => state,
         Err(_) => "unavailable".to_string(),
     };
+    // wlan0 is always included, for clients which predate the uplink field
+    let wlan_state = if uplink == "wlan0" {
+        uplink_state.clone()
+    } else {
+        network_client::state("wlan0").unwrap_or_else(|_| "unavailable".to_string())
+    };
     let data = json!({
         "wlan0": wlan_state,
-        "ap0": ap_state
+        uplink.as_str(): uplink_state,
+        "ap0": ap_state,
+        "uplink": uplink
     });
     let status = "success".to_string();
     Json(build_json_response(status, Some(data), None))
@@ -349,9 +426,28 @@ pub fn modify_password(wifi: Json<WiFi>) -> Json<JsonResponse> {
     }
 }
 
+#[post("/api/v1/network/wifi/order", data = "<order>")]
+pub fn order_networks(order: Json<SsidOrder>) -> Json<JsonResponse> {
+    // assign descending priorities to the saved networks in the given order
+    match network_client::reorder("wlan0", &order.ssids) {
+        Ok(_) => {
+            debug!("Updated the preferred order of saved networks.");
+            let status = "success".to_string();
+            let msg = "Network order updated.".to_string();
+            Json(build_json_response(status, None, Some(msg)))
+        }
+        Err(_) => {
+            warn!("Failed to update the preferred order of saved networks.");
+            let status = "error".to_string();
+            let msg = "Failed to update network order.".to_string();
+            Json(build_json_response(status, None, Some(msg)))
+        }
+    }
+}
+
 #[post("/api/v1/network/wifi/usage", data = "<thresholds>")]
-pub fn update_wifi_alerts(thresholds: Json<Threshold>) -> Json<JsonResponse> {
-    match monitor::update_store(thresholds.into_inner()) {
+pub fn update_wifi_alerts(thresholds: Json<ThresholdForm>) -> Json<JsonResponse> {
+    match monitor::update_store(thresholds.into_inner().into()) {
         Ok(_) => {
             debug!("WiFi data usage thresholds updated.");
             let status = "success".to_string();
@@ -367,23 +463,48 @@ pub fn update_wifi_alerts(thresholds: Json<Threshold>) -> Json<JsonResponse> {
     }
 }
 
+#[post("/api/v1/network/wifi/usage/override")]
+pub fn override_cutoff() -> Json<JsonResponse> {
+    match monitor::override_cutoff() {
+        Ok(_) => {
+            debug!("Overrode network data usage cutoff.");
+            let status = "success".to_string();
+            let msg = "Restored network connectivity.".to_string();
+            Json(build_json_response(status, None, Some(msg)))
+        }
+        Err(_) => {
+            warn!("Failed to override network data usage cutoff.");
+            let status = "error".to_string();
+            let msg = "Failed to restore network connectivity.".to_string();
+            Json(build_json_response(status, None, Some(msg)))
+        }
+    }
+}
+
+#[get("/api/v1/network/wifi/usage/history")]
+pub fn return_usage_history() -> Json<JsonResponse> {
+    match monitor::get_history() {
+        Ok(history) => {
+            let status = "success".to_string();
+            let data = json!(history);
+            Json(build_json_response(status, Some(data), None))
+        }
+        Err(_) => {
+            warn!("Failed to retrieve network data usage history.");
+            let status = "error".to_string();
+            let msg = "Failed to retrieve network data usage history.".to_string();
+            Json(build_json_response(status, None, Some(msg)))
+        }
+    }
+}
+
 #[post("/api/v1/network/wifi/usage/reset")]
 pub fn reset_data_total() -> Json<JsonResponse> {
     match monitor::reset_data() {
         Ok(_) => {
             debug!("Reset network data usage total.");
-            let traffic = match network_client::traffic("wlan0") {
-                Ok(t) => t,
-                Err(_) => Traffic {
-                    received: 0,
-                    transmitted: 0,
-                    rx_unit: None,
-                    tx_unit: None,
-                },
-            };
-            // current wifi traffic values as bytes
-            let current_traffic = traffic.received + traffic.transmitted;
-            let data = json!(current_traffic);
+            // the stored usage total (bytes) starts counting from zero
+            let data = json!(0);
             let status = "success".to_string();
             let msg = "Reset network data usage total.".to_string();
             Json(build_json_response(status, Some(data), Some(msg)))
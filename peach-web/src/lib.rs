@@ -32,8 +32,10 @@ pub mod common;
 pub mod context;
 pub mod device;
 pub mod error;
+mod filters;
 pub mod forms;
 pub mod json_api;
+pub mod metrics;
 pub mod monitor;
 pub mod routes;
 #[cfg(test)]
@@ -62,8 +64,10 @@ fn rocket() -> rocket::Rocket {
                 add_credentials,                 // WEB ROUTE
                 connect_wifi,                    // WEB ROUTE
                 disconnect_wifi,                 // WEB ROUTE
+                deauthenticate_station,          // WEB ROUTE
                 deploy_ap,                       // WEB ROUTE
                 deploy_client,                   // WEB ROUTE
+                device_display,                  // WEB ROUTE
                 device_stats,                    // WEB ROUTE
                 files,                           // WEB ROUTE
                 forget_wifi,                     // WEB ROUTE
@@ -72,6 +76,8 @@ fn rocket() -> rocket::Rocket {
                 login,                           // WEB ROUTE
                 logout,                          // WEB ROUTE
                 messages,                        // WEB ROUTE
+                prometheus_metrics,              // WEB ROUTE
+                network_ap,                      // WEB ROUTE
                 network_home,                    // WEB ROUTE
                 network_add_ssid,                // WEB ROUTE
                 network_add_wifi,                // WEB ROUTE
@@ -86,6 +92,7 @@ fn rocket() -> rocket::Rocket {
                 wifi_set_password,               // WEB ROUTE
                 wifi_usage,                      // WEB ROUTE
                 wifi_usage_alerts,               // WEB ROUTE
+                wifi_usage_override,             // WEB ROUTE
                 wifi_usage_reset,                // WEB ROUTE
                 configure_dns,                   // WEB ROUTE
                 configure_dns_post,              // WEB ROUTE
@@ -102,15 +109,21 @@ fn rocket() -> rocket::Rocket {
                 activate_client,                 // JSON API
                 add_wifi,                        // JSON API
                 connect_ap,                      // JSON API
+                deauthenticate_station_json,     // JSON API
                 disconnect_ap,                   // JSON API
                 forget_ap,                       // JSON API
                 modify_password,                 // JSON API
+                order_networks,                  // JSON API
                 ping_pong,                       // JSON API
                 test_route,                      // JSON API
                 ping_network,                    // JSON API
                 ping_oled,                       // JSON API
                 ping_stats,                      // JSON API
+                override_cutoff,                 // JSON API
                 reset_data_total,                // JSON API
+                return_usage_history,            // JSON API
+                return_interfaces,               // JSON API
+                return_stations,                 // JSON API
                 return_ip,                       // JSON API
                 return_rssi,                     // JSON API
                 return_ssid,                     // JSON API
@@ -126,7 +139,9 @@ fn rocket() -> rocket::Rocket {
             ],
         )
         .register(catchers![not_found, internal_error])
-        .attach(Template::fairing())
+        .attach(Template::custom(|engines| {
+            engines.tera.register_filter("bytes", filters::bytes);
+        }))
 }
 
 // launch the rocket server
@@ -0,0 +1,301 @@
+//! Prometheus metrics exporter.
+//!
+//! Device statistics are gathered from the PeachCloud microservices and
+//! rendered in the Prometheus text exposition format for the `/metrics`
+//! route. Access is restricted by the `MetricsAccess` request guard, which
+//! accepts requests bearing the `metrics_token` defined in the PeachCloud
+//! config or originating from one of the `metrics_allowed_hosts`.
+
+use std::fmt::Write;
+use std::net::IpAddr;
+use std::process::Command;
+
+use peach_lib::config_manager;
+use peach_lib::dyndns_client;
+use peach_lib::network_client;
+use peach_lib::sbot_client;
+use peach_lib::stats_client;
+use rocket::http::Status;
+use rocket::request::{self, FromRequest, Request};
+use rocket::Outcome;
+
+/// Microservices probed by `peach-probe`.
+const SERVICES: [&str; 6] = [
+    "peach-oled",
+    "peach-network",
+    "peach-stats",
+    "peach-menu",
+    "peach-web",
+    "peach-buttons",
+];
+
+/// Request guard which succeeds if the request may read the metrics.
+pub struct MetricsAccess;
+
+impl<'a, 'r> FromRequest<'a, 'r> for MetricsAccess {
+    type Error = ();
+
+    fn from_request(request: &'a Request<'r>) -> request::Outcome<Self, Self::Error> {
+        let config = match config_manager::load_peach_config() {
+            Ok(config) => config,
+            Err(_) => return Outcome::Failure((Status::Forbidden, ())),
+        };
+
+        // `Authorization: Bearer <token>`
+        let token = request
+            .headers()
+            .get_one("Authorization")
+            .and_then(|header| header.strip_prefix("Bearer "));
+        if let Some(token) = token {
+            if !config.metrics_token.is_empty() && token_eq(token, &config.metrics_token) {
+                return Outcome::Success(MetricsAccess);
+            }
+        }
+
+        // the client IP is the address of the peer, unless the request was
+        // proxied by nginx on this host, in which case it is taken from the
+        // `X-Real-IP` header set by the proxy (the header is not trusted from
+        // any other peer, since clients can set it themselves)
+        let client_ip = match request.remote().map(|addr| addr.ip()) {
+            Some(peer) if peer.is_loopback() => request.real_ip().or(Some(peer)),
+            peer => peer,
+        };
+        if let Some(ip) = client_ip {
+            if config
+                .metrics_allowed_hosts
+                .iter()
+                .any(|host| host.parse::<IpAddr>().map_or(false, |host| host == ip))
+            {
+                return Outcome::Success(MetricsAccess);
+            }
+        }
+
+        Outcome::Failure((Status::Forbidden, ()))
+    }
+}
+
+/// Compare tokens in constant time (for tokens of equal length).
+fn token_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0, |diff, (x, y)| diff | (x ^ y))
+            == 0
+}
+
+/// Metrics in the Prometheus text exposition format.
+#[derive(Default)]
+struct Metrics {
+    out: String,
+}
+
+impl Metrics {
+    /// Add a metric family with one sample per label value (or a single
+    /// sample without labels).
+    fn add(&mut self, name: &str, kind: &str, help: &str, samples: &[(Option<(&str, &str)>, f64)]) {
+        if samples.is_empty() {
+            return;
+        }
+        let _ = writeln!(self.out, "# HELP {} {}", name, help);
+        let _ = writeln!(self.out, "# TYPE {} {}", name, kind);
+        for (label, value) in samples {
+            let _ = match label {
+                Some((key, val)) => writeln!(
+                    self.out,
+                    "{}{{{}=\"{}\"}} {}",
+                    name,
+                    key,
+                    escape(val),
+                    value
+                ),
+                None => writeln!(self.out, "{} {}", name, value),
+            };
+        }
+    }
+}
+
+/// Escape a label value.
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Check whether a service is running, in the same way as `peach-probe`.
+fn service_up(service: &str) -> bool {
+    Command::new("/usr/bin/systemctl")
+        .arg("status")
+        .arg(service)
+        .output()
+        .map(|output| output.status.success())
+        .unwrap_or(false)
+}
+
+/// Gather the device statistics and render them. Statistics which cannot be
+/// retrieved (for example, because a microservice is not running) are
+/// omitted.
+pub fn render() -> String {
+    let mut metrics = Metrics::default();
+
+    if let Ok(cpu) = stats_client::cpu_stats_percent() {
+        metrics.add(
+            "peach_cpu_usage_percent",
+            "gauge",
+            "CPU usage by mode since boot (percent).",
+            &[
+                (Some(("mode", "user")), cpu.user.into()),
+                (Some(("mode", "system")), cpu.system.into()),
+                (Some(("mode", "nice")), cpu.nice.into()),
+                (Some(("mode", "idle")), cpu.idle.into()),
+            ],
+        );
+    }
+
+    if let Ok(mem) = stats_client::mem_stats() {
+        // memory statistics are given in kilobytes
+        metrics.add(
+            "peach_memory_bytes",
+            "gauge",
+            "Memory by state (bytes).",
+            &[
+                (Some(("state", "total")), (mem.total * 1024) as f64),
+                (Some(("state", "used")), (mem.used * 1024) as f64),
+                (Some(("state", "free")), (mem.free * 1024) as f64),
+            ],
+        );
+    }
+
+    if let Ok(disks) = stats_client::disk_usage() {
+        let size: Vec<_> = disks
+            .iter()
+            .map(|d| {
+                (
+                    Some(("mountpoint", d.mountpoint.as_str())),
+                    d.size.as_u64() as f64,
+                )
+            })
+            .collect();
+        let used: Vec<_> = disks
+            .iter()
+            .map(|d| {
+                (
+                    Some(("mountpoint", d.mountpoint.as_str())),
+                    d.used.as_u64() as f64,
+                )
+            })
+            .collect();
+        metrics.add(
+            "peach_filesystem_size_bytes",
+            "gauge",
+            "Filesystem size (bytes).",
+            &size,
+        );
+        metrics.add(
+            "peach_filesystem_used_bytes",
+            "gauge",
+            "Filesystem space used (bytes).",
+            &used,
+        );
+    }
+
+    if let Ok(load) = stats_client::load_average() {
+        metrics.add(
+            "peach_load_average",
+            "gauge",
+            "System load average.",
+            &[
+                (Some(("period", "1m")), load.one.into()),
+                (Some(("period", "5m")), load.five.into()),
+                (Some(("period", "15m")), load.fifteen.into()),
+            ],
+        );
+    }
+
+    if let Ok(minutes) = stats_client::uptime() {
+        if let Ok(minutes) = minutes.parse::<u64>() {
+            metrics.add(
+                "peach_uptime_seconds",
+                "gauge",
+                "System uptime (seconds, minute precision).",
+                &[(None, (minutes * 60) as f64)],
+            );
+        }
+    }
+
+    if let Ok(ifaces) = network_client::list_interfaces() {
+        let mut received = Vec::new();
+        let mut transmitted = Vec::new();
+        let mut rssi = Vec::new();
+        for iface in ifaces.iter().filter(|iface| iface.kind != "loopback") {
+            if let Ok(traffic) = network_client::traffic(&iface.name) {
+                received.push((
+                    Some(("iface", iface.name.as_str())),
+                    traffic.received.as_u64() as f64,
+                ));
+                transmitted.push((
+                    Some(("iface", iface.name.as_str())),
+                    traffic.transmitted.as_u64() as f64,
+                ));
+            }
+            if iface.kind == "wifi" {
+                if let Ok(Ok(dbm)) = network_client::rssi(&iface.name).map(|dbm| dbm.parse::<f64>())
+                {
+                    rssi.push((Some(("iface", iface.name.as_str())), dbm));
+                }
+            }
+        }
+        metrics.add(
+            "peach_network_receive_bytes_total",
+            "counter",
+            "Bytes received by interface.",
+            &received,
+        );
+        metrics.add(
+            "peach_network_transmit_bytes_total",
+            "counter",
+            "Bytes transmitted by interface.",
+            &transmitted,
+        );
+        metrics.add(
+            "peach_wifi_rssi_dbm",
+            "gauge",
+            "Signal strength of the connected WiFi network (dBm).",
+            &rssi,
+        );
+    }
+
+    let services: Vec<_> = SERVICES
+        .iter()
+        .map(|service| {
+            let up = if service_up(service) { 1.0 } else { 0.0 };
+            (Some(("service", *service)), up)
+        })
+        .collect();
+    metrics.add(
+        "peach_service_up",
+        "gauge",
+        "Whether the microservice is running (1) or not (0).",
+        &services,
+    );
+
+    if let Ok(Some(secs)) = dyndns_client::get_num_seconds_since_successful_dns_update() {
+        metrics.add(
+            "peach_dyndns_seconds_since_update",
+            "gauge",
+            "Time since the last successful dynamic DNS update (seconds).",
+            &[(None, secs as f64)],
+        );
+    }
+
+    if let Ok(online) = sbot_client::is_sbot_online() {
+        metrics.add(
+            "peach_sbot_up",
+            "gauge",
+            "Whether the Scuttlebutt server is running (1) or not (0).",
+            &[(None, if online { 1.0 } else { 0.0 })],
+        );
+    }
+
+    metrics.out
+}
@@ -9,6 +9,7 @@
 //! | ------ | --------------------------- | --------------------------------- |
 //! | GET    | /                           | Home                              |
 //! | GET    | /device                     | Device statistics                 |
+//! | GET    | /device/display.png         | Snapshot of the OLED display      |
 //! | GET    | /device/reboot              | Reboot device                     |
 //! | GET    | /device/shutdown            | Shutdown device                   |
 //! | GET    | /help                       | Help and usage guidelines         |
@@ -16,7 +17,9 @@
 //! | POST   | /login                      | Login form submission             |
 //! | POST   | /logout                     | Logout authenticated user         |
 //! | GET    | /network                    | Network overview                  |
+//! | GET    | /network/ap                 | Access point connected devices    |
 //! | GET    | /network/ap/activate        | Activate WiFi access point mode   |
+//! | POST   | /network/ap/deauthenticate  | Disconnect device from AP         |
 //! | GET    | /network/wifi               | List of networks                  |
 //! | GET    | /network/wifi?<ssid>        | Details of single network         |
 //! | GET    | /network/wifi/activate      | Activate WiFi client mode         |
@@ -30,8 +33,10 @@
 //! | POST   | /network/wifi/modify        | Modify network password           |
 //! | GET    | /network/wifi/usage         | WiFi data usage form              |
 //! | POST   | /network/wifi/usage         | WiFi data usage form submission   |
+//! | GET    | /network/wifi/usage/override | Override data usage cutoff       |
 //! | GET    | /network/wifi/usage/reset   | Reset stored data usage total     |
 //! | GET    | /messages                   | Private Scuttlebutt messages      |
+//! | GET    | /metrics                    | Prometheus metrics                |
 //! | GET    | /peers                      | Scuttlebutt peers overview        |
 //! | GET    | /profile                    | Scuttlebutt user profile          |
 //! | GET    | /shutdown                   | Shutdown menu                     |
@@ -48,14 +53,16 @@ use std::path::{Path, PathBuf};
 
 use log::{debug, info, warn};
 use percent_encoding::percent_decode;
-use rocket::http::RawStr;
+use rocket::http::{ContentType, RawStr};
 use rocket::request::{FlashMessage, Form};
+use rocket::response::content::Content;
 use rocket::response::{Flash, NamedFile, Redirect};
 use rocket::{catch, get, post, uri};
 use rocket_contrib::templates::Template;
 
 use peach_lib::config_manager;
 use peach_lib::network_client;
+use peach_lib::oled_client;
 use peach_lib::password_utils;
 
 use crate::common::{
@@ -64,16 +71,17 @@ use crate::common::{
 use crate::context::{
     AddAdminContext, ChangePasswordContext, ConfigureAdminContext, ConfigureDNSContext,
     DeviceContext, ErrorContext, HelpContext, HomeContext, LoginContext, MessageContext,
-    NetworkAddContext, NetworkAlertContext, NetworkContext, NetworkDetailContext,
+    NetworkAddContext, NetworkAlertContext, NetworkApContext, NetworkContext, NetworkDetailContext,
     NetworkListContext, PeerContext, ProfileContext, ResetPasswordContext,
     SendPasswordResetContext, ShutdownContext,
 };
 use crate::device;
 use crate::forms::{
-    AddAdminForm, DeleteAdminForm, DnsForm, PasswordForm, ResetPasswordForm, Ssid, WiFi,
+    AddAdminForm, DeleteAdminForm, DnsForm, Mac, PasswordForm, ResetPasswordForm, Ssid,
+    ThresholdForm, WiFi,
 };
+use crate::metrics::{self, MetricsAccess};
 use crate::monitor;
-use crate::monitor::Threshold;
 
 #[get("/")]
 pub fn index() -> Template {
@@ -101,6 +109,18 @@ pub fn device_stats(flash: Option<FlashMessage>) -> Template {
     Template::render("device", &context)
 }
 
+// serves the frame currently shown on the oled display (404 if unavailable)
+#[get("/device/display.png")]
+pub fn device_display() -> Option<Content<Vec<u8>>> {
+    match oled_client::snapshot("png") {
+        Ok(snapshot) => Some(Content(ContentType::PNG, snapshot.bytes)),
+        Err(e) => {
+            warn!("Failed to take a snapshot of the display: {}", e);
+            None
+        }
+    }
+}
+
 #[get("/device/reboot")]
 pub fn reboot_cmd() -> Flash<Redirect> {
     match device::reboot() {
@@ -179,6 +199,31 @@ pub fn network_home(flash: Option<FlashMessage>) -> Template {
     Template::render("network_card", &context)
 }
 
+#[get("/network/ap")]
+pub fn network_ap(flash: Option<FlashMessage>) -> Template {
+    // assign context through context_builder call
+    let mut context = NetworkApContext::build();
+    context.back = Some("/network".to_string());
+    context.title = Some("Connected Devices".to_string());
+    // check to see if there is a flash message to display
+    if let Some(flash) = flash {
+        // add flash message contents to the context object
+        context.flash_name = Some(flash.name().to_string());
+        context.flash_msg = Some(flash.msg().to_string());
+    };
+    // template_dir is set in Rocket.toml
+    Template::render("network_ap", &context)
+}
+
+#[post("/network/ap/deauthenticate", data = "<station>")]
+pub fn deauthenticate_station(station: Form<Mac>) -> Flash<Redirect> {
+    let url = uri!(network_ap);
+    match network_client::deauthenticate("ap0", &station.mac) {
+        Ok(_) => Flash::success(Redirect::to(url), "Device disconnected"),
+        Err(_) => Flash::error(Redirect::to(url), "Failed to disconnect device"),
+    }
+}
+
 #[get("/network/ap/activate")]
 pub fn deploy_ap() -> Flash<Redirect> {
     // activate the wireless access point
@@ -334,8 +379,8 @@ pub fn wifi_usage(flash: Option<FlashMessage>) -> Template {
 }
 
 #[post("/network/wifi/usage", data = "<thresholds>")]
-pub fn wifi_usage_alerts(thresholds: Form<Threshold>) -> Flash<Redirect> {
-    match monitor::update_store(thresholds.into_inner()) {
+pub fn wifi_usage_alerts(thresholds: Form<ThresholdForm>) -> Flash<Redirect> {
+    match monitor::update_store(thresholds.into_inner().into()) {
         Ok(_) => {
             debug!("WiFi data usage thresholds updated.");
             Flash::success(
@@ -575,6 +620,15 @@ pub fn delete_admin_post(delete_admin_form: Form<DeleteAdminForm>) -> Flash<Redi
     }
 }
 
+#[get("/network/wifi/usage/override")]
+pub fn wifi_usage_override() -> Flash<Redirect> {
+    let url = uri!(wifi_usage);
+    match monitor::override_cutoff() {
+        Ok(_) => Flash::success(Redirect::to(url), "Restored network connectivity"),
+        Err(_) => Flash::error(Redirect::to(url), "Failed to restore network connectivity"),
+    }
+}
+
 #[get("/network/wifi/usage/reset")]
 pub fn wifi_usage_reset() -> Flash<Redirect> {
     let url = uri!(wifi_usage);
@@ -714,6 +768,11 @@ pub fn shutdown_menu(flash: Option<FlashMessage>) -> Template {
     Template::render("shutdown", &context)
 }
 
+#[get("/metrics")]
+pub fn prometheus_metrics(_access: MetricsAccess) -> Content<String> {
+    Content(ContentType::Plain, metrics::render())
+}
+
 #[get("/<file..>", rank = 2)]
 pub fn files(file: PathBuf) -> Option<NamedFile> {
     NamedFile::open(Path::new("static/").join(file)).ok()
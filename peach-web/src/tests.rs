@@ -1,7 +1,7 @@
 use std::fs::File;
 use std::io::Read;
 
-use rocket::http::{ContentType, Status};
+use rocket::http::{ContentType, Header, Status};
 use rocket::local::Client;
 
 use super::rocket;
@@ -131,6 +131,13 @@ fn device_html() {
     assert!(body.contains("Statistics"));
 }
 
+#[test]
+fn device_display_unavailable() {
+    let client = Client::new(rocket()).expect("valid rocket instance");
+    let response = client.get("/device/display.png").dispatch();
+    assert_eq!(response.status(), Status::NotFound);
+}
+
 #[test]
 fn help_html() {
     let client = Client::new(rocket()).expect("valid rocket instance");
@@ -200,10 +207,20 @@ fn network_usage_html() {
     let body = response.body_string().unwrap();
     assert!(body.contains("Network Data Usage"));
     assert!(body.contains("WARNING THRESHOLD"));
+    assert!(body.contains("BILLING CYCLE RESET"));
     assert!(body.contains("Update"));
     assert!(body.contains("Cancel"));
 }
 
+#[test]
+fn wifi_usage_override() {
+    let client = Client::new(rocket()).expect("valid rocket instance");
+    let response = client.get("/network/wifi/usage/override").dispatch();
+    // check for 303 status (redirect)
+    assert_eq!(response.status(), Status::SeeOther);
+    assert_eq!(response.content_type(), None);
+}
+
 #[test]
 fn add_credentials() {
     let client = Client::new(rocket()).expect("valid rocket instance");
@@ -240,6 +257,29 @@ fn modify_password() {
     assert_eq!(response.content_type(), None);
 }
 
+#[test]
+fn network_ap_html() {
+    let client = Client::new(rocket()).expect("valid rocket instance");
+    let mut response = client.get("/network/ap").dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.content_type(), Some(ContentType::HTML));
+    let body = response.body_string().unwrap();
+    assert!(body.contains("Connected Devices"));
+    assert!(body.contains("Deploy the access point to view connected devices."));
+}
+
+#[test]
+fn deauthenticate_station() {
+    let client = Client::new(rocket()).expect("valid rocket instance");
+    let response = client
+        .post("/network/ap/deauthenticate")
+        .header(ContentType::Form)
+        .body("mac=aa:bb:cc:dd:ee:ff")
+        .dispatch();
+    assert_eq!(response.status(), Status::SeeOther);
+    assert_eq!(response.content_type(), None);
+}
+
 #[test]
 fn deploy_ap() {
     let client = Client::new(rocket()).expect("valid rocket instance");
@@ -282,6 +322,100 @@ fn activate_client() {
     assert_eq!(response.content_type(), Some(ContentType::JSON));
 }
 
+#[test]
+fn deauthenticate_station_json() {
+    let client = Client::new(rocket()).expect("valid rocket instance");
+    let mut response = client
+        .post("/api/v1/network/ap/deauthenticate")
+        .header(ContentType::JSON)
+        .body(r#"{ "mac": "aa:bb:cc:dd:ee:ff" }"#)
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.content_type(), Some(ContentType::JSON));
+    let body = response.body_string().unwrap();
+    assert!(body.contains("Failed to disconnect device."));
+}
+
+#[test]
+fn return_stations() {
+    let client = Client::new(rocket()).expect("valid rocket instance");
+    let mut response = client
+        .get("/api/v1/network/ap/stations")
+        .header(ContentType::JSON)
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.content_type(), Some(ContentType::JSON));
+    let body = response.body_string().unwrap();
+    assert!(body.contains(r#""status":"error""#));
+    assert!(body.contains("Access point is not currently active."));
+}
+
+#[test]
+fn override_cutoff() {
+    let client = Client::new(rocket()).expect("valid rocket instance");
+    let response = client
+        .post("/api/v1/network/wifi/usage/override")
+        .header(ContentType::JSON)
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.content_type(), Some(ContentType::JSON));
+}
+
+#[test]
+fn metrics_forbidden() {
+    let client = Client::new(rocket()).expect("valid rocket instance");
+    let response = client
+        .get("/metrics")
+        .header(Header::new("Authorization", "Bearer wrong-token"))
+        .dispatch();
+    assert_eq!(response.status(), Status::Forbidden);
+}
+
+#[test]
+fn metrics_spoofed_real_ip_forbidden() {
+    let client = Client::new(rocket()).expect("valid rocket instance");
+    // a remote client claiming to be an allowed host
+    let response = client
+        .get("/metrics")
+        .remote("203.0.113.7:40000".parse().unwrap())
+        .header(Header::new("X-Real-IP", "127.0.0.1"))
+        .dispatch();
+    assert_eq!(response.status(), Status::Forbidden);
+}
+
+#[test]
+fn return_usage_history() {
+    let client = Client::new(rocket()).expect("valid rocket instance");
+    let mut response = client
+        .get("/api/v1/network/wifi/usage/history")
+        .header(ContentType::JSON)
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.content_type(), Some(ContentType::JSON));
+    let body = response.body_string().unwrap();
+    // the shared store under /var/lib is only accessible with permissions
+    if body.contains("success") {
+        assert!(body.contains("daily"));
+        assert!(body.contains("monthly"));
+        assert!(body.contains("cycle"));
+    } else {
+        assert!(body.contains("Failed to retrieve network data usage history."));
+    }
+}
+
+#[test]
+fn return_interfaces() {
+    let client = Client::new(rocket()).expect("valid rocket instance");
+    let mut response = client
+        .get("/api/v1/network/interfaces")
+        .header(ContentType::JSON)
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.content_type(), Some(ContentType::JSON));
+    let body = response.body_string().unwrap();
+    assert!(body.contains("Failed to retrieve network interfaces."));
+}
+
 #[test]
 fn return_ip() {
     let client = Client::new(rocket()).expect("valid rocket instance");
@@ -405,6 +539,20 @@ fn new_password() {
     assert!(body.contains("Failed to update WiFi password."));
 }
 
+#[test]
+fn order_networks() {
+    let client = Client::new(rocket()).expect("valid rocket instance");
+    let mut response = client
+        .post("/api/v1/network/wifi/order")
+        .header(ContentType::JSON)
+        .body(r#"{ "ssids": ["Home", "Office"] }"#)
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.content_type(), Some(ContentType::JSON));
+    let body = response.body_string().unwrap();
+    assert!(body.contains("Failed to update network order."));
+}
+
 #[test]
 fn ping_pong() {
     let client = Client::new(rocket()).expect("valid rocket instance");
@@ -0,0 +1,42 @@
+/*
+
+behavioural layer for the `device.html.tera` template,
+corresponding to the web route `/device`
+
+ - periodically reload the snapshot of the oled display
+ - hide the snapshot if the display becomes unavailable
+
+methods:
+
+ PEACH_DISPLAY.refresh();
+
+*/
+
+var PEACH_DISPLAY = {};
+
+// interval between snapshot requests (milliseconds)
+PEACH_DISPLAY.interval = 2000;
+
+// reload the snapshot image at a regular interval
+PEACH_DISPLAY.refresh = function() {
+    document.addEventListener('DOMContentLoaded', function() {
+        var display = document.getElementById('oledDisplay');
+        var snapshot = document.getElementById('oledSnapshot');
+        if (display && snapshot) {
+            snapshot.addEventListener('error', function() {
+                // peach-oled is unreachable: hide the widget
+                display.style.display = 'none';
+            });
+            snapshot.addEventListener('load', function() {
+                display.style.display = '';
+            });
+            setInterval(function() {
+                // append a timestamp to avoid a cached response
+                snapshot.src = "/device/display.png?t=" + Date.now();
+            }, PEACH_DISPLAY.interval);
+        }
+    });
+}
+
+var displayInstance = PEACH_DISPLAY;
+displayInstance.refresh();
@@ -0,0 +1,124 @@
+/*
+
+behavioural layer for the `network_list.html.tera` template,
+corresponding to the web route `/network/wifi`
+
+ - allow saved networks to be dragged into order of preference
+ - perform json api call with the new order
+ - update the dom
+
+methods:
+
+ PEACH_NETWORK.order();
+ PEACH_NETWORK.saveOrder();
+ PEACH_NETWORK.flashMsg(status, msg);
+
+*/
+
+var PEACH_NETWORK = {};
+
+// make saved networks draggable and save the order after each drop
+PEACH_NETWORK.order = function() {
+    document.addEventListener('DOMContentLoaded', function() {
+        var networkList = document.getElementById('networkList');
+        if (networkList) {
+            var dragged = null;
+            networkList.addEventListener('dragstart', function(e) {
+                // only saved networks are draggable
+                dragged = e.target.closest('li.draggable');
+                if (dragged) {
+                    dragged.classList.add('dragging');
+                    e.dataTransfer.effectAllowed = 'move';
+                    // firefox requires data to be set for dragging to begin
+                    e.dataTransfer.setData('text/plain', dragged.dataset.ssid);
+                }
+            }, false);
+            networkList.addEventListener('dragover', function(e) {
+                var target = e.target.closest('li.draggable');
+                if (dragged && target && target !== dragged) {
+                    e.preventDefault();
+                    // insert above or below the target depending on pointer position
+                    var rect = target.getBoundingClientRect();
+                    var after = (e.clientY - rect.top) > (rect.height / 2);
+                    networkList.insertBefore(dragged, after ? target.nextSibling : target);
+                }
+            }, false);
+            networkList.addEventListener('drop', function(e) {
+                e.preventDefault();
+            }, false);
+            networkList.addEventListener('dragend', function(e) {
+                if (dragged) {
+                    dragged.classList.remove('dragging');
+                    dragged = null;
+                    PEACH_NETWORK.saveOrder();
+                }
+            }, false);
+        };
+    });
+}
+
+// collect the ssids of saved networks in display order and make POST request
+PEACH_NETWORK.saveOrder = function() {
+    var saved = document.querySelectorAll('#networkList li.draggable');
+    var ssids = [];
+    for (var i = 0; i < saved.length; i++) {
+        ssids.push(saved[i].dataset.ssid);
+    }
+    // create key:value pair
+    var orderData = { ssids: ssids };
+    // perform json serialization
+    var jsonData = JSON.stringify(orderData);
+    // write in-progress status message to ui
+    PEACH_NETWORK.flashMsg("info", "Saving network order...");
+    // send order_networks POST request
+    fetch("/api/v1/network/wifi/order", {
+        method: "post",
+        headers: {
+            'Content-Type': 'application/json',
+        },
+        body: jsonData
+    })
+    .then( (response) => {
+        return response.json()
+    })
+    .then( (jsonData) => {
+        // write json response message to ui
+        PEACH_NETWORK.flashMsg(jsonData.status, jsonData.msg);
+    })
+}
+
+// display a message by appending a paragraph element
+PEACH_NETWORK.flashMsg = function(status, msg) {
+    // set the class of the element according to status
+    var elementClass;
+    if (status === "success") {
+        elementClass = "capsule center-text flash-message font-success";
+    } else if (status === "info") {
+        elementClass = "capsule center-text flash-message font-info";
+    } else {
+        elementClass = "capsule center-text flash-message font-failure";
+    };
+
+    var flashElement = document.getElementById("flashMsg");
+    // if flashElement exists, update the class & text
+    if (flashElement) {
+        flashElement.className = elementClass;
+        flashElement.innerText = msg;
+    // if flashElement does not exist, create it, set id, class, text & append
+    } else {
+        // create new div for flash message
+        var flashDiv = document.createElement("DIV");
+        // set div attributes
+        flashDiv.id = "flashMsg";
+        flashDiv.className = elementClass;
+        // add json response message to flash message div
+        var flashMsg = document.createTextNode(msg);
+        flashDiv.appendChild(flashMsg);
+        // insert the flash message div below the network list
+        var networkList = document.getElementById("networkList");
+        networkList.parentNode.insertBefore(flashDiv, networkList.nextSibling);
+    }
+}
+
+var listInstance = PEACH_NETWORK;
+listInstance.order();
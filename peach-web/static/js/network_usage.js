@@ -11,6 +11,9 @@ methods:
 
  PEACH_NETWORK.updateAlerts();
  PEACH_NETWORK.resetUsage();
+ PEACH_NETWORK.restoreUplink();
+ PEACH_NETWORK.usageHistory();
+ PEACH_NETWORK.drawChart(buckets);
  PEACH_NETWORK.toggleWarning();
  PEACH_NETWORK.toggleCutoff();
  PEACH_NETWORK.flashMsg(status, msg);
@@ -31,12 +34,15 @@ PEACH_NETWORK.updateAlerts = function() {
             let cut = formElement.elements.cut.value;
             let warn_flag = formElement.elements.warn_flag.checked;
             let cut_flag = formElement.elements.cut_flag.checked;
-            // perform json serialization
+            let reset_day = formElement.elements.reset_day.value;
+            // perform json serialization (thresholds are parsed by the server,
+            // so they may include a unit, eg. "2.5GB")
             var jsonData = JSON.stringify({
-                "warn": parseFloat(warn),
-                "cut": parseFloat(cut),
+                "warn": warn,
+                "cut": cut,
                 "warn_flag": warn_flag,
                 "cut_flag": cut_flag,
+                "reset_day": parseInt(reset_day) || 0,
             });
             // send update_alerts POST request
             fetch("/api/v1/network/wifi/usage", {
@@ -91,6 +97,105 @@ PEACH_NETWORK.resetUsage = function() {
     });
 }
 
+// catch click of 'Restore Connectivity' and make POST request
+PEACH_NETWORK.restoreUplink = function() {
+    document.addEventListener('DOMContentLoaded', function() {
+        var restoreBtn = document.getElementById('restoreUplink');
+        if (restoreBtn) {
+            restoreBtn.addEventListener('click', function(e) {
+                // prevent redirect on button press (default behavior)
+                e.preventDefault();
+                // send override_cutoff POST request
+                fetch("/api/v1/network/wifi/usage/override", {
+                    method: "post",
+                    headers: {
+                        'Accept': 'application/json',
+                        'Content-Type': 'application/json'
+                    },
+                })
+                .then( (response) => {
+                    return response.json()
+                })
+                .then( (jsonData) => {
+                    // write json response message to ui
+                    PEACH_NETWORK.flashMsg(jsonData.status, jsonData.msg);
+                    // if the override is successful, remove the cutoff notice
+                    if (jsonData.status === "success") {
+                        document.getElementById('cutoffNotice').remove();
+                    }
+                })
+            }, false);
+        }
+    });
+}
+
+// retrieve usage history and redraw the chart when the period changes
+PEACH_NETWORK.usageHistory = function() {
+    document.addEventListener('DOMContentLoaded', function() {
+        var periodSelect = document.getElementById('historyPeriod');
+        if (periodSelect) {
+            var history = null;
+            var draw = function() {
+                PEACH_NETWORK.drawChart(history[periodSelect.value]);
+            };
+            fetch("/api/v1/network/wifi/usage/history", {
+                headers: {
+                    'Accept': 'application/json',
+                },
+            })
+            .then( (response) => {
+                return response.json()
+            })
+            .then( (jsonData) => {
+                if (jsonData.status === "success") {
+                    history = jsonData.data;
+                    draw();
+                    periodSelect.addEventListener('change', draw, false);
+                } else {
+                    PEACH_NETWORK.flashMsg(jsonData.status, jsonData.msg);
+                }
+            })
+        }
+    });
+}
+
+// format a number of bytes with binary units (matches the `bytes` template filter)
+PEACH_NETWORK.formatBytes = function(bytes) {
+    var units = ["B", "KiB", "MiB", "GiB", "TiB"];
+    var i = 0;
+    while (bytes >= Math.pow(1024, i + 1) && i < units.length - 1) {
+        i++;
+    }
+    return i === 0 ? bytes + " B" : (bytes / Math.pow(1024, i)).toFixed(1) + " " + units[i];
+}
+
+// draw a bar for each period, with received and transmitted bytes stacked
+PEACH_NETWORK.drawChart = function(buckets) {
+    var chart = document.getElementById('usageChart');
+    chart.innerHTML = "";
+    if (!buckets || buckets.length === 0) {
+        chart.innerText = "No usage history recorded yet.";
+        return;
+    }
+    var max = Math.max.apply(null, buckets.map(function(b) { return b.rx + b.tx; }));
+    buckets.forEach(function(bucket) {
+        var total = bucket.rx + bucket.tx;
+        var bar = document.createElement("DIV");
+        bar.className = "chart-bar";
+        bar.title = bucket.period + ": " + PEACH_NETWORK.formatBytes(bucket.rx) + " down, "
+            + PEACH_NETWORK.formatBytes(bucket.tx) + " up";
+        var rx = document.createElement("DIV");
+        rx.className = "chart-bar-rx";
+        rx.style.height = (max ? bucket.rx / max * 100 : 0) + "%";
+        var tx = document.createElement("DIV");
+        tx.className = "chart-bar-tx";
+        tx.style.height = (max ? bucket.tx / max * 100 : 0) + "%";
+        bar.appendChild(tx);
+        bar.appendChild(rx);
+        chart.appendChild(bar);
+    });
+}
+
 // update data usage total in ui
 PEACH_NETWORK.updateTotal = function(data) {
     document.addEventListener('DOMContentLoaded', function() {
@@ -168,6 +273,8 @@ PEACH_NETWORK.flashMsg = function(status, msg) {
 
 var usageInstance = PEACH_NETWORK;
 usageInstance.resetUsage();
+usageInstance.restoreUplink();
+usageInstance.usageHistory();
 usageInstance.toggleWarning();
 usageInstance.toggleCutoff();
 usageInstance.updateAlerts();